@@ -0,0 +1,418 @@
+use crate::search::GameModel;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/*
+    Beam search on any GameModel, that the bots use instead of their own copy of the algorithm.
+    It plans a sequence of moves of the player(s) that the model has move, usually a single one : the model
+    then ignores the opponents, or plays fixed moves for them.
+    - The search goes down the tree one level at a time. In Beam mode, only the [width] best nodes of a level
+      are expanded. In Bfs mode, all of them are, which finds the shortest path to a terminal state (e.g. at the end of the game).
+    - The nodes are evaluated by a function given to the search, and their eval is discounted by their depth,
+      so that the same eval is better reached sooner.
+    - The nodes are stored in an arena, allocated once for BeamOptions.max_nodes nodes. Each node keeps its state.
+    - A state already reached through another path is pruned, if the model gives the states a key (GameModel::state_key).
+    The best path leads to the node with the best eval, among all the nodes created.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchMode {
+    Beam,
+    // Exhaustive search, that stops at the end of the first level with a terminal state
+    Bfs,
+}
+
+#[derive(Clone)]
+pub struct BeamOptions {
+    pub mode: SearchMode,
+    // Number of nodes of a level that are expanded in Beam mode
+    pub width: usize,
+    // Eval of a node at depth d = evaluation * depth_discount^d. 1.0 to not discount
+    pub depth_discount: f32,
+    pub prune_duplicates: bool,
+    // The search stops at the first budget reached, or when the arena is full. No limit if None.
+    // The first level is always expanded, so that there's a move to play
+    pub max_depth: Option<usize>,
+    pub time_limit: Option<Duration>,
+    // Size of the node arena
+    pub max_nodes: usize,
+}
+
+impl Default for BeamOptions {
+    fn default() -> Self {
+        BeamOptions {
+            mode: SearchMode::Beam,
+            width: 1000,
+            depth_discount: 0.99,
+            prune_duplicates: true,
+            max_depth: None,
+            time_limit: Some(Duration::from_millis(50)),
+            max_nodes: 1_000_000,
+        }
+    }
+}
+
+struct Node<State, Move> {
+    // None for the root
+    move_: Option<Move>,
+    state: State,
+    parent: Option<usize>,
+    eval: f32,
+}
+
+pub struct Beam<M: GameModel> {
+    nodes: Vec<Node<M::State, M::Move>>,
+    options: BeamOptions,
+    // Buffer of the valid moves of the expanded node
+    moves: Vec<M::Move>,
+    // Keys of the states reached by the last search
+    seen: HashSet<u64>,
+    // Whether the last search explored the whole tree (within the depth limit), without dropping nodes
+    exhausted: bool,
+}
+
+impl<M: GameModel> Beam<M> {
+    pub fn new(options: BeamOptions) -> Self {
+        Beam {
+            nodes: Vec::with_capacity(options.max_nodes),
+            options,
+            moves: Vec::new(),
+            seen: HashSet::new(),
+            exhausted: false,
+        }
+    }
+
+    /*
+        Searches from [start_state] until a budget is reached, and returns the best path found : its moves,
+        each with the eval of the node it leads to. Empty if [start_state] is terminal.
+        [evaluate] gives the value of a state for the player(s) being planned for, the higher the better
+    */
+    pub fn search<E>(
+        &mut self,
+        model: &M,
+        start_state: &M::State,
+        evaluate: &mut E,
+    ) -> Vec<(M::Move, f32)>
+    where
+        E: FnMut(&M, &M::State) -> f32,
+    {
+        let start = Instant::now();
+        self.init(model, start_state);
+
+        let mut best_node: usize = 0;
+        let mut best_eval = f32::NEG_INFINITY;
+
+        let mut frontier: Vec<usize> = vec![0];
+        let mut next_frontier: Vec<(usize, f32)> = Vec::new();
+        let mut depth: usize = 0;
+        // Whether nodes were left out of the frontier by the width of the beam
+        let mut dropped_nodes = false;
+
+        'search: loop {
+            if frontier.is_empty()
+                || (depth > 0
+                    && self
+                        .options
+                        .max_depth
+                        .is_some_and(|max_depth| depth >= max_depth))
+            {
+                self.exhausted = !dropped_nodes;
+                break;
+            }
+
+            next_frontier.clear();
+            let discount = self.options.depth_discount.powi(depth as i32 + 1);
+            let mut terminal_found = false;
+
+            for node_idx in frontier.iter() {
+                if depth > 0
+                    && self
+                        .options
+                        .time_limit
+                        .is_some_and(|time_limit| start.elapsed() >= time_limit)
+                {
+                    break 'search;
+                }
+
+                let state = self.nodes[*node_idx].state.clone();
+                if model.is_terminal(&state) {
+                    continue;
+                }
+
+                let player = model.valid_moves(&state, &mut self.moves);
+                for move_ in self.moves.iter() {
+                    if self.nodes.len() >= self.options.max_nodes {
+                        break 'search;
+                    }
+
+                    let mut child_state = state.clone();
+                    model.update_state(&mut child_state, player, *move_);
+
+                    if self.options.prune_duplicates {
+                        if let Some(key) = model.state_key(&child_state) {
+                            if !self.seen.insert(key) {
+                                continue;
+                            }
+                        }
+                    }
+
+                    terminal_found |= model.is_terminal(&child_state);
+                    let eval = evaluate(model, &child_state) * discount;
+                    let child_idx = self.nodes.len();
+                    self.nodes.push(Node {
+                        move_: Some(*move_),
+                        state: child_state,
+                        parent: Some(*node_idx),
+                        eval,
+                    });
+
+                    if eval > best_eval {
+                        best_eval = eval;
+                        best_node = child_idx;
+                    }
+                    next_frontier.push((child_idx, eval));
+                }
+            }
+
+            depth += 1;
+
+            match self.options.mode {
+                SearchMode::Bfs if terminal_found => break,
+                SearchMode::Bfs => {}
+                SearchMode::Beam => {
+                    /* Keep the [width] best nodes of the level */
+                    let width = self.options.width;
+                    if next_frontier.len() > width {
+                        next_frontier.select_nth_unstable_by(width, |a, b| b.1.total_cmp(&a.1));
+                        next_frontier.truncate(width);
+                        dropped_nodes = true;
+                    }
+                }
+            }
+
+            frontier.clear();
+            frontier.extend(next_frontier.iter().map(|(node_idx, _)| *node_idx));
+        }
+
+        self.path_to(best_node)
+    }
+
+    // Number of nodes created by the last search
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    // Whether the last search explored the whole tree (within the depth limit) : it wasn't stopped by the time
+    // or the arena, and in Beam mode, no node was dropped by the width of the beam
+    pub fn exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    fn init(&mut self, model: &M, start_state: &M::State) {
+        self.nodes.clear();
+        self.nodes.push(Node {
+            move_: None,
+            state: start_state.clone(),
+            parent: None,
+            eval: 0.0,
+        });
+
+        self.seen.clear();
+        if let Some(key) = model.state_key(start_state) {
+            self.seen.insert(key);
+        }
+        self.exhausted = false;
+    }
+
+    fn path_to(&self, node_idx: usize) -> Vec<(M::Move, f32)> {
+        let mut path: Vec<(M::Move, f32)> = Vec::new();
+        let mut n = node_idx;
+        while let Some(parent) = self.nodes[n].parent {
+            path.push((self.nodes[n].move_.unwrap(), self.nodes[n].eval));
+            n = parent;
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::GameScore;
+
+    /*
+        Single player puzzle : starting from 1, reach [target] by adding 1 or by doubling the number.
+        The game is over once the number reaches or exceeds the target
+    */
+    struct Counter {
+        target: u32,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Op {
+        Add,
+        Double,
+    }
+
+    impl GameModel for Counter {
+        type State = u32;
+        type Move = Op;
+
+        fn valid_moves(&self, _state: &u32, moves: &mut Vec<Op>) -> u8 {
+            moves.clear();
+            moves.extend([Op::Add, Op::Double]);
+            0
+        }
+
+        fn update_state(&self, state: &mut u32, _player: u8, move_: Op) {
+            match move_ {
+                Op::Add => *state += 1,
+                Op::Double => *state *= 2,
+            }
+        }
+
+        fn is_terminal(&self, state: &u32) -> bool {
+            *state >= self.target
+        }
+
+        fn get_scores(&self, state: &u32) -> GameScore {
+            match *state == self.target {
+                true => [1.0, 0.0, 0.0, 0.0],
+                false => [0.0; 4],
+            }
+        }
+
+        fn state_key(&self, state: &u32) -> Option<u64> {
+            Some(*state as u64)
+        }
+    }
+
+    fn closeness(model: &Counter, state: &u32) -> f32 {
+        match (*state).cmp(&model.target) {
+            std::cmp::Ordering::Equal => 100.0,
+            std::cmp::Ordering::Greater => -100.0,
+            std::cmp::Ordering::Less => *state as f32 - model.target as f32,
+        }
+    }
+
+    fn play_path(path: &[(Op, f32)]) -> u32 {
+        let mut state = 1;
+        for (move_, _) in path.iter() {
+            Counter { target: 0 }.update_state(&mut state, 0, *move_);
+        }
+        state
+    }
+
+    #[test]
+    fn test_bfs_finds_the_shortest_path() {
+        let model = Counter { target: 10 };
+        let mut beam: Beam<Counter> = Beam::new(BeamOptions {
+            mode: SearchMode::Bfs,
+            time_limit: None,
+            max_nodes: 1000,
+            ..Default::default()
+        });
+
+        // 1 -> 2 -> 4 -> 5 -> 10
+        let path = beam.search(&model, &1, &mut closeness);
+        assert_eq!(path.len(), 4);
+        assert_eq!(play_path(&path), 10);
+        assert_eq!(path[3].1, 100.0 * 0.99f32.powi(4));
+        assert!(!beam.exhausted());
+    }
+
+    #[test]
+    fn test_beam_keeps_the_best_nodes_of_each_level() {
+        let model = Counter { target: 10 };
+        let mut beam: Beam<Counter> = Beam::new(BeamOptions {
+            width: 2,
+            time_limit: None,
+            max_nodes: 1000,
+            ..Default::default()
+        });
+
+        // Doubling looks better at first, and the beam is too narrow to find the shortest path
+        let path = beam.search(&model, &1, &mut closeness);
+        assert_eq!(play_path(&path), 10);
+        assert_eq!(path.len(), 5);
+        assert!(!beam.exhausted());
+
+        // A beam wide enough to keep all the nodes explores the whole tree
+        let mut beam: Beam<Counter> = Beam::new(BeamOptions {
+            width: 100,
+            time_limit: None,
+            max_nodes: 1000,
+            ..Default::default()
+        });
+        let path = beam.search(&model, &1, &mut closeness);
+        assert_eq!(path.len(), 4);
+        assert!(beam.exhausted());
+        assert!(beam.node_count() <= 1 + 2 * 2 * path.len());
+    }
+
+    #[test]
+    fn test_duplicate_states_are_pruned() {
+        let model = Counter { target: 1000 };
+        let options = BeamOptions {
+            mode: SearchMode::Bfs,
+            max_depth: Some(6),
+            time_limit: None,
+            max_nodes: 1000,
+            ..Default::default()
+        };
+
+        let mut beam: Beam<Counter> = Beam::new(BeamOptions {
+            prune_duplicates: false,
+            ..options.clone()
+        });
+        let path = beam.search(&model, &1, &mut closeness);
+        assert_eq!(beam.node_count(), 127);
+        assert_eq!(play_path(&path), 64);
+
+        let mut beam: Beam<Counter> = Beam::new(options);
+        let path = beam.search(&model, &1, &mut closeness);
+        assert!(beam.node_count() < 127, "{}", beam.node_count());
+        assert_eq!(play_path(&path), 64);
+        assert!(beam.exhausted());
+    }
+
+    #[test]
+    fn test_search_budgets() {
+        let model = Counter { target: 1000 };
+
+        let mut beam: Beam<Counter> = Beam::new(BeamOptions {
+            time_limit: None,
+            max_nodes: 10,
+            ..Default::default()
+        });
+        let path = beam.search(&model, &1, &mut closeness);
+        assert_eq!(beam.node_count(), 10);
+        assert!(!path.is_empty());
+        assert!(!beam.exhausted());
+
+        // The first level is expanded even without depth or time
+        let mut beam: Beam<Counter> = Beam::new(BeamOptions {
+            max_depth: Some(0),
+            max_nodes: 1000,
+            ..Default::default()
+        });
+        let path = beam.search(&model, &1, &mut closeness);
+        assert_eq!(path.len(), 1);
+        assert!(beam.exhausted());
+
+        let mut beam: Beam<Counter> = Beam::new(BeamOptions {
+            time_limit: Some(Duration::ZERO),
+            ..Default::default()
+        });
+        let path = beam.search(&model, &1, &mut closeness);
+        assert_eq!(path.len(), 1);
+        assert!(!beam.exhausted());
+
+        // Nothing to search from a terminal state
+        let path = beam.search(&model, &1000, &mut closeness);
+        assert!(path.is_empty());
+        assert!(beam.exhausted());
+    }
+}
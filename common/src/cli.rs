@@ -0,0 +1,476 @@
+use crate::simulator::{self, BotRegistry, PlayerPlayFunction, RunOptions, Sprt};
+use crate::tuning::{self, SpsaOptions, SpsaParam};
+use crate::{position, tournament, Game};
+use std::time::Instant;
+
+/*
+    Command-line runner shared by the game crates. Each crate's main gives the registry of its bots,
+    its scripts, and its default run options (e.g. CodinGame's time limits), then calls cli::main.
+*/
+
+const USAGE: &str = "Usage :
+  run <bot> <bot> [options]         Play the bots against each other and print the statistics
+  tournament [<bot>...] [options]   Round-robin between the bots (all bots if none is given)
+  tune <bot> <name>=<v1>,<v2>... [options]
+                                    Grid search over the bot's parameters, against its default parameters
+  spsa <bot> <name>=<start>,<min>,<max>,<step>[,int]... [options]
+                                    SPSA tuning of the bot's parameters, [--runs] being the number of iterations
+  bots                              List the bots
+  <script> [args]                   Run one of the game's scripts
+  help
+
+A bot is given by its name, optionally followed by its version and named parameters : mcts@4:exploration=0.4,time_limit_ms=50
+
+Options :
+  --runs <n>             Number of runs (default 10)
+  --permut               Play all the seatings of each run
+  --paired               With --permut, play all the seatings of a run with the same seed
+  --seed <seed>          Seed of the first run (random by default)
+  --threads <n>          Number of games played concurrently (default 1)
+  --record               Record the games in the output directory
+  --output <dir>         Output directory of the records (default output)
+  --no-stats             Don't print the statistics
+  --no-time-limits       Don't enforce the time limits of the game
+  --serialized           Only one bot computes at a time, in all the games : its search isn't slowed down by the others
+  --memory-limit <MB>    Flag the bots whose heap goes above this limit (default 768). The heap is only measured
+                         when built with --features common/count_allocations
+  --position <file>      Start every run from a hand-written position
+  --sprt <elo0>,<elo1>   Stop as soon as the SPRT (alpha = beta = 0.05) knows if the first bot is stronger";
+
+pub struct Script {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub run: fn(&[String]) -> Result<(), String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct CliOptions {
+    runs: u32,
+    permut: bool,
+    paired: bool,
+    seed: Option<u64>,
+    threads: usize,
+    record: bool,
+    output: String,
+    stats: bool,
+    time_limits: bool,
+    serialized: bool,
+    memory_limit_mb: Option<u64>,
+    position: Option<String>,
+    sprt: Option<(f64, f64)>,
+}
+
+impl Default for CliOptions {
+    fn default() -> Self {
+        CliOptions {
+            runs: 10,
+            permut: false,
+            paired: false,
+            seed: None,
+            threads: 1,
+            record: false,
+            output: "output".to_string(),
+            stats: true,
+            time_limits: true,
+            serialized: false,
+            memory_limit_mb: None,
+            position: None,
+            sprt: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Help,
+    Bots,
+    Run(Vec<String>, CliOptions),
+    Tournament(Vec<String>, CliOptions),
+    Tune(String, Vec<String>, CliOptions),
+    Spsa(String, Vec<String>, CliOptions),
+    Script(String, Vec<String>),
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or(format!("Missing value of {}", option))?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value of {} : {}", option, value))
+}
+
+// Splits the arguments of a command in its positional arguments and its options
+fn parse_options(args: &[String]) -> Result<(Vec<String>, CliOptions), String> {
+    let mut positional = Vec::new();
+    let mut options = CliOptions::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--runs" => options.runs = parse_number(arg, args.next())?,
+            "--permut" => options.permut = true,
+            "--paired" => options.paired = true,
+            "--seed" => options.seed = Some(parse_number(arg, args.next())?),
+            "--threads" => options.threads = parse_number(arg, args.next())?,
+            "--record" => options.record = true,
+            "--output" => options.output = args.next().ok_or("Missing value of --output")?.clone(),
+            "--no-stats" => options.stats = false,
+            "--no-time-limits" => options.time_limits = false,
+            "--serialized" => options.serialized = true,
+            "--memory-limit" => options.memory_limit_mb = Some(parse_number(arg, args.next())?),
+            "--position" => {
+                options.position = Some(args.next().ok_or("Missing value of --position")?.clone())
+            }
+            "--sprt" => {
+                let value = args.next().ok_or("Missing value of --sprt")?;
+                let (elo0, elo1) = value
+                    .split_once(',')
+                    .ok_or(format!("Invalid value of --sprt : {}", value))?;
+                options.sprt = Some((
+                    parse_number(arg, Some(&elo0.to_string()))?,
+                    parse_number(arg, Some(&elo1.to_string()))?,
+                ));
+            }
+            a if a.starts_with("--") => return Err(format!("Unknown option {}", a)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+    Ok((positional, options))
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let command = match args.first() {
+        None => return Ok(Command::Help),
+        Some(command) => command.as_str(),
+    };
+
+    match command {
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        "bots" => Ok(Command::Bots),
+        "run" => {
+            let (bots, options) = parse_options(&args[1..])?;
+            if bots.len() < 2 {
+                return Err("run needs at least 2 bots".to_string());
+            }
+            Ok(Command::Run(bots, options))
+        }
+        "tournament" => {
+            let (bots, options) = parse_options(&args[1..])?;
+            Ok(Command::Tournament(bots, options))
+        }
+        "tune" | "spsa" => {
+            let (mut positional, options) = parse_options(&args[1..])?;
+            if positional.len() < 2 {
+                return Err(format!("{} needs a bot and at least 1 parameter", command));
+            }
+            let bot = positional.remove(0);
+            match command {
+                "tune" => Ok(Command::Tune(bot, positional, options)),
+                _ => Ok(Command::Spsa(bot, positional, options)),
+            }
+        }
+        _ => Ok(Command::Script(command.to_string(), args[1..].to_vec())),
+    }
+}
+
+fn parse_grid(params: &[String]) -> Result<Vec<(&str, Vec<String>)>, String> {
+    params
+        .iter()
+        .map(|p| {
+            let (name, values) = p
+                .split_once('=')
+                .ok_or(format!("Invalid parameter {}", p))?;
+            Ok((name, values.split(',').map(|v| v.to_string()).collect()))
+        })
+        .collect()
+}
+
+fn parse_spsa_params(params: &[String]) -> Result<Vec<SpsaParam>, String> {
+    params
+        .iter()
+        .map(|p| {
+            let invalid = || format!("Invalid parameter {}", p);
+            let (name, values) = p.split_once('=').ok_or_else(invalid)?;
+            let values: Vec<&str> = values.split(',').collect();
+            if values.len() < 4 || values.len() > 5 || (values.len() == 5 && values[4] != "int") {
+                return Err(invalid());
+            }
+            let numbers = values[0..4]
+                .iter()
+                .map(|v| v.parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|_| invalid())?;
+            Ok(SpsaParam {
+                name: name.to_string(),
+                start: numbers[0],
+                min: numbers[1],
+                max: numbers[2],
+                step: numbers[3],
+                integer: values.len() == 5,
+            })
+        })
+        .collect()
+}
+
+fn run_options<G: Game>(
+    cli_options: &CliOptions,
+    default_options: &RunOptions,
+) -> Result<RunOptions, String> {
+    Ok(RunOptions {
+        nb_threads: cli_options.threads,
+        time_limits: match cli_options.time_limits {
+            true => default_options.time_limits,
+            false => None,
+        },
+        seed: cli_options.seed,
+        sprt: cli_options.sprt.map(|(elo0, elo1)| Sprt {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }),
+        paired: cli_options.paired,
+        serialized: cli_options.serialized,
+        memory_limit_mb: cli_options
+            .memory_limit_mb
+            .or(default_options.memory_limit_mb),
+        start: match &cli_options.position {
+            Some(position_path) => {
+                Some(position::from_position_file::<G>(position_path).map_err(|e| e.to_string())?)
+            }
+            None => None,
+        },
+    })
+}
+
+fn execute<G: Game + 'static>(
+    command: Command,
+    registry: &BotRegistry,
+    scripts: &[Script],
+    default_options: &RunOptions,
+) -> Result<(), String> {
+    let start = Instant::now();
+
+    match command {
+        Command::Help => {
+            println!("{}", USAGE);
+            if !scripts.is_empty() {
+                println!("\nScripts :");
+                for script in scripts.iter() {
+                    println!("  {:<20} {}", script.name, script.description);
+                }
+            }
+        }
+        Command::Bots => {
+            for bot in registry.bots().iter() {
+                println!(
+                    "{}@{} : {}{}",
+                    bot.name,
+                    bot.version,
+                    bot.description,
+                    match &bot.player.params {
+                        Some(params) => format!(" (default params : {})", params.join(",")),
+                        None => String::new(),
+                    }
+                );
+            }
+        }
+        Command::Run(bot_specs, cli_options) => {
+            let players = bot_specs
+                .iter()
+                .map(|spec| registry.player(spec))
+                .collect::<Result<Vec<PlayerPlayFunction>, String>>()?;
+            let record_path = match cli_options.record {
+                true => Some(cli_options.output.clone()),
+                false => None,
+            };
+            if let Some(record_path) = &record_path {
+                std::fs::create_dir_all(record_path).map_err(|e| e.to_string())?;
+            }
+
+            let options = run_options::<G>(&cli_options, default_options)?;
+            let result = match cli_options.permut {
+                true => simulator::run_permut(
+                    G::new_with_seed,
+                    &players,
+                    cli_options.runs,
+                    record_path,
+                    cli_options.stats,
+                    &options,
+                ),
+                false => simulator::run(
+                    G::new_with_seed,
+                    &players,
+                    cli_options.runs,
+                    record_path,
+                    cli_options.stats,
+                    &options,
+                ),
+            }
+            .map_err(|e| e.to_string())?;
+
+            println!("Ran games in {:?}", start.elapsed());
+            if let Some(stats) = result {
+                print!("{}", stats);
+            }
+        }
+        Command::Tournament(bot_specs, cli_options) => {
+            let bot_specs = match bot_specs.is_empty() {
+                true => registry
+                    .bots()
+                    .iter()
+                    .map(|bot| format!("{}@{}", bot.name, bot.version))
+                    .collect(),
+                false => bot_specs,
+            };
+            let players = bot_specs
+                .iter()
+                .map(|spec| registry.player(spec))
+                .collect::<Result<Vec<PlayerPlayFunction>, String>>()?;
+            let pool: Vec<(&str, PlayerPlayFunction)> = bot_specs
+                .iter()
+                .zip(players)
+                .map(|(spec, player)| (spec.as_str(), player))
+                .collect();
+            let record_path = match cli_options.record {
+                true => Some(cli_options.output.clone()),
+                false => None,
+            };
+            if let Some(record_path) = &record_path {
+                std::fs::create_dir_all(record_path).map_err(|e| e.to_string())?;
+            }
+
+            let result = tournament::run_tournament(
+                G::new_with_seed,
+                &pool,
+                cli_options.runs,
+                record_path,
+                &run_options::<G>(&cli_options, default_options)?,
+            )
+            .map_err(|e| e.to_string())?;
+
+            println!("Ran tournament in {:?}", start.elapsed());
+            print!("{}", result);
+        }
+        Command::Tune(bot_spec, params, cli_options) => {
+            let bot = registry.player(&bot_spec)?;
+            let result = tuning::grid_search(
+                G::new_with_seed,
+                &bot,
+                &bot,
+                &parse_grid(&params)?,
+                cli_options.runs,
+                &run_options::<G>(&cli_options, default_options)?,
+            )
+            .map_err(|e| e.to_string())?;
+
+            println!("Ran tuning in {:?}", start.elapsed());
+            print!("{}", result);
+        }
+        Command::Spsa(bot_spec, params, cli_options) => {
+            let bot = registry.player(&bot_spec)?;
+            let result = tuning::spsa(
+                G::new_with_seed,
+                &bot,
+                &bot,
+                &parse_spsa_params(&params)?,
+                &SpsaOptions {
+                    iterations: cli_options.runs,
+                    seed: cli_options.seed.unwrap_or(0),
+                    ..Default::default()
+                },
+                &run_options::<G>(&cli_options, default_options)?,
+            )
+            .map_err(|e| e.to_string())?;
+
+            println!("Ran tuning in {:?}", start.elapsed());
+            print!("{}", result);
+        }
+        Command::Script(name, args) => {
+            let script = scripts.iter().find(|s| s.name == name).ok_or(format!(
+                "Unknown command {}, see help for the list of commands",
+                name
+            ))?;
+            (script.run)(&args)?;
+        }
+    }
+    Ok(())
+}
+
+// Parses the command-line arguments and runs the command. Exits with an error code if it fails
+pub fn main<G: Game + 'static>(
+    registry: &BotRegistry,
+    scripts: &[Script],
+    default_options: &RunOptions,
+) {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = parse_args(&args)
+        .and_then(|command| execute::<G>(command, registry, scripts, default_options));
+
+    if let Err(e) = result {
+        eprintln!("[CLI] {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_run() {
+        let command = parse_args(&args(
+            "run mcts mcts@3:exploration=0.4 --runs 50 --permut --paired --seed 7 --record --sprt 0,10 --serialized --memory-limit 512",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            command,
+            Command::Run(
+                vec!["mcts".to_string(), "mcts@3:exploration=0.4".to_string()],
+                CliOptions {
+                    runs: 50,
+                    permut: true,
+                    paired: true,
+                    seed: Some(7),
+                    record: true,
+                    sprt: Some((0.0, 10.0)),
+                    serialized: true,
+                    memory_limit_mb: Some(512),
+                    ..Default::default()
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_args(&args("run mcts_4")).is_err());
+        assert!(parse_args(&args("run a b --runs")).is_err());
+        assert!(parse_args(&args("run a b --runs ten")).is_err());
+        assert!(parse_args(&args("run a b --unknown")).is_err());
+        assert!(parse_args(&args("tune a")).is_err());
+    }
+
+    #[test]
+    fn test_parse_script() {
+        assert_eq!(
+            parse_args(&args("state_graph graph.dot")).unwrap(),
+            Command::Script("state_graph".to_string(), vec!["graph.dot".to_string()])
+        );
+        assert_eq!(parse_args(&[]).unwrap(), Command::Help);
+    }
+
+    #[test]
+    fn test_parse_spsa_params() {
+        let params =
+            parse_spsa_params(&args("exploration=0.41,0.05,2,0.2 depth=4,1,10,1,int")).unwrap();
+        assert_eq!(params[0].max, 2.0);
+        assert!(!params[0].integer);
+        assert!(params[1].integer);
+        assert!(parse_spsa_params(&args("depth=4,1,10")).is_err());
+    }
+}
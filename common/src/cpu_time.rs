@@ -0,0 +1,93 @@
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/*
+    CPU time of the player threads. The bots stop searching after a wall-clock time, so when the games played
+    concurrently (or the players of a simultaneous turn) compete for the cores, their searches get shorter
+    than on CodinGame. The CPU time a player spent on its turn shows how much of the wall time it actually searched.
+    Only available on unix : elsewhere, the CPU time is never measured.
+*/
+
+// CPU clock of a thread, that other threads can read while it's running
+#[derive(Clone, Copy)]
+pub struct ThreadCpuClock {
+    #[cfg(unix)]
+    clock_id: libc::clockid_t,
+}
+
+impl ThreadCpuClock {
+    #[cfg(unix)]
+    pub fn of<T>(thread: &JoinHandle<T>) -> Option<ThreadCpuClock> {
+        use std::os::unix::thread::JoinHandleExt;
+
+        let mut clock_id: libc::clockid_t = 0;
+        match unsafe { libc::pthread_getcpuclockid(thread.as_pthread_t(), &mut clock_id) } {
+            0 => Some(ThreadCpuClock { clock_id }),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn of<T>(_thread: &JoinHandle<T>) -> Option<ThreadCpuClock> {
+        None
+    }
+
+    // CPU time the thread used since it started. Only meaningful while the thread is running
+    #[cfg(unix)]
+    pub fn elapsed(&self) -> Option<Duration> {
+        let mut time = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        match unsafe { libc::clock_gettime(self.clock_id, &mut time) } {
+            0 => Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32)),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn elapsed(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use std::time::Instant;
+
+    #[test]
+    fn test_thread_cpu_clock() {
+        // Both threads wait for [stop] before ending, so that their clocks can still be read
+        let (busy_sender, busy_receiver) = channel::<()>();
+        let (stop_sender, stop_receiver) = channel::<()>();
+        let (idle_stop_sender, idle_stop_receiver) = channel::<()>();
+
+        let busy_thread = std::thread::spawn(move || {
+            let start = Instant::now();
+            let mut n: u64 = 0;
+            while start.elapsed() < Duration::from_millis(50) {
+                n = n.wrapping_add(1);
+            }
+            busy_sender.send(()).unwrap();
+            stop_receiver.recv().unwrap();
+            n
+        });
+        let idle_thread = std::thread::spawn(move || idle_stop_receiver.recv().unwrap());
+
+        let busy_clock = ThreadCpuClock::of(&busy_thread).unwrap();
+        let idle_clock = ThreadCpuClock::of(&idle_thread).unwrap();
+        busy_receiver.recv().unwrap();
+
+        let busy_time = busy_clock.elapsed().unwrap();
+        let idle_time = idle_clock.elapsed().unwrap();
+        assert!(busy_time >= Duration::from_millis(10), "{:?}", busy_time);
+        assert!(idle_time < busy_time, "{:?}", idle_time);
+
+        stop_sender.send(()).unwrap();
+        idle_stop_sender.send(()).unwrap();
+        busy_thread.join().unwrap();
+        idle_thread.join().unwrap();
+    }
+}
@@ -1,7 +1,17 @@
+pub mod beam;
+pub mod cli;
+pub mod cpu_time;
 pub mod graph;
+pub mod mcts;
+pub mod memory;
+pub mod position;
+pub mod process_player;
+pub mod replay;
+pub mod search;
 pub mod simulator;
-use serde::Serialize;
-use std::fmt::Display;
+pub mod tournament;
+pub mod tuning;
+use serde::{Deserialize, Serialize};
 
 #[macro_export]
 macro_rules! assert_vec_eq {
@@ -113,59 +123,91 @@ impl<T: Copy + Clone + Default, const MAX_SIZE: usize> StackVector<T, MAX_SIZE>
 pub trait Game {
     fn new() -> Self;
 
+    // Create a game whose random initial state is fully determined by [seed]
+    fn new_with_seed(seed: u64) -> Self;
+
+    // Create a game from a hand-written position, whose format is specific to each game.
+    // Whatever the position doesn't determine (e.g. the cards left in a deck) is drawn from [seed]
+    fn new_from_position(_position: &str, _seed: u64) -> Result<Self, String>
+    where
+        Self: Sized,
+    {
+        Err("This game can't be started from a position".to_string())
+    }
+
+    // The game continues with new players, that didn't get the input of the previous turns :
+    // the input that is only sent on the first turn (e.g. the map) should be sent again
+    fn reset_players(&mut self) {}
+
     fn turn(&self) -> Option<Message>;
 
     fn play(&mut self, msg: String);
 
+    // The messages of the next turn, one for each player that plays it, or None if the game is over.
+    // The players get their message at once and answer concurrently, and their moves are resolved together
+    // by play_simultaneous. By default, a turn is played by the single player of turn()
+    fn simultaneous_turn(&self) -> Option<Vec<Message>> {
+        self.turn().map(|message| vec![message])
+    }
+
+    // Plays the moves of a turn, in the order of the messages of simultaneous_turn().
+    // Returns the moves that couldn't be parsed or weren't legal, whose players lost the game.
+    // By default, the moves are given to play(), and the game doesn't report invalid moves
+    fn play_simultaneous(&mut self, moves: Vec<String>) -> Result<(), Vec<record::InvalidMove>> {
+        for player_move in moves {
+            self.play(player_move);
+        }
+        Ok(())
+    }
+
     fn winners(&self) -> Option<Vec<WinLossTie>>;
 
+    // Final score of each player once the game is over, that tells by how much a player won. None if the game has no scores
+    fn scores(&self) -> Option<Vec<f64>> {
+        None
+    }
+
     fn get_state(&self) -> record::GameState;
 
     fn get_board_representation() -> Option<record::BoardRepresentation>;
 
     fn end_game(&mut self, players_status: Vec<WinLossTie>);
 
-    fn end_game_if_invalid_move<M: Display>(
+    // Ends the game if some moves are invalid : their players lose, and the others win.
+    // Returns the invalid moves, which play_simultaneous() returns in turn
+    fn end_game_if_invalid_moves(
         &mut self,
-        players_moves: &[Option<M>],
-        did_players_do_valid_moves: &[bool],
-    ) -> bool {
-        if did_players_do_valid_moves.contains(&false) {
-            let mut players_status: Vec<WinLossTie> = Vec::new();
-            for p_valid_move in did_players_do_valid_moves.iter() {
-                players_status.push(match *p_valid_move {
-                    true => WinLossTie::Win,
-                    false => WinLossTie::Loss,
-                })
-            }
+        player_count: usize,
+        invalid_moves: Vec<record::InvalidMove>,
+    ) -> Result<(), Vec<record::InvalidMove>> {
+        if invalid_moves.is_empty() {
+            return Ok(());
+        }
 
-            eprintln!(
-                "[GAME] The following players did invalid moves : {}",
-                players_status
-                    .iter()
-                    .enumerate()
-                    .zip(players_moves.iter())
-                    .filter(|((p_id, p_status), p_move)| **p_status == WinLossTie::Loss)
-                    .map(|((p_id, p_status), p_move)| format!(
-                        "({}, {})",
-                        p_id,
-                        match p_move {
-                            Some(m) => format!("{}", m),
-                            None => "None".to_string(),
-                        }
-                    ))
-                    .collect::<Vec<String>>()
-                    .join(", ")
-            );
+        eprintln!(
+            "[GAME] The following players did invalid moves : {}",
+            invalid_moves
+                .iter()
+                .map(|m| format!("({}, {:?} {})", m.player, m.kind, m.player_move))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
 
-            self.end_game(players_status);
-            return true;
-        }
-        false
+        self.end_game(
+            (0..player_count)
+                .map(
+                    |p| match invalid_moves.iter().any(|m| m.player as usize == p) {
+                        true => WinLossTie::Loss,
+                        false => WinLossTie::Win,
+                    },
+                )
+                .collect(),
+        );
+        Err(invalid_moves)
     }
 }
 
-#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WinLossTie {
     Win,
     Loss,
@@ -174,60 +216,223 @@ pub enum WinLossTie {
 
 pub mod record {
     use super::WinLossTie;
-    use serde::Serialize;
+    use serde::{Deserialize, Serialize};
     use std::collections::HashMap;
-
-    #[derive(Serialize)]
-    pub struct Record {
+    use std::fs::File;
+    use std::io::{BufRead, BufReader, BufWriter, Error, Lines, Write};
+    use std::path::Path;
+
+    /*
+        A record is a JSON Lines file : its first line is a RecordHeader,
+        and each following line is a GameRun, appended as soon as the run is over.
+    */
+    #[derive(Serialize, Deserialize)]
+    pub struct RecordHeader {
         pub board_representation: Option<BoardRepresentation>,
-        pub game_runs: Vec<GameRun>,
     }
 
-    #[derive(Serialize)]
+    pub struct RecordWriter {
+        writer: BufWriter<File>,
+    }
+
+    impl RecordWriter {
+        // Create the record file, and write its header
+        pub fn create(
+            path: impl AsRef<Path>,
+            board_representation: Option<BoardRepresentation>,
+        ) -> Result<RecordWriter, Error> {
+            let mut writer = RecordWriter {
+                writer: BufWriter::new(File::create(path)?),
+            };
+            writer.write_line(&RecordHeader {
+                board_representation,
+            })?;
+            Ok(writer)
+        }
+
+        // Append a finished run. It's flushed right away, so that a crash doesn't lose the previous runs
+        pub fn write_run(&mut self, game_run: &GameRun) -> Result<(), Error> {
+            self.write_line(game_run)
+        }
+
+        fn write_line(&mut self, value: &impl Serialize) -> Result<(), Error> {
+            serde_json::to_writer(&mut self.writer, value)?;
+            self.writer.write_all(b"\n")?;
+            self.writer.flush()
+        }
+    }
+
+    // Streams the runs of a record, one at a time
+    pub struct RecordReader {
+        pub header: RecordHeader,
+        lines: Lines<BufReader<File>>,
+    }
+
+    impl RecordReader {
+        pub fn open(path: impl AsRef<Path>) -> Result<RecordReader, Error> {
+            let mut lines = BufReader::new(File::open(path)?).lines();
+            let header = match lines.next() {
+                Some(line) => serde_json::from_str(&line?)?,
+                None => {
+                    return Err(Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "Record file is empty",
+                    ))
+                }
+            };
+            Ok(RecordReader { header, lines })
+        }
+    }
+
+    impl Iterator for RecordReader {
+        type Item = Result<GameRun, Error>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            // Skip blank lines, e.g. added by hand when editing a record
+            for line in self.lines.by_ref() {
+                match line {
+                    Err(e) => return Some(Err(e)),
+                    Ok(line) if line.trim().is_empty() => continue,
+                    Ok(line) => return Some(serde_json::from_str(&line).map_err(Error::from)),
+                }
+            }
+            None
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
     pub enum BoardType {
         SQUARE(u32, u32),
         REGULAR_HEXAGONE_4_SIDES_FLAT_TOP,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, Deserialize)]
     pub struct BoardRepresentation {
         pub board_type: BoardType,
         pub classes: Vec<HashMap<char, CellClass>>,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, Deserialize)]
     pub struct CellClass {
         pub text: Option<String>,
         pub text_style: Option<HashMap<String, String>>,
         pub cell_style: Option<HashMap<String, String>>,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, Deserialize)]
     pub struct GameRun {
         pub run_id: u32,
+        // Seed the game was built from
+        pub seed: u64,
+        // Where the run started, if it didn't start from the beginning of a new game (see position.rs)
+        pub start: Option<GameStart>,
         pub total_turns: u32,
         pub turns: Vec<GameTurn>,
         pub final_state: GameState,
         pub winners: Vec<WinLossTie>,
+        pub timeout: Option<Timeout>,
+        // For each seat, the index of the player that played it, in the list of players given to the simulator
+        pub seats: Vec<u32>,
+        // For each seat, the name of the bot that played it, if it came from a BotRegistry
+        pub bots: Vec<Option<String>>,
+        pub crash: Option<Crash>,
+        // Final score of each seat, if the game has scores and ended normally (without a timeout, a crash or an invalid move)
+        pub scores: Option<Vec<f64>>,
+        // Moves that ended the game, because they were invalid or late. They were played on the last turn
+        pub invalid_moves: Vec<InvalidMove>,
+        // Peak heap of each seat in bytes, if the allocations were counted
+        pub peak_memory: Option<Vec<u64>>,
+    }
+
+    // A mid-game position the players of a run started from
+    #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+    pub struct GameStart {
+        // Hand-written position the game is built from, in the format of Game::new_from_position.
+        // None if it's built as a new game
+        pub position: Option<String>,
+        // Seed the game is built from, whatever the seed of the run. None if each run uses its own seed
+        pub seed: Option<u64>,
+        // Moves played from there on each turn, before the players of the run are asked to play
+        pub moves: Vec<Vec<String>>,
     }
 
-    #[derive(Serialize)]
+    // A move that made its player lose the game
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct InvalidMove {
+        pub player: u32,
+        pub kind: InvalidMoveKind,
+        // The move, as the player sent it. Empty if the player didn't answer in time
+        pub player_move: String,
+        // The legal moves of the player, if the game can list them
+        pub legal_moves: Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum InvalidMoveKind {
+        // The move couldn't be parsed
+        ParseError,
+        // The move was parsed, but isn't allowed in the position
+        IllegalMove,
+        // The player didn't answer in time
+        Timeout,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Timeout {
+        pub player: u32,
+        pub turn: u32,
+        pub time_limit_ms: u64,
+        // None if the player didn't respond at all
+        pub response_time_ms: Option<f64>,
+    }
+
+    // A panic that ended the game. The player loses, whether it panicked itself or the game panicked on its move.
+    // If the game panicked on the moves of several players, or outside of the moves of a turn (e.g. when it was built),
+    // none of them is blamed : the game is a tie, which the run statistics leave out of the results
+    #[derive(Serialize, Deserialize)]
+    pub struct Crash {
+        // None if the crash can't be traced to a player
+        pub player: Option<u32>,
+        // None if the game panicked outside of the moves of a turn : the turns of the run weren't recorded then
+        pub turn: Option<u32>,
+        pub source: CrashSource,
+        pub message: String,
+        pub backtrace: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CrashSource {
+        Player,
+        Game,
+    }
+
+    #[derive(Serialize, Deserialize)]
     pub struct GameTurn {
         pub turn: u32,
         pub game_state: GameState,
+        // Move of each player that played the turn : a single one, unless the players played simultaneously
+        pub moves: Vec<PlayerMove>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct PlayerMove {
         pub player: u32,
         pub player_input: Vec<String>,
         pub player_state: HashMap<String, String>,
         pub player_move: String,
+        // Time the player took to send its move
+        pub response_time_ms: f64,
+        // CPU time the player used to compute its move, if it could be measured
+        pub cpu_time_ms: Option<f64>,
     }
 
-    #[derive(Serialize, Default, Clone)]
+    #[derive(Serialize, Deserialize, Default, Clone)]
     pub struct CellState {
         pub cell_state: String,
         pub tooltip: Option<String>,
     }
 
-    #[derive(Serialize, Default)]
+    #[derive(Serialize, Deserialize, Default)]
     pub struct GameState {
         pub board: Option<Vec<Vec<CellState>>>,
         pub state: HashMap<String, String>,
@@ -0,0 +1,491 @@
+use crate::search::{GameModel, GameScore};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::time::{Duration, Instant};
+
+/*
+    MCTS on any GameModel, that the bots use instead of their own copy of the algorithm.
+    - The nodes are stored in an arena, allocated once for MctsOptions.max_nodes nodes. The children of a node are contiguous.
+    - The tree is kept from one turn to the next : advance() moves its root down along the moves played since the last search.
+      The subtree of the new root is then moved to the front of the arena, and the rest of the tree is freed,
+      so that a long game doesn't fill the arena. If the moves aren't in the tree, the search starts from a new root.
+    - A new node is evaluated by a function given to the search : a random rollout (random_rollout),
+      a rollout with a smarter policy, or a heuristic evaluation of the state.
+    Each node stores the total score of the player who made its move, so that each player maximizes its own score.
+*/
+
+#[derive(Clone)]
+pub struct MctsOptions {
+    // Exploration coefficient of the selection formula
+    pub exploration: f32,
+    // Selection formula : value of a child given (parent visits, child total score, child visits, exploration)
+    pub selection: fn(u32, f32, u32, f32) -> f32,
+    // The search stops at the first budget reached, or when the arena is full. No limit if None
+    pub time_limit: Option<Duration>,
+    pub max_simulations: Option<u32>,
+    // Size of the node arena
+    pub max_nodes: usize,
+    // Seed of the random choices, e.g. to reproduce a search. Random if None
+    pub seed: Option<u64>,
+}
+
+impl Default for MctsOptions {
+    fn default() -> Self {
+        MctsOptions {
+            exploration: 0.41,
+            selection: ucb1,
+            time_limit: Some(Duration::from_millis(100)),
+            max_simulations: None,
+            max_nodes: 1_000_000,
+            seed: None,
+        }
+    }
+}
+
+pub fn ucb1(parent_visits: u32, score: f32, visits: u32, exploration: f32) -> f32 {
+    match visits {
+        0 => f32::INFINITY,
+        _ => {
+            (score / visits as f32)
+                + exploration * ((parent_visits as f32).ln() / (visits as f32)).sqrt()
+        }
+    }
+}
+
+// Plays random moves until the game is over : the usual evaluation of MCTS
+pub fn random_rollout<M: GameModel>(
+    model: &M,
+    state: &mut M::State,
+    rng: &mut StdRng,
+) -> GameScore {
+    model.random_playout(state, rng)
+}
+
+#[derive(Clone, Copy)]
+struct Node<Move> {
+    // None for the first root
+    move_: Option<Move>,
+    // Player who did the move
+    player: u8,
+
+    parent: Option<usize>,
+    child_first: usize,
+    child_count: usize,
+    // Whether its children were created. A terminal node is expanded without children
+    expanded: bool,
+
+    visits: u32,
+    score: f32,
+}
+
+impl<Move> Node<Move> {
+    fn new(move_: Option<Move>, player: u8, parent: Option<usize>) -> Self {
+        Node {
+            move_,
+            player,
+            parent,
+            child_first: 0,
+            child_count: 0,
+            expanded: false,
+            visits: 0,
+            score: 0.0,
+        }
+    }
+}
+
+// Simulation results of a move of the root
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveStats<Move> {
+    pub move_: Move,
+    pub visits: u32,
+    // Total score of the player who made the move
+    pub score: f32,
+}
+
+impl<Move> MoveStats<Move> {
+    pub fn mean_score(&self) -> f32 {
+        match self.visits {
+            0 => 0.0,
+            _ => self.score / self.visits as f32,
+        }
+    }
+}
+
+pub struct Mcts<M: GameModel> {
+    nodes: Vec<Node<M::Move>>,
+    root: usize,
+    options: MctsOptions,
+    rng: StdRng,
+    // Buffer of the valid moves of the expanded node
+    moves: Vec<M::Move>,
+    // Number of simulations of the last search
+    simulations: u32,
+    // Buffer of the new index of each node when the arena is compacted
+    new_indices: Vec<usize>,
+}
+
+impl<M: GameModel> Mcts<M> {
+    pub fn new(options: MctsOptions) -> Self {
+        let rng = match options.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let mut mcts = Mcts {
+            nodes: Vec::with_capacity(options.max_nodes),
+            root: 0,
+            options,
+            rng,
+            moves: Vec::new(),
+            simulations: 0,
+            new_indices: Vec::new(),
+        };
+        mcts.reset();
+        mcts
+    }
+
+    // Forgets the tree : the next search starts from a new root
+    pub fn reset(&mut self) {
+        self.nodes.clear();
+        self.nodes.push(Node::new(None, 0, None));
+        self.root = 0;
+    }
+
+    /*
+        Moves the root down the tree along [moves], the moves played by all players since the last search (in order),
+        and frees the nodes that aren't reachable from the new root anymore.
+        If the moves aren't in the tree (e.g. a node that was never expanded), it's reset and false is returned
+    */
+    pub fn advance(&mut self, moves: &[M::Move]) -> bool {
+        for move_ in moves.iter() {
+            let root = &self.nodes[self.root];
+            match (root.child_first..root.child_first + root.child_count)
+                .find(|c| self.nodes[*c].move_.as_ref() == Some(move_))
+            {
+                Some(child) => self.root = child,
+                None => {
+                    self.reset();
+                    return false;
+                }
+            }
+        }
+        self.compact();
+        true
+    }
+
+    /*
+        Moves the subtree of the root to the front of the arena, in the same order, and drops the other nodes.
+        A child is always created after its parent, so the root's subtree is after the root, a node is reachable
+        if its parent is, and a node only moves towards the front. A block of children stays contiguous.
+    */
+    fn compact(&mut self) {
+        if self.root == 0 {
+            return;
+        }
+
+        const UNREACHABLE: usize = usize::MAX;
+        self.new_indices.clear();
+        self.new_indices.resize(self.nodes.len(), UNREACHABLE);
+
+        let mut len = 0;
+        for i in self.root..self.nodes.len() {
+            let reachable = i == self.root
+                || self.nodes[i]
+                    .parent
+                    .is_some_and(|p| self.new_indices[p] != UNREACHABLE);
+            if reachable {
+                self.new_indices[i] = len;
+                len += 1;
+            }
+        }
+
+        for i in self.root..self.nodes.len() {
+            let new_index = self.new_indices[i];
+            if new_index == UNREACHABLE {
+                continue;
+            }
+            let mut node = self.nodes[i];
+            node.parent = match i == self.root {
+                true => None,
+                false => Some(self.new_indices[node.parent.unwrap()]),
+            };
+            if node.child_count > 0 {
+                node.child_first = self.new_indices[node.child_first];
+            }
+            self.nodes[new_index] = node;
+        }
+
+        self.nodes.truncate(len);
+        self.root = 0;
+    }
+
+    /*
+        Searches from [root_state], the state of the root (i.e. after the moves given to advance),
+        until a budget is reached. Returns the move of the root with the best mean score.
+        New nodes are evaluated by [evaluate], e.g. random_rollout, that can play on the state it's given
+    */
+    pub fn search<E>(&mut self, model: &M, root_state: &M::State, evaluate: &mut E) -> M::Move
+    where
+        E: FnMut(&M, &mut M::State, &mut StdRng) -> GameScore,
+    {
+        assert!(
+            !model.is_terminal(root_state),
+            "[MCTS] Can't search from a terminal state"
+        );
+        let start = Instant::now();
+        self.simulations = 0;
+
+        loop {
+            let mut state = root_state.clone();
+
+            // (1) Selection : go down the tree, choosing the best child each time, until a leaf
+            let mut node_idx = self.root;
+            while self.nodes[node_idx].expanded && self.nodes[node_idx].child_count > 0 {
+                node_idx = self.select_child(node_idx);
+                let node = &self.nodes[node_idx];
+                model.update_state(&mut state, node.player, node.move_.unwrap());
+            }
+
+            // (2) Expansion : a leaf that was already evaluated (or the root) gets its children, and one is chosen.
+            // The search stops if they don't fit in the arena
+            let terminal = model.is_terminal(&state);
+            if !terminal && (self.nodes[node_idx].visits > 0 || node_idx == self.root) {
+                if !self.expand(model, node_idx, &state) {
+                    break;
+                }
+                node_idx = self.nodes[node_idx].child_first;
+                let node = &self.nodes[node_idx];
+                model.update_state(&mut state, node.player, node.move_.unwrap());
+            } else if terminal {
+                self.nodes[node_idx].expanded = true;
+            }
+
+            // (3) Evaluation
+            let score = match model.is_terminal(&state) {
+                true => model.get_scores(&state),
+                false => evaluate(model, &mut state, &mut self.rng),
+            };
+
+            // (4) Backpropagation
+            self.backpropagate(node_idx, score);
+            self.simulations += 1;
+
+            if self
+                .options
+                .time_limit
+                .is_some_and(|time_limit| start.elapsed() >= time_limit)
+                || self
+                    .options
+                    .max_simulations
+                    .is_some_and(|max| self.simulations >= max)
+            {
+                break;
+            }
+        }
+
+        let root = &self.nodes[self.root];
+        let best_child = (root.child_first..root.child_first + root.child_count)
+            .filter(|c| self.nodes[*c].visits > 0)
+            .max_by(|a, b| {
+                let mean = |c: usize| self.nodes[c].score / self.nodes[c].visits as f32;
+                mean(*a).total_cmp(&mean(*b))
+            })
+            .expect("[MCTS] The root wasn't expanded, the arena is too small");
+        self.nodes[best_child].move_.unwrap()
+    }
+
+    fn select_child(&self, node_idx: usize) -> usize {
+        let node = &self.nodes[node_idx];
+        let mut best_value = -f32::INFINITY;
+        let mut best_child = node.child_first;
+        for c in node.child_first..node.child_first + node.child_count {
+            let child = &self.nodes[c];
+            let value = (self.options.selection)(
+                node.visits,
+                child.score,
+                child.visits,
+                self.options.exploration,
+            );
+            // The children are shuffled when they're created, so the first unvisited child is a random one
+            if value == f32::INFINITY {
+                return c;
+            } else if value > best_value {
+                best_value = value;
+                best_child = c;
+            }
+        }
+        best_child
+    }
+
+    // Creates the children of [node_idx], in a random order. Returns false if they don't fit in the arena
+    fn expand(&mut self, model: &M, node_idx: usize, state: &M::State) -> bool {
+        let player = model.valid_moves(state, &mut self.moves);
+        if self.nodes.len() + self.moves.len() > self.options.max_nodes {
+            return false;
+        }
+        self.moves.shuffle(&mut self.rng);
+
+        let child_first = self.nodes.len();
+        for move_ in self.moves.iter() {
+            self.nodes
+                .push(Node::new(Some(*move_), player, Some(node_idx)));
+        }
+        let node = &mut self.nodes[node_idx];
+        node.child_first = child_first;
+        node.child_count = self.moves.len();
+        node.expanded = true;
+        true
+    }
+
+    fn backpropagate(&mut self, node_idx: usize, score: GameScore) {
+        let mut node_idx = node_idx;
+        while node_idx != self.root {
+            let node = &mut self.nodes[node_idx];
+            node.visits += 1;
+            node.score += score[node.player as usize];
+            node_idx = node.parent.unwrap();
+        }
+        self.nodes[self.root].visits += 1;
+    }
+
+    // Simulation results of each move of the root
+    pub fn root_stats(&self) -> Vec<MoveStats<M::Move>> {
+        let root = &self.nodes[self.root];
+        self.nodes[root.child_first..root.child_first + root.child_count]
+            .iter()
+            .map(|node| MoveStats {
+                move_: node.move_.unwrap(),
+                visits: node.visits,
+                score: node.score,
+            })
+            .collect()
+    }
+
+    pub fn simulations(&self) -> u32 {
+        self.simulations
+    }
+
+    // Number of nodes in the arena
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::tests::{Nim, NimState};
+
+    fn options() -> MctsOptions {
+        MctsOptions {
+            time_limit: None,
+            max_simulations: Some(5000),
+            seed: Some(0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_mcts_finds_the_winning_move() {
+        let mut mcts: Mcts<Nim> = Mcts::new(options());
+
+        // The winning move leaves a multiple of 4 sticks
+        for (sticks, winning_move) in [(5, 1), (6, 2), (7, 3)] {
+            mcts.reset();
+            let state = NimState { sticks, player: 0 };
+            assert_eq!(mcts.search(&Nim, &state, &mut random_rollout), winning_move);
+            assert_eq!(mcts.simulations(), 5000);
+
+            let stats = mcts.root_stats();
+            assert_eq!(stats.len(), 3);
+            assert_eq!(stats.iter().map(|s| s.visits).sum::<u32>(), 5000);
+        }
+    }
+
+    #[test]
+    fn test_mcts_with_an_evaluation_function() {
+        let mut mcts: Mcts<Nim> = Mcts::new(MctsOptions {
+            max_simulations: Some(50),
+            ..options()
+        });
+        // Exact value of a position : the player to move loses on a multiple of 4
+        let mut evaluate = |_: &Nim, state: &mut NimState, _: &mut StdRng| {
+            let mut score = [0.0; 4];
+            let winner = match state.sticks % 4 {
+                0 => 1 - state.player,
+                _ => state.player,
+            };
+            score[winner as usize] = 1.0;
+            score
+        };
+
+        let state = NimState {
+            sticks: 23,
+            player: 0,
+        };
+        assert_eq!(mcts.search(&Nim, &state, &mut evaluate), 3);
+    }
+
+    #[test]
+    fn test_mcts_reuses_the_tree() {
+        let mut mcts: Mcts<Nim> = Mcts::new(options());
+        let mut state = NimState {
+            sticks: 10,
+            player: 0,
+        };
+        let move_ = mcts.search(&Nim, &state, &mut random_rollout);
+        assert_eq!(move_, 2);
+
+        // Both players moved : the new root keeps the simulations that went through it,
+        // and the rest of the tree is freed
+        let node_count = mcts.node_count();
+        Nim.update_state(&mut state, 0, 2);
+        Nim.update_state(&mut state, 1, 1);
+        assert!(mcts.advance(&[2, 1]));
+        let previous_visits: u32 = mcts.root_stats().iter().map(|s| s.visits).sum();
+        assert!(previous_visits > 0);
+        assert!(mcts.node_count() < node_count);
+
+        assert_eq!(mcts.search(&Nim, &state, &mut random_rollout), 3);
+        let visits: u32 = mcts.root_stats().iter().map(|s| s.visits).sum();
+        assert_eq!(visits, previous_visits + 5000);
+
+        // A move that isn't in the tree resets it
+        assert!(!mcts.advance(&[3, 3, 3, 3]));
+        assert_eq!(mcts.node_count(), 1);
+    }
+
+    #[test]
+    fn test_mcts_tree_reuse_during_a_long_game() {
+        // Without compaction, the nodes of the previous turns would fill the arena after a few turns
+        let mut mcts: Mcts<Nim> = Mcts::new(MctsOptions {
+            max_simulations: Some(500),
+            max_nodes: 5000,
+            ..options()
+        });
+        let mut state = NimState {
+            sticks: 120,
+            player: 0,
+        };
+        let mut previous_moves: Vec<u8> = Vec::new();
+
+        while !Nim.is_terminal(&state) {
+            assert!(mcts.advance(&previous_moves));
+            previous_moves.clear();
+
+            let player = state.player;
+            let move_ = mcts.search(&Nim, &state, &mut random_rollout);
+            assert_eq!(mcts.simulations(), 500);
+            Nim.update_state(&mut state, player, move_);
+            previous_moves.push(move_);
+        }
+
+        // A node that was never created : the search starts again from a new root
+        assert!(!mcts.advance(&[1, 1, 1, 1, 1, 1]));
+        let state = NimState {
+            sticks: 6,
+            player: 0,
+        };
+        assert_eq!(mcts.search(&Nim, &state, &mut random_rollout), 2);
+    }
+}
@@ -0,0 +1,149 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+
+/*
+    Heap usage of the players. CodinGame kills a bot that uses more than 768 MB, and a search tree that
+    preallocates its nodes gets there quickly.
+    Allocations are only counted if the binary uses CountingAllocator, which is opt-in :
+    build with --features common/count_allocations (e.g. cargo run --release --features common/count_allocations -- run ...).
+    Each player thread is given a HeapTracker, that counts what the thread allocates minus what it frees.
+    Allocations of the threads a player spawns itself, and of external processes, aren't counted.
+    A value allocated by a thread and freed by another (e.g. the messages sent through the channels) is counted
+    by both, which is negligible next to the heap of a bot.
+*/
+
+// Memory limit of the bots on CodinGame
+pub const CODINGAME_MEMORY_LIMIT_MB: u64 = 768;
+
+pub struct CountingAllocator;
+
+#[cfg(any(test, feature = "count_allocations"))]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// Set as soon as CountingAllocator allocates, i.e. if it's the global allocator
+static COUNTING: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // Tracker of the current thread, if it's a player thread. A const raw pointer, so that the allocator
+    // can read it without allocating
+    static TRACKER: Cell<*const HeapTracker> = const { Cell::new(ptr::null()) };
+}
+
+#[derive(Default)]
+pub struct HeapTracker {
+    current: AtomicI64,
+    peak: AtomicI64,
+}
+
+impl HeapTracker {
+    fn add(&self, size: i64) {
+        let current = self.current.fetch_add(size, Ordering::Relaxed) + size;
+        self.peak.fetch_max(current, Ordering::Relaxed);
+    }
+
+    // Highest heap usage of the thread, in bytes
+    pub fn peak_bytes(&self) -> u64 {
+        self.peak.load(Ordering::Relaxed).max(0) as u64
+    }
+}
+
+// Counts the allocations of the current thread in [tracker], until the guard is dropped
+pub struct TrackingGuard {
+    // Keeps the tracker alive while the thread points to it
+    _tracker: Arc<HeapTracker>,
+}
+
+pub fn track(tracker: Arc<HeapTracker>) -> TrackingGuard {
+    TRACKER.with(|t| t.set(Arc::as_ptr(&tracker)));
+    TrackingGuard { _tracker: tracker }
+}
+
+impl Drop for TrackingGuard {
+    fn drop(&mut self) {
+        // Stop counting before the tracker can be freed
+        let _ = TRACKER.try_with(|t| t.set(ptr::null()));
+    }
+}
+
+// Whether allocations are counted, i.e. if CountingAllocator is the global allocator
+pub fn is_counting() -> bool {
+    COUNTING.load(Ordering::Relaxed)
+}
+
+fn count(size: i64) {
+    if !COUNTING.load(Ordering::Relaxed) {
+        COUNTING.store(true, Ordering::Relaxed);
+    }
+    // The thread local is already destroyed when a thread frees memory while it's exiting
+    let _ = TRACKER.try_with(|t| {
+        let tracker = t.get();
+        if !tracker.is_null() {
+            // The guard resets the pointer before its tracker is dropped
+            unsafe { (*tracker).add(size) };
+        }
+    });
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let p = System.alloc(layout);
+        if !p.is_null() {
+            count(layout.size() as i64);
+        }
+        p
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let p = System.alloc_zeroed(layout);
+        if !p.is_null() {
+            count(layout.size() as i64);
+        }
+        p
+    }
+
+    unsafe fn dealloc(&self, p: *mut u8, layout: Layout) {
+        System.dealloc(p, layout);
+        count(-(layout.size() as i64));
+    }
+
+    unsafe fn realloc(&self, p: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_p = System.realloc(p, layout, new_size);
+        if !new_p.is_null() {
+            count(new_size as i64 - layout.size() as i64);
+        }
+        new_p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_counts_the_thread_allocations() {
+        let tracker = Arc::new(HeapTracker::default());
+        let thread_tracker = tracker.clone();
+
+        std::thread::spawn(move || {
+            let _guard = track(thread_tracker);
+            let v = vec![0u8; 10 * 1024 * 1024];
+            drop(v);
+            let _v = vec![0u8; 1024 * 1024];
+        })
+        .join()
+        .unwrap();
+
+        assert!(is_counting());
+        let peak = tracker.peak_bytes();
+        assert!(
+            (10 * 1024 * 1024..11 * 1024 * 1024).contains(&peak),
+            "{}",
+            peak
+        );
+        assert!(tracker.current.load(Ordering::Relaxed) < 1024 * 1024);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::record::{GameRun, GameStart, RecordReader};
+use crate::Game;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+/*
+    Starts of runs from a mid-game position. They're given to simulator::run (or run_permut) in RunOptions.start,
+    and the players are asked to play from that position. The start is recorded in each run, so that it can be
+    replayed or played again.
+*/
+
+// Start from the position before [turn] of a recorded run : the game is rebuilt from the run's seed (or from its start),
+// and the recorded moves of the previous turns are played again
+pub fn from_game_run(game_run: &GameRun, turn: u32) -> GameStart {
+    let mut start = game_run.start.clone().unwrap_or_default();
+    start.seed = Some(game_run.seed);
+    start.moves.extend(
+        game_run
+            .turns
+            .iter()
+            .filter(|t| t.turn < turn)
+            .map(|t| t.moves.iter().map(|m| m.player_move.clone()).collect()),
+    );
+    start
+}
+
+// Same as from_game_run, for the run [run_id] of a record file
+pub fn from_record_file(
+    path: impl AsRef<Path>,
+    run_id: u32,
+    turn: u32,
+) -> Result<GameStart, Error> {
+    for game_run in RecordReader::open(path)? {
+        let game_run = game_run?;
+        if game_run.run_id == run_id {
+            return Ok(from_game_run(&game_run, turn));
+        }
+    }
+    Err(Error::new(
+        ErrorKind::NotFound,
+        format!("Run {} is not in the record", run_id),
+    ))
+}
+
+// Start from a hand-written position file, in the format of Game::new_from_position
+pub fn from_position_file<G: Game>(path: impl AsRef<Path>) -> Result<GameStart, Error> {
+    let start = GameStart {
+        position: Some(std::fs::read_to_string(path)?),
+        ..Default::default()
+    };
+
+    // Check the position once, so that an invalid position doesn't fail every run
+    if let Err(e) = build_game::<_, G>(&G::new_with_seed, &start, 0) {
+        return Err(Error::new(ErrorKind::InvalidData, e));
+    }
+
+    Ok(start)
+}
+
+// Seed the game of a run is built from, when the run was given [seed]
+pub fn start_seed(start: Option<&GameStart>, seed: u64) -> u64 {
+    start.and_then(|start| start.seed).unwrap_or(seed)
+}
+
+// Builds the game of a run that starts at [start], [game_constr] building the game from a seed when there's no position
+pub fn build_game<GC, G>(game_constr: &GC, start: &GameStart, seed: u64) -> Result<G, String>
+where
+    GC: Fn(u64) -> G,
+    G: Game,
+{
+    let seed = start_seed(Some(start), seed);
+    let mut game = match &start.position {
+        None => game_constr(seed),
+        Some(position) => G::new_from_position(position, seed)?,
+    };
+    for (turn, moves) in start.moves.iter().enumerate() {
+        if let Err(invalid_moves) = game.play_simultaneous(moves.clone()) {
+            return Err(format!(
+                "The moves of turn {} are invalid : {:?}",
+                turn, invalid_moves
+            ));
+        }
+    }
+    game.reset_players();
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::tests::{players, HighestNumberGame};
+    use crate::simulator::{run, run_single, RunOptions};
+
+    #[test]
+    fn test_from_game_run() {
+        let mut game = HighestNumberGame::new();
+        let game_run = run_single(&mut game, &players(), 0, 7, true, &RunOptions::default())
+            .record
+            .unwrap();
+
+        // Player 0 played 10, and player 1 echoed the turn number 1
+        let start = from_game_run(&game_run, 2);
+        assert_eq!(start.seed, Some(7));
+        assert_eq!(
+            start.moves,
+            vec![vec!["10".to_string()], vec!["1".to_string()]]
+        );
+        let mut game = build_game(&HighestNumberGame::new_with_seed, &start, 0).unwrap();
+        assert_eq!(game.get_state().state["numbers"], "[10, 1]");
+
+        let game_run = run_single(&mut game, &players(), 0, 7, true, &RunOptions::default())
+            .record
+            .unwrap();
+        assert_eq!(game_run.total_turns, 2);
+        assert_eq!(game.get_state().state["numbers"], "[20, 4]");
+
+        // A start from a run that started from a position keeps the moves before that position
+        let game_run = GameRun {
+            start: Some(start),
+            ..game_run
+        };
+        let start = from_game_run(&game_run, 1);
+        assert_eq!(start.moves.len(), 3);
+        let game: HighestNumberGame =
+            build_game(&HighestNumberGame::new_with_seed, &start, 0).unwrap();
+        assert_eq!(game.get_state().state["numbers"], "[20, 1]");
+    }
+
+    #[test]
+    fn test_runs_record_their_start() {
+        let record_dir =
+            std::env::temp_dir().join(format!("position_record_test_{}", std::process::id()));
+        std::fs::create_dir_all(&record_dir).unwrap();
+
+        let mut game = HighestNumberGame::new();
+        let game_run = run_single(&mut game, &players(), 0, 7, true, &RunOptions::default())
+            .record
+            .unwrap();
+        let start = from_game_run(&game_run, 2);
+
+        run(
+            HighestNumberGame::new_with_seed,
+            &players(),
+            2,
+            Some(record_dir.to_str().unwrap().to_string()),
+            false,
+            &RunOptions {
+                seed: Some(100),
+                start: Some(start.clone()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let record_file = std::fs::read_dir(&record_dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        for game_run in RecordReader::open(&record_file).unwrap() {
+            let game_run = game_run.unwrap();
+            // The game was built from the seed of the recorded run, not from the seed of the run
+            assert_eq!(game_run.seed, 7);
+            assert_eq!(game_run.start, Some(start.clone()));
+            assert_eq!(game_run.total_turns, 2);
+            assert!(crate::replay::replay(HighestNumberGame::new_with_seed, &game_run).is_ok());
+        }
+
+        std::fs::remove_dir_all(&record_dir).unwrap();
+    }
+
+    #[test]
+    fn test_from_position_file_unsupported_game() {
+        let path = std::env::temp_dir().join(format!("position_test_{}", std::process::id()));
+        std::fs::write(&path, "10 1").unwrap();
+
+        let result = from_position_file::<HighestNumberGame>(&path);
+        assert_eq!(result.err().unwrap().kind(), ErrorKind::InvalidData);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
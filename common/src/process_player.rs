@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread;
+
+// Kills the process when the player is over, even if the player panicked
+struct ChildGuard(Child);
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/*
+    A player that runs an external executable speaking the CodinGame protocol :
+    each message line is written to the process' stdin, and its move is read from its stdout.
+    [params] contains the path of the executable, followed by its arguments.
+    The process' stderr is inherited, so its debug output is printed as is.
+*/
+pub fn play(
+    ctr_rcv: Receiver<bool>,
+    msg_rcv: Receiver<String>,
+    msg_snd: Sender<(String, Option<HashMap<String, String>>)>,
+    params: Option<Vec<String>>,
+) {
+    let command = params.expect("[PROCESS PLAYER] params should contain the executable to run");
+
+    let mut child = ChildGuard(
+        Command::new(&command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .unwrap_or_else(|e| panic!("[PROCESS PLAYER] Couldn't start {:?}: {}", command, e)),
+    );
+    let mut stdin = child.0.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.0.stdout.take().unwrap());
+
+    // The number of lines of each turn is only known by the process, so forward every line as soon as it's received.
+    // This thread stops when the simulator closes the channel, or when the process is killed
+    thread::spawn(move || {
+        for msg in msg_rcv.iter() {
+            if writeln!(stdin, "{}", msg)
+                .and_then(|_| stdin.flush())
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    while ctr_rcv.recv().unwrap() {
+        let mut player_move = String::new();
+        let read_bytes = stdout.read_line(&mut player_move).unwrap();
+        if read_bytes == 0 {
+            panic!(
+                "[PROCESS PLAYER] {:?} exited before sending its move",
+                command
+            );
+        }
+
+        if msg_snd
+            .send((player_move.trim_end().to_string(), None))
+            .is_err()
+        {
+            break;
+        }
+    }
+}
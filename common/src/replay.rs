@@ -0,0 +1,287 @@
+use crate::record::{
+    self, CellState, CrashSource, GameRun, GameState, InvalidMove, InvalidMoveKind,
+};
+use crate::simulator::{catch_panic, crash_result, player_loses};
+use crate::{position, Game, Message};
+use std::io::Error;
+use std::path::Path;
+
+// First difference between a recorded game run and its replay
+#[derive(Debug)]
+pub struct Divergence {
+    pub run_id: u32,
+    // Turn where the replay diverged, None if it diverged on the final state or winners
+    pub turn: Option<u32>,
+    pub description: String,
+}
+
+/*
+    Replays a recorded game run through the referee : the game is rebuilt from the run's seed (and from its start,
+    if it started from a mid-game position), and the recorded moves are played again. The messages sent to the players and the game state
+    before each move must match the recording, and so must the final state and the winners.
+*/
+pub fn replay<GC, G>(game_constr: GC, game_run: &GameRun) -> Result<(), Divergence>
+where
+    GC: Fn(u64) -> G,
+    G: Game,
+{
+    let diverged = |turn: Option<u32>, description: String| Divergence {
+        run_id: game_run.run_id,
+        turn,
+        description,
+    };
+
+    let build_game = || match &game_run.start {
+        None => Ok(game_constr(game_run.seed)),
+        Some(start) => position::build_game(&game_constr, start, game_run.seed),
+    };
+
+    // The game panicked outside of the moves of a turn, and the turns of the run weren't recorded :
+    // only a panic of the game when it's built can be replayed
+    if let Some(crash) = &game_run.crash {
+        if crash.source == CrashSource::Game && crash.turn.is_none() {
+            return match catch_panic(build_game) {
+                Err(_) | Ok(Err(_)) => Ok(()),
+                Ok(Ok(_)) => Err(diverged(
+                    None,
+                    "The game doesn't crash when it's built anymore, and the turns after that weren't recorded"
+                        .to_string(),
+                )),
+            };
+        }
+    }
+
+    let mut game = match build_game() {
+        Ok(game) => game,
+        Err(e) => {
+            return Err(diverged(
+                None,
+                format!("The start of the run can't be built : {}", e),
+            ))
+        }
+    };
+    let player_count = game_run.winners.len();
+    // Invalid moves reported by the game (timeouts are reported by the simulator)
+    let mut invalid_moves: Vec<InvalidMove> = Vec::new();
+
+    for recorded_turn in game_run.turns.iter() {
+        let turn = Some(recorded_turn.turn);
+
+        match game.simultaneous_turn() {
+            None => return Err(diverged(turn, "The game is already over".to_string())),
+            Some(game_messages) => {
+                let players: Vec<u32> = game_messages.iter().map(|m| m.player_id as u32).collect();
+                let recorded_players: Vec<u32> =
+                    recorded_turn.moves.iter().map(|m| m.player).collect();
+                if players != recorded_players {
+                    return Err(diverged(
+                        turn,
+                        format!(
+                            "Players {:?} play, instead of players {:?}",
+                            players, recorded_players
+                        ),
+                    ));
+                }
+                for (Message { messages, .. }, recorded_move) in
+                    game_messages.iter().zip(recorded_turn.moves.iter())
+                {
+                    if *messages != recorded_move.player_input {
+                        return Err(diverged(
+                            turn,
+                            format!(
+                                "Player {} input is {:?}, instead of {:?}",
+                                recorded_move.player, messages, recorded_move.player_input
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+
+        if let Some(difference) = state_difference(&recorded_turn.game_state, &game.get_state()) {
+            return Err(diverged(turn, difference));
+        }
+
+        let player_moves: Vec<String> = recorded_turn
+            .moves
+            .iter()
+            .map(|m| m.player_move.clone())
+            .collect();
+        match &game_run.crash {
+            // The simulator caught the game's panic on this move, and ended the game
+            Some(crash)
+                if crash.source == CrashSource::Game && crash.turn == Some(recorded_turn.turn) =>
+            {
+                if catch_panic(|| game.play_simultaneous(player_moves)).is_ok() {
+                    return Err(diverged(
+                        turn,
+                        "The move doesn't crash the game anymore".to_string(),
+                    ));
+                }
+                game.end_game(crash_result(player_count, crash.player.map(|p| p as usize)));
+            }
+            _ => {
+                if let Err(moves) = game.play_simultaneous(player_moves) {
+                    invalid_moves = moves;
+                }
+            }
+        }
+    }
+
+    let recorded_invalid_moves: Vec<InvalidMove> = game_run
+        .invalid_moves
+        .iter()
+        .filter(|m| m.kind != InvalidMoveKind::Timeout)
+        .cloned()
+        .collect();
+    if invalid_moves != recorded_invalid_moves {
+        return Err(diverged(
+            None,
+            format!(
+                "Invalid moves are {:?}, instead of {:?}",
+                invalid_moves, recorded_invalid_moves
+            ),
+        ));
+    }
+
+    // The simulator also ends the game when a player times out or crashes
+    if let Some(timeout) = &game_run.timeout {
+        game.end_game(player_loses(player_count, timeout.player as usize));
+    }
+    if let Some(crash) = &game_run.crash {
+        if let (CrashSource::Player, Some(player)) = (crash.source, crash.player) {
+            game.end_game(player_loses(player_count, player as usize));
+        }
+    }
+
+    if let Some(difference) = state_difference(&game_run.final_state, &game.get_state()) {
+        return Err(diverged(None, difference));
+    }
+    match game.winners() {
+        Some(winners) if winners == game_run.winners => Ok(()),
+        winners => Err(diverged(
+            None,
+            format!(
+                "Winners are {:?}, instead of {:?}",
+                winners, game_run.winners
+            ),
+        )),
+    }
+}
+
+// Replays all the runs of a record file, and returns the divergences found
+pub fn replay_record<GC, G>(
+    game_constr: GC,
+    path: impl AsRef<Path>,
+) -> Result<Vec<Divergence>, Error>
+where
+    GC: Fn(u64) -> G,
+    G: Game,
+{
+    let mut divergences = Vec::new();
+    for game_run in record::RecordReader::open(path)? {
+        if let Err(divergence) = replay(&game_constr, &game_run?) {
+            divergences.push(divergence);
+        }
+    }
+    Ok(divergences)
+}
+
+// Describes the first difference between the recorded and the replayed game states
+fn state_difference(recorded: &GameState, replayed: &GameState) -> Option<String> {
+    let mut keys: Vec<&String> = recorded.state.keys().chain(replayed.state.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        let recorded_value = recorded.state.get(key);
+        let replayed_value = replayed.state.get(key);
+        if recorded_value != replayed_value {
+            return Some(format!(
+                "State[{}] is {:?}, instead of {:?}",
+                key, replayed_value, recorded_value
+            ));
+        }
+    }
+
+    match (&recorded.board, &replayed.board) {
+        (None, None) => None,
+        (Some(recorded_board), Some(replayed_board)) => {
+            board_difference(recorded_board, replayed_board)
+        }
+        _ => Some("Board is recorded in only one of the states".to_string()),
+    }
+}
+
+fn board_difference(recorded: &[Vec<CellState>], replayed: &[Vec<CellState>]) -> Option<String> {
+    if recorded.len() != replayed.len() {
+        return Some(format!(
+            "Board has {} rows, instead of {}",
+            replayed.len(),
+            recorded.len()
+        ));
+    }
+    for (r, (recorded_row, replayed_row)) in recorded.iter().zip(replayed.iter()).enumerate() {
+        if recorded_row.len() != replayed_row.len() {
+            return Some(format!(
+                "Board row {} has {} cells, instead of {}",
+                r,
+                replayed_row.len(),
+                recorded_row.len()
+            ));
+        }
+        for (c, (recorded_cell, replayed_cell)) in
+            recorded_row.iter().zip(replayed_row.iter()).enumerate()
+        {
+            if recorded_cell.cell_state != replayed_cell.cell_state
+                || recorded_cell.tooltip != replayed_cell.tooltip
+            {
+                return Some(format!(
+                    "Board cell ({}, {}) is {:?}, instead of {:?}",
+                    r, c, replayed_cell.cell_state, recorded_cell.cell_state
+                ));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::tests::{players, HighestNumberGame};
+    use crate::simulator::{run_single, RunOptions};
+    use crate::WinLossTie;
+
+    fn recorded_game_run() -> GameRun {
+        let mut game = HighestNumberGame::new();
+        run_single(&mut game, &players(), 0, 0, true, &RunOptions::default())
+            .record
+            .unwrap()
+    }
+
+    #[test]
+    fn test_replay_matches_recording() {
+        let game_run = recorded_game_run();
+        assert!(replay(HighestNumberGame::new_with_seed, &game_run).is_ok());
+    }
+
+    #[test]
+    fn test_replay_reports_first_divergence() {
+        let mut game_run = recorded_game_run();
+        game_run.turns[1].moves[0].player_move = "5".to_string();
+
+        let divergence = replay(HighestNumberGame::new_with_seed, &game_run).unwrap_err();
+        assert_eq!(divergence.turn, Some(2));
+        assert!(divergence.description.starts_with("State[numbers]"));
+    }
+
+    #[test]
+    fn test_replay_reports_different_winners() {
+        let mut game_run = recorded_game_run();
+        game_run.winners = vec![WinLossTie::Tie, WinLossTie::Tie];
+
+        let divergence = replay(HighestNumberGame::new_with_seed, &game_run).unwrap_err();
+        assert_eq!(divergence.turn, None);
+        assert!(divergence.description.starts_with("Winners"));
+    }
+}
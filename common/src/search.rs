@@ -0,0 +1,130 @@
+use rand::Rng;
+
+/*
+    The model of a game that the search algorithms (MCTS, beam search...) play on. It's not the referee (see Game) :
+    it has its own State & Move types, designed to be copied and updated fast, and it's seen from one of the players.
+    A model is a value, so that it can keep the data that doesn't change during the game (e.g. the map, or
+    tables computed once at the beginning), which the bots call their Cache.
+
+    Players are identified by their index (0, 1...). A model may represent simultaneous moves as sequential ones :
+    the players move one after the other, and the state only resolves the turn once the last one moved.
+*/
+
+// Maximum number of players of a game
+pub const MAX_PLAYERS: usize = 4;
+
+// Score of each player at the end of the game, e.g. 1.0 for a win, 0.5 for a tie and 0.0 for a loss
+pub type GameScore = [f32; MAX_PLAYERS];
+
+pub trait GameModel {
+    type State: Clone;
+    type Move: Copy + PartialEq;
+
+    // Writes in [moves] (after clearing it) the valid moves of the player that moves next, and returns that player.
+    // [moves] is given by the search, so that it's allocated once
+    fn valid_moves(&self, state: &Self::State, moves: &mut Vec<Self::Move>) -> u8;
+
+    // Plays the [move_] of [player]. Assumes that it's the player's turn and that the move is valid
+    fn update_state(&self, state: &mut Self::State, player: u8, move_: Self::Move);
+
+    fn is_terminal(&self, state: &Self::State) -> bool;
+
+    // Only called on a terminal state
+    fn get_scores(&self, state: &Self::State) -> GameScore;
+
+    // A key that identifies the state, e.g. its hash, so that the same state reached through different paths
+    // can be recognized (see beam search). None if the states can't be compared
+    fn state_key(&self, _state: &Self::State) -> Option<u64> {
+        None
+    }
+
+    // A valid move of the player that moves next, chosen at random, and that player.
+    // Override it when the moves can be drawn without listing them all
+    fn random_valid_move<R: Rng>(&self, state: &Self::State, rng: &mut R) -> (u8, Self::Move) {
+        let mut moves = Vec::new();
+        let player = self.valid_moves(state, &mut moves);
+        (player, moves[rng.gen_range(0..moves.len())])
+    }
+
+    // Plays random moves from [state] until the game is over, and returns the scores
+    fn random_playout<R: Rng>(&self, state: &mut Self::State, rng: &mut R) -> GameScore {
+        while !self.is_terminal(state) {
+            let (player, move_) = self.random_valid_move(state, rng);
+            self.update_state(state, player, move_);
+        }
+        self.get_scores(state)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /*
+        Nim : the players take 1 to 3 sticks in turn, and the one that takes the last stick wins.
+        The player to move loses if the number of sticks is a multiple of 4
+    */
+    pub(crate) struct Nim;
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub(crate) struct NimState {
+        pub(crate) sticks: u8,
+        pub(crate) player: u8,
+    }
+
+    impl GameModel for Nim {
+        type State = NimState;
+        type Move = u8;
+
+        fn valid_moves(&self, state: &NimState, moves: &mut Vec<u8>) -> u8 {
+            moves.clear();
+            moves.extend((1..=3).filter(|m| *m <= state.sticks));
+            state.player
+        }
+
+        fn update_state(&self, state: &mut NimState, player: u8, move_: u8) {
+            state.sticks -= move_;
+            state.player = 1 - player;
+        }
+
+        fn is_terminal(&self, state: &NimState) -> bool {
+            state.sticks == 0
+        }
+
+        fn get_scores(&self, state: &NimState) -> GameScore {
+            // The player that took the last stick isn't the one to move
+            match state.player {
+                0 => [0.0, 1.0, 0.0, 0.0],
+                _ => [1.0, 0.0, 0.0, 0.0],
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_valid_move() {
+        let state = NimState {
+            sticks: 2,
+            player: 1,
+        };
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let (player, move_) = Nim.random_valid_move(&state, &mut rng);
+            assert_eq!(player, 1);
+            assert!(move_ == 1 || move_ == 2);
+        }
+    }
+
+    #[test]
+    fn test_random_playout() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let mut state = NimState {
+                sticks: 10,
+                player: 0,
+            };
+            let scores = Nim.random_playout(&mut state, &mut rng);
+            assert!(Nim.is_terminal(&state));
+            assert_eq!(scores[0] + scores[1], 1.0);
+        }
+    }
+}
@@ -1,26 +1,450 @@
-use crate::{record, WinLossTie};
+use crate::cpu_time::ThreadCpuClock;
+use crate::memory::{self, HeapTracker};
+use crate::{position, process_player, record, WinLossTie};
 use crate::{Game, Message};
 use itertools::Itertools;
-use std::collections::HashMap;
-use std::fs::File;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::io::Error;
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, Once, PoisonError};
 use std::thread;
 use std::thread::JoinHandle;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// When a player times out, how much longer we wait for its move, just to measure how late it was,
+// and then for its thread to stop once the game is over
+const TIMEOUT_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+const MB: u64 = 1024 * 1024;
+
+// Held by the player that is computing its move, when the runs are serialized
+static CPU_LOCK: Mutex<()> = Mutex::new(());
+
+// z-score of the 95% confidence intervals
+const CONFIDENCE_Z: f64 = 1.96;
 
 pub struct RunStatistics {
+    // (wins, losses, ties) of each player
     pub players_win_loss: Vec<(i32, i32, i32)>,
+    // Number of games each player lost by crashing (or by crashing the game with its move)
+    pub players_crashes: Vec<i32>,
+    // Number of games that crashed without a player to blame (see record::Crash).
+    // They're bugs of the referee : they're left out of the wins, losses & ties, and of the pairs
+    pub game_crashes: i32,
+    // (parse errors, illegal moves, timeouts) that made each player lose a game
+    pub players_invalid_moves: Vec<(i32, i32, i32)>,
+    // Time each player took to send its moves
+    pub players_response_times: Vec<ResponseTimes>,
+    // CPU time each player used to compute its moves. Empty if it can't be measured (see cpu_time.rs)
+    pub players_cpu_times: Vec<ResponseTimes>,
+    // Final scores of each player, if the game has scores
+    pub players_scores: Vec<FinalScores>,
+    // Highest heap usage of each player in all its games, in bytes. None if the allocations aren't counted (see memory.rs)
+    pub players_peak_memory: Option<Vec<u64>>,
+    // Number of games in which each player used more memory than RunOptions.memory_limit_mb
+    pub players_over_memory_limit: Vec<i32>,
+    // Decision of the SPRT, if RunOptions.sprt was set
+    pub sprt: Option<SprtDecision>,
+    // (pairs won, pairs split, pairs lost) of each player, if RunOptions.paired was set.
+    // A pair is won (lost) when the player won (lost) the games of all the seatings played with the same seed
+    pub players_pair_results: Option<Vec<(i32, i32, i32)>>,
 }
 
 impl RunStatistics {
     fn new(player_count: usize) -> RunStatistics {
         RunStatistics {
             players_win_loss: vec![(0, 0, 0); player_count as usize],
+            players_crashes: vec![0; player_count],
+            game_crashes: 0,
+            players_invalid_moves: vec![(0, 0, 0); player_count],
+            players_response_times: vec![Default::default(); player_count],
+            players_cpu_times: vec![Default::default(); player_count],
+            players_scores: vec![Default::default(); player_count],
+            players_peak_memory: None,
+            players_over_memory_limit: vec![0; player_count],
+            sprt: None,
+            players_pair_results: None,
+        }
+    }
+
+    // Score of [player] (a tie counts as half a win), with the bounds of its 95% confidence interval
+    pub fn score(&self, player: usize) -> (f64, f64, f64) {
+        let (n, score, variance) = score_mean_variance(self.players_win_loss[player]);
+        if n == 0.0 {
+            return (0.5, 0.0, 1.0);
+        }
+
+        let margin = CONFIDENCE_Z * (variance / n).sqrt();
+        (score, (score - margin).max(0.0), (score + margin).min(1.0))
+    }
+
+    // Elo difference between [player] and its opponents, with the margin of its 95% confidence interval.
+    // Infinite if the player won or lost all its games
+    pub fn elo(&self, player: usize) -> (f64, f64) {
+        let (score, low, high) = self.score(player);
+        // The interval of a clean sweep is a single point, at an infinite Elo
+        let margin = match score == 0.0 || score == 1.0 {
+            true => f64::INFINITY,
+            false => (elo_from_score(high) - elo_from_score(low)) / 2.0,
+        };
+        (elo_from_score(score), margin)
+    }
+}
+
+impl fmt::Display for RunStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (p, (wins, losses, ties)) in self.players_win_loss.iter().enumerate() {
+            let (score, low, high) = self.score(p);
+            let (elo, elo_margin) = self.elo(p);
+            writeln!(
+                f,
+                "Player {} : {} W / {} L / {} T | score {:.1}% [{:.1}%, {:.1}%] | Elo {:+.0} ± {:.0}",
+                p,
+                wins,
+                losses,
+                ties,
+                score * 100.0,
+                low * 100.0,
+                high * 100.0,
+                elo,
+                elo_margin
+            )?;
+        }
+        for (p, times) in self.players_response_times.iter().enumerate() {
+            if !times.turns_ms.is_empty() || !times.first_turns_ms.is_empty() {
+                writeln!(
+                    f,
+                    "Player {} response times : mean {:.1} ms, p95 {:.1} ms, p99 {:.1} ms, max {:.1} ms | first turn mean {:.1} ms, max {:.1} ms",
+                    p,
+                    times.mean_ms(),
+                    times.percentile_ms(95.0),
+                    times.percentile_ms(99.0),
+                    times.max_ms(),
+                    times.first_turn_mean_ms(),
+                    times.first_turn_max_ms()
+                )?;
+            }
+        }
+        for (p, times) in self.players_cpu_times.iter().enumerate() {
+            if !times.turns_ms.is_empty() || !times.first_turns_ms.is_empty() {
+                writeln!(
+                    f,
+                    "Player {} CPU times : mean {:.1} ms, p95 {:.1} ms, p99 {:.1} ms, max {:.1} ms | first turn mean {:.1} ms, max {:.1} ms",
+                    p,
+                    times.mean_ms(),
+                    times.percentile_ms(95.0),
+                    times.percentile_ms(99.0),
+                    times.max_ms(),
+                    times.first_turn_mean_ms(),
+                    times.first_turn_max_ms()
+                )?;
+            }
+        }
+        for (p, scores) in self.players_scores.iter().enumerate() {
+            if !scores.scores.is_empty() {
+                writeln!(
+                    f,
+                    "Player {} scores : mean {:.1} | margin mean {:+.1}, min {:+.1}, p25 {:+.1}, median {:+.1}, p75 {:+.1}, max {:+.1}",
+                    p,
+                    scores.mean(),
+                    scores.margin_mean(),
+                    scores.margin_percentile(0.0),
+                    scores.margin_percentile(25.0),
+                    scores.margin_percentile(50.0),
+                    scores.margin_percentile(75.0),
+                    scores.margin_percentile(100.0)
+                )?;
+            }
+        }
+        if let Some(peak_memory) = &self.players_peak_memory {
+            for (p, peak) in peak_memory.iter().enumerate() {
+                writeln!(
+                    f,
+                    "Player {} peak heap : {:.1} MB",
+                    p,
+                    *peak as f64 / MB as f64
+                )?;
+            }
+        }
+        for (p, over_limit) in self.players_over_memory_limit.iter().enumerate() {
+            if *over_limit > 0 {
+                writeln!(
+                    f,
+                    "Player {} : exceeded the memory limit in {} games",
+                    p, over_limit
+                )?;
+            }
+        }
+        for (p, crashes) in self.players_crashes.iter().enumerate() {
+            if *crashes > 0 {
+                writeln!(f, "Player {} : {} crashes", p, crashes)?;
+            }
+        }
+        if self.game_crashes > 0 {
+            writeln!(
+                f,
+                "Game crashes : {} (not counted in the results)",
+                self.game_crashes
+            )?;
+        }
+        for (p, (parse_errors, illegal_moves, timeouts)) in
+            self.players_invalid_moves.iter().enumerate()
+        {
+            if parse_errors + illegal_moves + timeouts > 0 {
+                writeln!(
+                    f,
+                    "Player {} : {} parse errors / {} illegal moves / {} timeouts",
+                    p, parse_errors, illegal_moves, timeouts
+                )?;
+            }
+        }
+        if let Some(pair_results) = &self.players_pair_results {
+            for (p, (won, split, lost)) in pair_results.iter().enumerate() {
+                writeln!(
+                    f,
+                    "Player {} : {} pairs won / {} split / {} lost",
+                    p, won, split, lost
+                )?;
+            }
+        }
+        if let Some(decision) = self.sprt {
+            writeln!(f, "SPRT : {:?}", decision)?;
+        }
+        Ok(())
+    }
+}
+
+// Response times of a player. The first turn has its own time budget, so it's kept apart
+#[derive(Clone, Default)]
+pub struct ResponseTimes {
+    pub turns_ms: Vec<f64>,
+    pub first_turns_ms: Vec<f64>,
+}
+
+impl ResponseTimes {
+    pub fn mean_ms(&self) -> f64 {
+        mean(&self.turns_ms)
+    }
+
+    // Response time under which [percentile]% of the turns (except the first ones) were played
+    pub fn percentile_ms(&self, percentile: f64) -> f64 {
+        percentile_of(&self.turns_ms, percentile)
+    }
+
+    pub fn max_ms(&self) -> f64 {
+        max(&self.turns_ms)
+    }
+
+    pub fn first_turn_mean_ms(&self) -> f64 {
+        mean(&self.first_turns_ms)
+    }
+
+    pub fn first_turn_max_ms(&self) -> f64 {
+        max(&self.first_turns_ms)
+    }
+
+    fn extend(&mut self, other: &ResponseTimes) {
+        self.turns_ms.extend(&other.turns_ms);
+        self.first_turns_ms.extend(&other.first_turns_ms);
+    }
+}
+
+/*
+    Final scores of a player, in the games that ended normally (not by a timeout or a crash).
+    The margin of a game is the player's score minus the best score of its opponents :
+    a player that wins more narrowly has a smaller margin, even if it wins as often.
+*/
+#[derive(Clone, Default)]
+pub struct FinalScores {
+    pub scores: Vec<f64>,
+    pub margins: Vec<f64>,
+}
+
+impl FinalScores {
+    pub fn mean(&self) -> f64 {
+        mean(&self.scores)
+    }
+
+    pub fn margin_mean(&self) -> f64 {
+        mean(&self.margins)
+    }
+
+    // Margin under which [percentile]% of the games were played (0 for the smallest margin, 100 for the largest)
+    pub fn margin_percentile(&self, percentile: f64) -> f64 {
+        percentile_of(&self.margins, percentile)
+    }
+
+    fn push(&mut self, scores: &[f64], seat: usize) {
+        let best_opponent_score = scores
+            .iter()
+            .enumerate()
+            .filter(|(s, _)| *s != seat)
+            .map(|(_, score)| *score)
+            .fold(f64::NEG_INFINITY, f64::max);
+        self.scores.push(scores[seat]);
+        self.margins.push(scores[seat] - best_opponent_score);
+    }
+}
+
+// Nearest-rank percentile, 0 if there are no values
+fn percentile_of(values: &[f64], percentile: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn mean(values: &[f64]) -> f64 {
+    match values.len() {
+        0 => 0.0,
+        n => values.iter().sum::<f64>() / n as f64,
+    }
+}
+
+fn max(values: &[f64]) -> f64 {
+    values.iter().cloned().fold(0.0, f64::max)
+}
+
+// Number of games, mean score and variance of the score of a player, given its (wins, losses, ties)
+fn score_mean_variance((wins, losses, ties): (i32, i32, i32)) -> (f64, f64, f64) {
+    let (wins, losses, ties) = (wins as f64, losses as f64, ties as f64);
+    let n = wins + losses + ties;
+    if n == 0.0 {
+        return (0.0, 0.5, 0.0);
+    }
+
+    let score = (wins + ties / 2.0) / n;
+    let variance =
+        (wins * (1.0 - score).powi(2) + losses * score.powi(2) + ties * (0.5 - score).powi(2)) / n;
+    (n, score, variance)
+}
+
+fn elo_from_score(score: f64) -> f64 {
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/*
+    Sequential Probability Ratio Test : runs stop as soon as we know, with the given error rates,
+    whether player 0 is stronger than its opponents by at least [elo1] (H1) or by at most [elo0] (H0).
+    e.g. elo0 = 0, elo1 = 10, alpha = beta = 0.05 to check that a new version of a bot is an improvement.
+*/
+#[derive(Clone, Copy)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    // Probability of accepting H1 while H0 is true
+    pub alpha: f64,
+    // Probability of accepting H0 while H1 is true
+    pub beta: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SprtDecision {
+    // H1 accepted : player 0 is stronger by at least elo1
+    Stronger,
+    // H0 accepted : player 0 is not stronger by more than elo0
+    NotStronger,
+    // All runs were played without reaching a decision
+    Inconclusive,
+}
+
+impl Sprt {
+    // Log-likelihood ratio of H1 over H0, given the (wins, losses, ties) of player 0.
+    // Uses the normal approximation of the score distribution
+    pub fn llr(&self, win_loss: (i32, i32, i32)) -> f64 {
+        let (mut n, mut score, mut variance) = score_mean_variance(win_loss);
+        if n == 0.0 {
+            return 0.0;
+        }
+        // When all results are the same, the variance is null : regularize it with an extra win and loss
+        if variance == 0.0 {
+            let (wins, losses, ties) = win_loss;
+            (n, score, variance) = score_mean_variance((wins + 1, losses + 1, ties));
+        }
+
+        let score0 = score_from_elo(self.elo0);
+        let score1 = score_from_elo(self.elo1);
+        n * (score1 - score0) * (2.0 * score - score0 - score1) / (2.0 * variance)
+    }
+
+    // Decision given the (wins, losses, ties) of player 0, or None if more runs are needed
+    pub fn decide(&self, win_loss: (i32, i32, i32)) -> Option<SprtDecision> {
+        let llr = self.llr(win_loss);
+        if llr >= ((1.0 - self.beta) / self.alpha).ln() {
+            Some(SprtDecision::Stronger)
+        } else if llr <= (self.beta / (1.0 - self.alpha)).ln() {
+            Some(SprtDecision::NotStronger)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RunOptions {
+    // Number of games that are played concurrently, each one in its own worker thread
+    pub nb_threads: usize,
+    // Time budgets that players have to respect, or they lose the game. No limit if None
+    pub time_limits: Option<TimeLimits>,
+    // Seed of the first run, the following runs use the next seeds. A random seed is chosen if None
+    pub seed: Option<u64>,
+    // Stop the runs as soon as the SPRT reaches a decision about player 0. All runs are played if None
+    pub sprt: Option<Sprt>,
+    // In run_permut, play all the seatings of a run with the same seed (e.g. the same map),
+    // so that swapping seats cancels out the luck of the initial state
+    pub paired: bool,
+    // Only one player computes its move at a time, in all the games : the players of a simultaneous turn
+    // are sent their messages one after the other, and concurrent games wait for each other.
+    // The players then search as much as on CodinGame, instead of sharing the cores
+    pub serialized: bool,
+    // Players whose heap goes above this limit are flagged (but don't lose), if the allocations are counted. No limit if None
+    pub memory_limit_mb: Option<u64>,
+    // Mid-game position the runs start from (see position.rs). They start from the beginning of a new game if None
+    pub start: Option<record::GameStart>,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        RunOptions {
+            nb_threads: 1,
+            time_limits: None,
+            seed: None,
+            sprt: None,
+            paired: false,
+            serialized: false,
+            memory_limit_mb: Some(memory::CODINGAME_MEMORY_LIMIT_MB),
+            start: None,
         }
     }
 }
 
+fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64
+}
+
+#[derive(Clone, Copy)]
+pub struct TimeLimits {
+    // Time budget of the first turn of each player
+    pub first_turn: Duration,
+    // Time budget of all the following turns
+    pub turn: Duration,
+}
+
 #[derive(Clone)]
 pub struct PlayerPlayFunction {
     pub func: &'static (dyn Fn(
@@ -32,31 +456,241 @@ pub struct PlayerPlayFunction {
 
     // Parameters that are sent to the player
     pub params: Option<Vec<String>>,
+
+    // Name of the bot (e.g. "mcts@4"), written in the records. None if it doesn't come from a BotRegistry
+    pub name: Option<String>,
+}
+
+impl PlayerPlayFunction {
+    // A player that runs an external executable, e.g. the exact binary submitted to CodinGame.
+    // [command] is the path of the executable, followed by its arguments
+    pub fn process(command: &[&str]) -> PlayerPlayFunction {
+        PlayerPlayFunction {
+            func: &process_player::play,
+            params: Some(command.iter().map(|c| c.to_string()).collect()),
+            name: command.first().map(|c| c.to_string()),
+        }
+    }
+
+    // The same player, with the named parameters ("name=value") [params] replacing or added to its own
+    pub fn with_params(&self, params: &[(String, String)]) -> PlayerPlayFunction {
+        let mut all_params = self.params.clone().unwrap_or_default();
+        for (name, value) in params.iter() {
+            let param = format!("{}={}", name, value);
+            match all_params
+                .iter_mut()
+                .find(|p| p.split_once('=').map(|(n, _)| n) == Some(name.as_str()))
+            {
+                Some(p) => *p = param,
+                None => all_params.push(param),
+            }
+        }
+
+        let params_desc = params
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<String>>()
+            .join(",");
+        PlayerPlayFunction {
+            func: self.func,
+            params: Some(all_params),
+            name: self
+                .name
+                .as_ref()
+                .map(|name| format!("{}:{}", name, params_desc)),
+        }
+    }
+}
+
+// A bot that a game crate registers, with the default parameters of its player
+#[derive(Clone)]
+pub struct Bot {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub description: &'static str,
+    pub player: PlayerPlayFunction,
+}
+
+/*
+    The bots of a game, that runners, tournaments and records refer to by name.
+    A bot is given as "name", "name@version" or "name@version:param=value,param=value" :
+    without a version, it's the last registered version of the bot.
+*/
+#[derive(Default)]
+pub struct BotRegistry {
+    bots: Vec<Bot>,
+}
+
+impl BotRegistry {
+    pub fn new() -> BotRegistry {
+        Default::default()
+    }
+
+    pub fn register(&mut self, bot: Bot) {
+        if self.get(bot.name, Some(bot.version)).is_some() {
+            panic!("Bot {}@{} is already registered", bot.name, bot.version);
+        }
+        self.bots.push(bot);
+    }
+
+    pub fn bots(&self) -> &[Bot] {
+        &self.bots
+    }
+
+    pub fn get(&self, name: &str, version: Option<&str>) -> Option<&Bot> {
+        self.bots
+            .iter()
+            .rev()
+            .find(|b| b.name == name && version.is_none_or(|v| b.version == v))
+    }
+
+    // The player of the bot [spec], named after the bot
+    pub fn player(&self, spec: &str) -> Result<PlayerPlayFunction, String> {
+        let (name_version, params) = match spec.split_once(':') {
+            Some((name_version, params)) => (name_version, Some(params)),
+            None => (spec, None),
+        };
+        let (name, version) = match name_version.split_once('@') {
+            Some((name, version)) => (name, Some(version)),
+            None => (name_version, None),
+        };
+
+        let bot = self.get(name, version).ok_or(format!(
+            "Unknown bot {}, the bots are : {}",
+            name_version,
+            self.bots
+                .iter()
+                .map(|b| format!("{}@{}", b.name, b.version))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ))?;
+
+        let player = PlayerPlayFunction {
+            name: Some(format!("{}@{}", bot.name, bot.version)),
+            ..bot.player.clone()
+        };
+        match params {
+            None => Ok(player),
+            Some(params) => {
+                let params = params
+                    .split(',')
+                    .map(|p| {
+                        p.split_once('=')
+                            .map(|(name, value)| (name.to_string(), value.to_string()))
+                            .ok_or(format!("Invalid parameter {}", p))
+                    })
+                    .collect::<Result<Vec<(String, String)>, String>>()?;
+                Ok(player.with_params(&params))
+            }
+        }
+    }
+}
+
+// Message & backtrace of a panic
+type PanicReport = (String, String);
+
+thread_local! {
+    // Last panic of the current thread, saved by the panic hook
+    static LAST_PANIC: RefCell<Option<PanicReport>> = const { RefCell::new(None) };
+}
+
+static PANIC_HOOK: Once = Once::new();
+
+// Saves the message & backtrace of each panic, so that they can be recorded after the panic is caught.
+// The panic is still printed by the previous hook
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let payload = match info.payload().downcast_ref::<&str>() {
+                Some(s) => s.to_string(),
+                None => match info.payload().downcast_ref::<String>() {
+                    Some(s) => s.clone(),
+                    None => "Box<dyn Any>".to_string(),
+                },
+            };
+            let message = match info.location() {
+                Some(location) => format!("{} at {}", payload, location),
+                None => payload,
+            };
+            let backtrace = Backtrace::force_capture().to_string();
+            LAST_PANIC.with(|p| *p.borrow_mut() = Some((message, backtrace)));
+
+            previous_hook(info);
+        }));
+    });
+}
+
+// Runs [f], returning the message & backtrace of its panic if it panicked
+pub(crate) fn catch_panic<R>(f: impl FnOnce() -> R) -> Result<R, PanicReport> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|_| {
+        LAST_PANIC
+            .with(|p| p.borrow_mut().take())
+            .unwrap_or_else(|| ("Unknown panic".to_string(), String::new()))
+    })
+}
+
+// Move & state sent by a player
+type PlayerResponse = (String, Option<HashMap<String, String>>);
+// A player response, with the time it was sent and the CPU time the player had used at that time
+type StampedResponse = (Instant, Option<Duration>, PlayerResponse);
+
+// Time between [start] and [instant], in ms
+fn elapsed_ms(start: Instant, instant: Instant) -> f64 {
+    instant.saturating_duration_since(start).as_secs_f64() * 1000.0
+}
+
+// A finished run
+pub(crate) struct RunResult {
+    winners: Vec<WinLossTie>,
+    // Seat of the player that crashed
+    crashed_player: Option<usize>,
+    // The game crashed, and the crash can't be traced to a player
+    crashed_game: bool,
+    // Response times of the player at each seat
+    response_times: Vec<ResponseTimes>,
+    // CPU times of the player at each seat
+    cpu_times: Vec<ResponseTimes>,
+    // Final score of each seat, if the game has scores and ended normally (without a timeout, a crash or an invalid move)
+    scores: Option<Vec<f64>>,
+    // Moves that made their player lose (their player being a seat, as in the record)
+    invalid_moves: Vec<record::InvalidMove>,
+    // Peak heap of each seat in bytes, if the allocations are counted
+    peak_memory: Option<Vec<u64>>,
+    pub(crate) record: Option<record::GameRun>,
 }
 
-fn run_single(
+pub(crate) fn run_single(
     game: &mut impl Game,
-    players: &Vec<PlayerPlayFunction>,
+    players: &[PlayerPlayFunction],
     game_id: u32,
+    seed: u64,
     record_game: bool,
-) -> Option<record::GameRun> {
-    println!("Run {}", game_id);
+    options: &RunOptions,
+) -> RunResult {
+    println!("Run {} (seed {})", game_id, seed);
+    install_panic_hook();
     let player_count = players.len();
-    // Vector of thread handles
-    let mut p_threads: Vec<JoinHandle<()>> = Vec::new();
+    // Vector of thread handles. A player thread returns its panic, if it panicked
+    let mut p_threads: Vec<Option<JoinHandle<Option<PanicReport>>>> = Vec::new();
     // Vector of channels to send messages to the player
     let mut sp_message_senders: Vec<Sender<String>> = Vec::new();
-    // Vector of channels to receive messages from the player
-    let mut ps_message_receivers: Vec<Receiver<(String, Option<HashMap<String, String>>)>> =
-        Vec::new();
+    // Vector of channels to receive messages from the player, with the time they were sent
+    let mut ps_message_receivers: Vec<Receiver<StampedResponse>> = Vec::new();
     // Vector of channels to send control to the player (telling it to stop or continue)
     let mut sp_control_senders: Vec<Sender<bool>> = Vec::new();
+    // Heap usage of each player thread
+    let heap_trackers: Vec<Arc<HeapTracker>> =
+        (0..player_count).map(|_| Default::default()).collect();
+    // CPU clock of each player thread, if it can be read
+    let mut cpu_clocks: Vec<Option<ThreadCpuClock>> = Vec::new();
 
     // For each player
     for pid in 0..player_count {
         // Create all channels between simulator and player
         let (sp_message_sender, sp_message_receiver) = channel();
-        let (ps_message_sender, ps_message_receiver) = channel();
+        let (ps_message_sender, ps_player_receiver) = channel::<PlayerResponse>();
+        let (ps_relay_sender, ps_message_receiver) = channel();
         let (sp_control_sender, sp_control_receiver) = channel();
 
         sp_message_senders.push(sp_message_sender);
@@ -65,203 +699,1909 @@ fn run_single(
 
         let player_func = players[pid].func;
         let player_params = players[pid].params.clone();
+        let heap_tracker = heap_trackers[pid].clone();
 
         let th = thread::Builder::new()
             .stack_size(8 * 1024 * 1024)
             .spawn(move || {
-                player_func(
-                    sp_control_receiver,
-                    sp_message_receiver,
-                    ps_message_sender,
-                    player_params,
-                )
+                let _tracking = memory::track(heap_tracker);
+                catch_panic(|| {
+                    player_func(
+                        sp_control_receiver,
+                        sp_message_receiver,
+                        ps_message_sender,
+                        player_params,
+                    )
+                })
+                .err()
             })
             .unwrap();
+        let cpu_clock = ThreadCpuClock::of(&th);
+        cpu_clocks.push(cpu_clock);
 
-        p_threads.push(th);
+        // Relay the player's messages, stamped with the time they were sent : the players of a simultaneous turn
+        // are waited for one after the other, but their response times must not depend on that order.
+        // The relay stops when the player thread is over
+        thread::spawn(move || {
+            for response in ps_player_receiver {
+                let cpu_time = cpu_clock.and_then(|clock| clock.elapsed());
+                if ps_relay_sender
+                    .send((Instant::now(), cpu_time, response))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        p_threads.push(Some(th));
     }
 
     // [RECORD] Create the game run record and fill it if record_game is True
-    let mut game_run_record = record::GameRun {
-        run_id: game_id,
-        total_turns: 0,
-        turns: Vec::new(),
-        final_state: Default::default(),
-        winners: Vec::new(),
-    };
+    let mut game_run_record = new_game_run_record(players, game_id, seed);
     /////////// [END RECORD]
 
+    // Players that already played their first turn
+    let mut played_first_turn: Vec<bool> = vec![false; player_count];
+    // Time each player took to send its moves
+    let mut response_times: Vec<ResponseTimes> = vec![Default::default(); player_count];
+    // CPU time each player used to compute its moves
+    let mut cpu_times: Vec<ResponseTimes> = vec![Default::default(); player_count];
+    // Player who didn't answer in time
+    let mut timeout: Option<record::Timeout> = None;
+    // Player who crashed, or whose move crashed the game
+    let mut crash: Option<record::Crash> = None;
+    // Moves that ended the game, because they were invalid or late
+    let mut invalid_moves: Vec<record::InvalidMove> = Vec::new();
+
     // Start the game
     let mut turn: u32 = 0;
-    loop {
-        let game_message = game.turn();
-        match game_message {
+    'turns: loop {
+        let game_messages = match game.simultaneous_turn() {
             None => {
                 break;
             }
-            Some(Message {
+            Some(game_messages) => game_messages,
+        };
+
+        // Sends its messages to a player. Returns if they were sent, when, and the CPU time the player had used then.
+        // If the player thread is over, sending fails and receiving returns Disconnected
+        let send = |Message {
+                        player_id,
+                        messages,
+                    }: &Message| {
+            let cpu_time = cpu_clocks[*player_id].and_then(|clock| clock.elapsed());
+            let start = Instant::now();
+            let sent = sp_control_senders[*player_id].send(true).is_ok()
+                && messages
+                    .iter()
+                    .all(|msg| sp_message_senders[*player_id].send(msg.to_string()).is_ok());
+            (sent, start, cpu_time)
+        };
+
+        // All the players of the turn get their message before any of them is waited for,
+        // unless the runs are serialized : then each player gets its message once it holds the CPU lock
+        let sent: Vec<(bool, Instant, Option<Duration>)> = match options.serialized {
+            true => Vec::new(),
+            false => game_messages.iter().map(send).collect(),
+        };
+
+        let mut player_moves: Vec<String> = Vec::new();
+        let mut moves_record: Vec<record::PlayerMove> = Vec::new();
+        for (
+            i,
+            Message {
                 player_id,
                 messages,
-            }) => {
-                sp_control_senders[player_id].send(true).unwrap();
+            },
+        ) in game_messages.iter().enumerate()
+        {
+            let player_id = *player_id;
+            let _cpu_lock = match options.serialized {
+                true => Some(CPU_LOCK.lock().unwrap_or_else(PoisonError::into_inner)),
+                false => None,
+            };
+            let (sent, start, cpu_start) = match options.serialized {
+                true => send(&game_messages[i]),
+                false => sent[i],
+            };
 
-                for msg in messages.iter() {
-                    sp_message_senders[player_id].send(msg.to_string()).unwrap();
-                }
-                let (player_move, player_state) = ps_message_receivers[player_id].recv().unwrap();
+            let time_limit =
+                options
+                    .time_limits
+                    .map(|time_limits| match played_first_turn[player_id] {
+                        false => time_limits.first_turn,
+                        true => time_limits.turn,
+                    });
+
+            // The time limit of each player starts when it gets its messages, whatever the players we waited for before
+            let response = match (sent, time_limit) {
+                (false, _) => Err(RecvTimeoutError::Disconnected),
+                (true, None) => ps_message_receivers[player_id]
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+                (true, Some(time_limit)) => ps_message_receivers[player_id]
+                    .recv_timeout(time_limit.saturating_sub(start.elapsed())),
+            };
+
+            let (sent_at, cpu_sent_at, (player_move, player_state)) = match response {
+                Ok(response) => response,
+                Err(RecvTimeoutError::Timeout) => {
+                    let time_limit = time_limit.unwrap();
+                    let response_time = ps_message_receivers[player_id]
+                        .recv_timeout(TIMEOUT_GRACE_PERIOD)
+                        .ok()
+                        .map(|(sent_at, _, _)| elapsed_ms(start, sent_at));
 
-                // [RECORD] Record game before playing the move
-                if record_game {
-                    let game_turn_record = record::GameTurn {
+                    eprintln!(
+                        "[SIMULATOR] Player {} timed out at turn {} : {} ms limit, responded in {}",
+                        player_id,
                         turn,
-                        game_state: game.get_state(),
+                        time_limit.as_millis(),
+                        match response_time {
+                            Some(t) => format!("{:.1} ms", t),
+                            None => "more than the grace period".to_string(),
+                        }
+                    );
+
+                    timeout = Some(record::Timeout {
+                        player: player_id as u32,
+                        turn,
+                        time_limit_ms: time_limit.as_millis() as u64,
+                        response_time_ms: response_time,
+                    });
+                    invalid_moves.push(record::InvalidMove {
                         player: player_id as u32,
-                        player_input: messages.clone(),
-                        player_state: match player_state {
-                            Some(state) => state,
-                            None => HashMap::new(),
-                        },
-                        player_move: player_move.clone(),
+                        kind: record::InvalidMoveKind::Timeout,
+                        player_move: String::new(),
+                        legal_moves: Vec::new(),
+                    });
+
+                    game.end_game(player_loses(player_count, player_id));
+                    break 'turns;
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    // The player thread is over, so joining it doesn't block
+                    let (message, backtrace) = match p_threads[player_id].take().unwrap().join() {
+                        Ok(Some(panic)) => panic,
+                        _ => (
+                            "Player stopped without sending its move".to_string(),
+                            String::new(),
+                        ),
                     };
+                    eprintln!(
+                        "[SIMULATOR] Player {} crashed at turn {} : {}",
+                        player_id, turn, message
+                    );
+
+                    crash = Some(record::Crash {
+                        player: Some(player_id as u32),
+                        turn: Some(turn),
+                        source: record::CrashSource::Player,
+                        message,
+                        backtrace,
+                    });
 
-                    game_run_record.turns.push(game_turn_record);
+                    game.end_game(player_loses(player_count, player_id));
+                    break 'turns;
+                }
+            };
+            let response_time_ms = elapsed_ms(start, sent_at);
+            match played_first_turn[player_id] {
+                false => response_times[player_id]
+                    .first_turns_ms
+                    .push(response_time_ms),
+                true => response_times[player_id].turns_ms.push(response_time_ms),
+            }
+            let cpu_time_ms = match (cpu_start, cpu_sent_at) {
+                (Some(cpu_start), Some(cpu_sent_at)) => {
+                    Some(cpu_sent_at.saturating_sub(cpu_start).as_secs_f64() * 1000.0)
+                }
+                _ => None,
+            };
+            if let Some(cpu_time_ms) = cpu_time_ms {
+                match played_first_turn[player_id] {
+                    false => cpu_times[player_id].first_turns_ms.push(cpu_time_ms),
+                    true => cpu_times[player_id].turns_ms.push(cpu_time_ms),
+                }
+            }
+            played_first_turn[player_id] = true;
+
+            // [RECORD] Record the player's move
+            if record_game {
+                moves_record.push(record::PlayerMove {
+                    player: player_id as u32,
+                    player_input: messages.clone(),
+                    player_state: player_state.unwrap_or_default(),
+                    player_move: player_move.clone(),
+                    response_time_ms,
+                    cpu_time_ms,
+                });
+            }
+            /////////// [END RECORD]
+
+            player_moves.push(player_move);
+        }
+
+        // [RECORD] Record game before playing the moves
+        if record_game {
+            game_run_record.turns.push(record::GameTurn {
+                turn,
+                game_state: game.get_state(),
+                moves: moves_record,
+            });
+        }
+        /////////// [END RECORD]
+
+        turn += 1;
+        // Invalid moves are reported by play_simultaneous, so a panic of the game is a bug of the referee.
+        // If a single player played the turn, it's its move that triggered it : the player loses.
+        // When the players played simultaneously, we can't tell whose move it was : no one is blamed, and it's a tie
+        match catch_panic(|| game.play_simultaneous(player_moves)) {
+            Ok(Ok(())) => {}
+            // The game ended, and the players of the invalid moves lost
+            Ok(Err(moves)) => invalid_moves = moves,
+            Err((message, backtrace)) => {
+                let player_id = match game_messages.as_slice() {
+                    [message] => Some(message.player_id),
+                    _ => None,
+                };
+                match player_id {
+                    Some(player_id) => eprintln!(
+                        "[SIMULATOR] Game crashed on the move of player {} at turn {} : {}",
+                        player_id,
+                        turn - 1,
+                        message
+                    ),
+                    None => eprintln!(
+                        "[SIMULATOR] Game crashed on the simultaneous moves of turn {} : {}",
+                        turn - 1,
+                        message
+                    ),
                 }
-                /////////// [END RECORD]
 
-                turn += 1;
-                game.play(player_move);
+                crash = Some(record::Crash {
+                    player: player_id.map(|p| p as u32),
+                    turn: Some(turn - 1),
+                    source: record::CrashSource::Game,
+                    message,
+                    backtrace,
+                });
+
+                game.end_game(crash_result(player_count, player_id));
+                break;
             }
         }
     }
 
-    // Now that the game is over, terminate all player threads
+    // Now that the game is over, terminate all player threads (a crashed player is already over)
     for ctrl in sp_control_senders {
-        ctrl.send(false).unwrap();
+        let _ = ctrl.send(false);
+    }
+
+    // Wait for the threads to finish. A player that timed out may still be computing its move : it stops once it reads
+    // the control message, so it's waited for during the grace period. When the runs are serialized, it holds the CPU lock
+    // meanwhile, so that it doesn't take the core of the players of the other games.
+    // A player that is still running after that (e.g. stuck in a loop) can't be stopped : its thread is left behind,
+    // and keeps using a core while the next games are played
+    let timed_out_player = timeout.as_ref().map(|t| t.player as usize);
+    for (pid, th) in p_threads.into_iter().enumerate() {
+        let Some(th) = th else {
+            continue;
+        };
+        if timed_out_player == Some(pid) {
+            let _cpu_lock = match options.serialized {
+                true => Some(CPU_LOCK.lock().unwrap_or_else(PoisonError::into_inner)),
+                false => None,
+            };
+            let deadline = Instant::now() + TIMEOUT_GRACE_PERIOD;
+            while !th.is_finished() && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(1));
+            }
+            if !th.is_finished() {
+                eprintln!(
+                    "[SIMULATOR] Player {} is still running after the game, its thread is left behind",
+                    pid
+                );
+                continue;
+            }
+        }
+        let _ = th.join();
+    }
+
+    let crashed_player = crash.as_ref().and_then(|c| c.player).map(|p| p as usize);
+    let crashed_game = crash.as_ref().is_some_and(|c| c.player.is_none());
+
+    // A player that was left behind may still be running : its peak is the one it reached so far
+    let peak_memory = match memory::is_counting() {
+        true => Some(
+            heap_trackers
+                .iter()
+                .map(|tracker| tracker.peak_bytes())
+                .collect::<Vec<u64>>(),
+        ),
+        false => None,
+    };
+    if let (Some(peak_memory), Some(limit_mb)) = (&peak_memory, options.memory_limit_mb) {
+        for (pid, peak) in peak_memory.iter().enumerate() {
+            if *peak > limit_mb * MB {
+                eprintln!(
+                    "[SIMULATOR] Player {} used {:.1} MB of heap, above the {} MB limit",
+                    pid,
+                    *peak as f64 / MB as f64,
+                    limit_mb
+                );
+            }
+        }
     }
+    // A game ended by a timeout, a crash or an invalid move has no final scores, only those of the turn it stopped at
+    let scores = match (&timeout, &crash, invalid_moves.is_empty()) {
+        (None, None, true) => game.scores(),
+        _ => None,
+    };
 
     // [RECORD] Record final result of game
     if record_game {
         game_run_record.total_turns = turn;
         game_run_record.winners = game.winners().unwrap();
         game_run_record.final_state = game.get_state();
+        game_run_record.timeout = timeout;
+        game_run_record.crash = crash;
+        game_run_record.scores = scores.clone();
+        game_run_record.invalid_moves = invalid_moves.clone();
+        game_run_record.peak_memory = peak_memory.clone();
     }
     /////////// [END RECORD]
 
-    // Wait for the threads to finish
-    for th in p_threads {
-        let _ = th.join();
+    RunResult {
+        winners: game.winners().unwrap(),
+        crashed_player,
+        crashed_game,
+        response_times,
+        cpu_times,
+        scores,
+        invalid_moves,
+        peak_memory,
+        // Return Record or None
+        record: match record_game {
+            false => None,
+            true => Some(game_run_record),
+        },
     }
+}
 
-    // Return Record or None
-    match record_game {
-        false => None,
-        true => Some(game_run_record),
+// Record of a run that didn't start yet
+fn new_game_run_record(players: &[PlayerPlayFunction], game_id: u32, seed: u64) -> record::GameRun {
+    record::GameRun {
+        run_id: game_id,
+        seed,
+        start: None,
+        total_turns: 0,
+        turns: Vec::new(),
+        final_state: Default::default(),
+        winners: Vec::new(),
+        timeout: None,
+        seats: (0..players.len() as u32).collect(),
+        bots: players.iter().map(|p| p.name.clone()).collect(),
+        crash: None,
+        scores: None,
+        invalid_moves: Vec::new(),
+        peak_memory: None,
+    }
+}
+
+// A run whose game panicked outside of play_simultaneous, e.g. when it was built or when it built the messages of a turn.
+// What the run recorded until then is lost : only the crash is recorded, and no one is blamed
+fn game_crash_result(
+    players: &[PlayerPlayFunction],
+    game_id: u32,
+    seed: u64,
+    record_game: bool,
+    (message, backtrace): PanicReport,
+) -> RunResult {
+    eprintln!(
+        "[SIMULATOR] Game crashed outside of a turn in run {} : {}",
+        game_id, message
+    );
+    let player_count = players.len();
+    let winners = crash_result(player_count, None);
+
+    RunResult {
+        winners: winners.clone(),
+        crashed_player: None,
+        crashed_game: true,
+        response_times: vec![Default::default(); player_count],
+        cpu_times: vec![Default::default(); player_count],
+        scores: None,
+        invalid_moves: Vec::new(),
+        peak_memory: None,
+        record: match record_game {
+            false => None,
+            true => Some(record::GameRun {
+                winners,
+                crash: Some(record::Crash {
+                    player: None,
+                    turn: None,
+                    source: record::CrashSource::Game,
+                    message,
+                    backtrace,
+                }),
+                ..new_game_run_record(players, game_id, seed)
+            }),
+        },
+    }
+}
+
+// Game result where [player] loses and all the others win
+pub(crate) fn player_loses(player_count: usize, player: usize) -> Vec<WinLossTie> {
+    (0..player_count)
+        .map(|p| match p == player {
+            true => WinLossTie::Loss,
+            false => WinLossTie::Win,
+        })
+        .collect()
+}
+
+// Game result when the game crashed : [player] loses if the crash was traced to its move, else it's a tie
+pub(crate) fn crash_result(player_count: usize, player: Option<usize>) -> Vec<WinLossTie> {
+    match player {
+        Some(player) => player_loses(player_count, player),
+        None => vec![WinLossTie::Tie; player_count],
     }
 }
 
 pub fn run<GC, G>(
     game_constr: GC,
-    players: &Vec<PlayerPlayFunction>,
+    players: &[PlayerPlayFunction],
     nb_runs: u32,
     record_path: Option<String>,
     return_stats: bool,
+    options: &RunOptions,
 ) -> Result<Option<RunStatistics>, Error>
 where
-    GC: Fn() -> G,
+    GC: Fn(u64) -> G + Sync,
     G: Game,
 {
-    // [RECORD] Create Record
-    let record_game = record_path.is_some();
-    let mut record = record::Record {
-        board_representation: G::get_board_representation(),
-        game_runs: Vec::new(),
-    };
-    /////////// [END RECORD]
-
-    // [STATS] Create statistics
-    let mut stats = RunStatistics::new(players.len());
-    /////////// [END STATS]
-
-    for i in 0..nb_runs {
-        let mut game = game_constr();
-        let run_record = run_single(&mut game, players, i, record_game);
-
-        // [RECORD] After run is over, record run
-        if record_game == true {
-            record.game_runs.push(run_record.unwrap());
-        }
-        /////////// [END RECORD]
-        //
-        // [STATS] After run is over, update stats
-        if return_stats == true {
-            let winners = game.winners().unwrap();
-            for (p, r) in winners.iter().enumerate() {
-                match r {
-                    WinLossTie::Win => stats.players_win_loss[p].0 += 1,
-                    WinLossTie::Loss => stats.players_win_loss[p].1 += 1,
-                    WinLossTie::Tie => stats.players_win_loss[p].2 += 1,
-                }
-            }
-        }
-        /////////// [END STATS]
-    }
-    // [RECORD] After all runs are over, print record
-    if record_game {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-
-        let record_file = format!("{}/record_{}.json", record_path.unwrap(), timestamp);
-
-        let mut _file = File::create(record_file)?;
-        serde_json::to_writer(_file, &record)?;
-    }
-    /////////// [END RECORD]
-
-    Ok(Some(stats))
+    let seating: Vec<usize> = (0..players.len()).collect();
+    run_seatings(
+        game_constr,
+        players,
+        &[seating],
+        nb_runs,
+        record_path,
+        return_stats,
+        options,
+    )
 }
 
+// Plays [nb_runs] games for each permutation of the players' seats
 pub fn run_permut<GC, G>(
     game_constr: GC,
-    players: &Vec<PlayerPlayFunction>,
+    players: &[PlayerPlayFunction],
     nb_runs: u32,
     record_path: Option<String>,
     return_stats: bool,
+    options: &RunOptions,
 ) -> Result<Option<RunStatistics>, Error>
 where
-    GC: Fn() -> G,
+    GC: Fn(u64) -> G + Sync,
     G: Game,
 {
     let player_count = players.len();
+    let seatings: Vec<Vec<usize>> = (0..player_count).permutations(player_count).collect();
 
-    let mut stats = RunStatistics::new(player_count);
+    run_seatings(
+        game_constr,
+        players,
+        &seatings,
+        nb_runs * seatings.len() as u32,
+        record_path,
+        return_stats,
+        options,
+    )
+}
 
-    let player_ids: Vec<usize> = (0..player_count).collect();
-    for perm in player_ids.iter().permutations(player_count) {
-        let mut perm_players = Vec::new();
-        for p in &perm {
-            perm_players.push(players[**p].clone());
-        }
-        let result = run(
-            &game_constr,
-            &perm_players,
-            nb_runs,
-            record_path.clone(),
-            return_stats,
-        )
-        .unwrap();
+/*
+    Plays [nb_runs] games, run i seating the players as in seatings[i % seatings.len()]
+    (seatings[k][seat] being the index of the player in [players]).
+    Seatings are interleaved so that stopping the runs early (SPRT) still gives each seating the same weight.
+    Statistics are given per player, whatever the seat it played in.
+*/
+fn run_seatings<GC, G>(
+    game_constr: GC,
+    players: &[PlayerPlayFunction],
+    seatings: &[Vec<usize>],
+    nb_runs: u32,
+    record_path: Option<String>,
+    return_stats: bool,
+    options: &RunOptions,
+) -> Result<Option<RunStatistics>, Error>
+where
+    GC: Fn(u64) -> G + Sync,
+    G: Game,
+{
+    let first_seed = options.seed.unwrap_or_else(random_seed);
 
-        // Update stats (by taking the correct player id from the permuted list)
-        if return_stats == true {
-            let stats_t = result.unwrap();
-            for i in 0..player_count {
-                let p = *perm[i];
-                stats.players_win_loss[p].0 += stats_t.players_win_loss[i].0;
-                stats.players_win_loss[p].1 += stats_t.players_win_loss[i].1;
-                stats.players_win_loss[p].2 += stats_t.players_win_loss[i].2;
-            }
+    // [RECORD] Create the record file, runs are appended to it as soon as they're over
+    let record_game = record_path.is_some();
+    let mut record_writer = match record_path {
+        None => None,
+        Some(record_path) => {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis();
+            let record_file = format!("{}/record_{}.jsonl", record_path, timestamp);
+            Some(record::RecordWriter::create(
+                record_file,
+                G::get_board_representation(),
+            )?)
         }
-    }
-    Ok(Some(stats))
+    };
+    /////////// [END RECORD]
+
+    // [STATS] Create statistics
+    let mut stats = RunStatistics::new(players.len());
+    let update_stats = return_stats || options.sprt.is_some();
+    if options.paired {
+        stats.players_pair_results = Some(vec![(0, 0, 0); players.len()]);
+    }
+    // Results of each player in the games of the current pair
+    let mut pair_winners: Vec<Vec<WinLossTie>> = vec![Vec::new(); players.len()];
+    /////////// [END STATS]
+
+    // Panics of the game are caught in the workers : their message & backtrace must be saved
+    install_panic_hook();
+
+    // Each worker picks the next run id, plays it and sends back the result.
+    // Results are then consumed in run id order, so that stats & records are the same as a sequential run
+    let next_run_id = AtomicU32::new(0);
+    // Set when the SPRT reached a decision : workers stop picking new runs
+    let stop_runs = AtomicBool::new(false);
+    let (result_sender, result_receiver) = channel::<(u32, RunResult)>();
+
+    thread::scope(|s| {
+        for _ in 0..options.nb_threads.max(1) {
+            let result_sender = result_sender.clone();
+            let game_constr = &game_constr;
+            let next_run_id = &next_run_id;
+            let stop_runs = &stop_runs;
+
+            s.spawn(move || loop {
+                if stop_runs.load(Ordering::SeqCst) {
+                    break;
+                }
+                let i = next_run_id.fetch_add(1, Ordering::SeqCst);
+                if i >= nb_runs {
+                    break;
+                }
+
+                let seating = &seatings[i as usize % seatings.len()];
+                let seated_players: Vec<PlayerPlayFunction> =
+                    seating.iter().map(|p| players[*p].clone()).collect();
+
+                let seed = match options.paired {
+                    true => first_seed.wrapping_add((i / seatings.len() as u32) as u64),
+                    false => first_seed.wrapping_add(i as u64),
+                };
+                // The seed the game is actually built from, which is the one recorded
+                let seed = position::start_seed(options.start.as_ref(), seed);
+                // run_single catches the panics of the players, and of the game when it plays their moves.
+                // The game may also panic when it's built, when it builds the messages of a turn or when the run is recorded :
+                // the run is then a game crash, and the worker goes on with the next runs
+                let mut result = catch_panic(|| {
+                    let mut game = match &options.start {
+                        None => game_constr(seed),
+                        Some(start) => position::build_game(game_constr, start, seed).unwrap(),
+                    };
+                    run_single(&mut game, &seated_players, i, seed, record_game, options)
+                })
+                .unwrap_or_else(|panic| {
+                    game_crash_result(&seated_players, i, seed, record_game, panic)
+                });
+                if let Some(run_record) = result.record.as_mut() {
+                    run_record.seats = seating.iter().map(|p| *p as u32).collect();
+                    run_record.start = options.start.clone();
+                }
+
+                // Fails only if the results are not consumed anymore, because the record couldn't be written
+                if result_sender.send((i, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(result_sender);
+
+        let mut pending_results = BTreeMap::new();
+        let mut next_result_id = 0;
+        for (i, result) in result_receiver.iter() {
+            pending_results.insert(i, result);
+
+            while let Some(result) = pending_results.remove(&next_result_id) {
+                // Once the SPRT has decided, the runs that were still being played are discarded
+                if stats.sprt.is_some() {
+                    break;
+                }
+                let seating = &seatings[next_result_id as usize % seatings.len()];
+                next_result_id += 1;
+
+                // [RECORD] After run is over, record run
+                if let Some(record_writer) = record_writer.as_mut() {
+                    let written = record_writer.write_run(&result.record.unwrap());
+                    if written.is_err() {
+                        stop_runs.store(true, Ordering::SeqCst);
+                    }
+                    written?;
+                }
+                /////////// [END RECORD]
+
+                // When paired, the stats of a pair are only complete after its last seating
+                let pair_complete = (next_result_id as usize).is_multiple_of(seatings.len());
+
+                // [STATS] After run is over, update stats (by taking the player seated at each position)
+                if update_stats {
+                    // A game crash that no player is blamed for doesn't tell which player is stronger
+                    if result.crashed_game {
+                        stats.game_crashes += 1;
+                    } else {
+                        for (seat, r) in result.winners.iter().enumerate() {
+                            let p = seating[seat];
+                            match r {
+                                WinLossTie::Win => stats.players_win_loss[p].0 += 1,
+                                WinLossTie::Loss => stats.players_win_loss[p].1 += 1,
+                                WinLossTie::Tie => stats.players_win_loss[p].2 += 1,
+                            }
+                            pair_winners[p].push(*r);
+                        }
+                    }
+                    for (seat, times) in result.response_times.iter().enumerate() {
+                        stats.players_response_times[seating[seat]].extend(times);
+                    }
+                    for (seat, times) in result.cpu_times.iter().enumerate() {
+                        stats.players_cpu_times[seating[seat]].extend(times);
+                    }
+                    if let Some(seat) = result.crashed_player {
+                        stats.players_crashes[seating[seat]] += 1;
+                    }
+                    for invalid_move in result.invalid_moves.iter() {
+                        let counts =
+                            &mut stats.players_invalid_moves[seating[invalid_move.player as usize]];
+                        match invalid_move.kind {
+                            record::InvalidMoveKind::ParseError => counts.0 += 1,
+                            record::InvalidMoveKind::IllegalMove => counts.1 += 1,
+                            record::InvalidMoveKind::Timeout => counts.2 += 1,
+                        }
+                    }
+                    if let Some(peak_memory) = &result.peak_memory {
+                        let players_peak_memory = stats
+                            .players_peak_memory
+                            .get_or_insert_with(|| vec![0; players.len()]);
+                        for (seat, peak) in peak_memory.iter().enumerate() {
+                            let p = seating[seat];
+                            players_peak_memory[p] = players_peak_memory[p].max(*peak);
+                            if options
+                                .memory_limit_mb
+                                .is_some_and(|limit_mb| *peak > limit_mb * MB)
+                            {
+                                stats.players_over_memory_limit[p] += 1;
+                            }
+                        }
+                    }
+                    if let Some(scores) = &result.scores {
+                        for (seat, p) in seating.iter().enumerate() {
+                            stats.players_scores[*p].push(scores, seat);
+                        }
+                    }
+
+                    if let Some(pair_results) = stats.players_pair_results.as_mut() {
+                        if pair_complete {
+                            for (p, winners) in pair_winners.iter_mut().enumerate() {
+                                // All the games of the pair crashed
+                                if winners.is_empty() {
+                                    continue;
+                                }
+                                if winners.iter().all(|r| *r == WinLossTie::Win) {
+                                    pair_results[p].0 += 1;
+                                } else if winners.iter().all(|r| *r == WinLossTie::Loss) {
+                                    pair_results[p].2 += 1;
+                                } else {
+                                    pair_results[p].1 += 1;
+                                }
+                                winners.clear();
+                            }
+                        }
+                    }
+                }
+                /////////// [END STATS]
+
+                if let Some(sprt) = options.sprt {
+                    if pair_complete || !options.paired {
+                        stats.sprt = sprt.decide(stats.players_win_loss[0]);
+                        if let Some(decision) = stats.sprt {
+                            println!(
+                                "[SIMULATOR] SPRT decided after {} runs : {:?}",
+                                next_result_id, decision
+                            );
+                            stop_runs.store(true, Ordering::SeqCst);
+                        }
+                    }
+                }
+            }
+        }
+        Ok::<(), Error>(())
+    })?;
+
+    if options.sprt.is_some() && stats.sprt.is_none() {
+        stats.sprt = Some(SprtDecision::Inconclusive);
+    }
+
+    Ok(Some(stats))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // A minimal 2-player game : each player plays 2 times, the player who says the largest number wins
+    pub(crate) struct HighestNumberGame {
+        turn: usize,
+        numbers: [u32; 2],
+        winners: Option<Vec<WinLossTie>>,
+    }
+
+    impl Game for HighestNumberGame {
+        fn new() -> Self {
+            HighestNumberGame {
+                turn: 0,
+                numbers: [0, 0],
+                winners: None,
+            }
+        }
+
+        fn new_with_seed(_seed: u64) -> Self {
+            HighestNumberGame::new()
+        }
+
+        fn turn(&self) -> Option<Message> {
+            match self.winners {
+                Some(_) => None,
+                None => Some(Message {
+                    player_id: self.turn % 2,
+                    messages: vec![self.turn.to_string()],
+                }),
+            }
+        }
+
+        fn play(&mut self, msg: String) {
+            self.numbers[self.turn % 2] += msg.parse::<u32>().unwrap();
+            self.turn += 1;
+
+            if self.turn == 4 {
+                self.end_game(match self.numbers[0].cmp(&self.numbers[1]) {
+                    std::cmp::Ordering::Greater => vec![WinLossTie::Win, WinLossTie::Loss],
+                    std::cmp::Ordering::Less => vec![WinLossTie::Loss, WinLossTie::Win],
+                    std::cmp::Ordering::Equal => vec![WinLossTie::Tie, WinLossTie::Tie],
+                });
+            }
+        }
+
+        fn winners(&self) -> Option<Vec<WinLossTie>> {
+            self.winners.clone()
+        }
+
+        fn scores(&self) -> Option<Vec<f64>> {
+            Some(self.numbers.iter().map(|n| *n as f64).collect())
+        }
+
+        fn get_state(&self) -> record::GameState {
+            record::GameState {
+                board: None,
+                state: HashMap::from([("numbers".to_string(), format!("{:?}", self.numbers))]),
+            }
+        }
+
+        fn get_board_representation() -> Option<record::BoardRepresentation> {
+            None
+        }
+
+        fn end_game(&mut self, players_status: Vec<WinLossTie>) {
+            self.winners = Some(players_status);
+        }
+    }
+
+    // A 2-player game of pure luck : the seed decides which seat wins, whatever the players play
+    struct LuckyGame {
+        seed: u64,
+        turn: usize,
+        winners: Option<Vec<WinLossTie>>,
+    }
+
+    impl Game for LuckyGame {
+        fn new() -> Self {
+            LuckyGame::new_with_seed(0)
+        }
+
+        fn new_with_seed(seed: u64) -> Self {
+            LuckyGame {
+                seed,
+                turn: 0,
+                winners: None,
+            }
+        }
+
+        fn turn(&self) -> Option<Message> {
+            match self.winners {
+                Some(_) => None,
+                None => Some(Message {
+                    player_id: self.turn % 2,
+                    messages: vec![self.turn.to_string()],
+                }),
+            }
+        }
+
+        fn play(&mut self, _msg: String) {
+            self.turn += 1;
+            if self.turn == 2 {
+                self.end_game(match self.seed % 2 {
+                    0 => vec![WinLossTie::Win, WinLossTie::Loss],
+                    _ => vec![WinLossTie::Loss, WinLossTie::Win],
+                });
+            }
+        }
+
+        fn winners(&self) -> Option<Vec<WinLossTie>> {
+            self.winners.clone()
+        }
+
+        fn get_state(&self) -> record::GameState {
+            Default::default()
+        }
+
+        fn get_board_representation() -> Option<record::BoardRepresentation> {
+            None
+        }
+
+        fn end_game(&mut self, players_status: Vec<WinLossTie>) {
+            self.winners = Some(players_status);
+        }
+    }
+
+    // The same game, in 2 turns where both players play simultaneously
+    struct SimultaneousNumberGame {
+        turn: usize,
+        numbers: [u32; 2],
+        winners: Option<Vec<WinLossTie>>,
+    }
+
+    impl Game for SimultaneousNumberGame {
+        fn new() -> Self {
+            SimultaneousNumberGame {
+                turn: 0,
+                numbers: [0, 0],
+                winners: None,
+            }
+        }
+
+        fn new_with_seed(_seed: u64) -> Self {
+            SimultaneousNumberGame::new()
+        }
+
+        // The players only play simultaneously
+        fn turn(&self) -> Option<Message> {
+            None
+        }
+
+        fn play(&mut self, _msg: String) {}
+
+        fn simultaneous_turn(&self) -> Option<Vec<Message>> {
+            match self.winners {
+                Some(_) => None,
+                None => Some(
+                    (0..2)
+                        .map(|player_id| Message {
+                            player_id,
+                            messages: vec![self.turn.to_string()],
+                        })
+                        .collect(),
+                ),
+            }
+        }
+
+        fn play_simultaneous(
+            &mut self,
+            moves: Vec<String>,
+        ) -> Result<(), Vec<record::InvalidMove>> {
+            let mut invalid_moves = Vec::new();
+            for (p, m) in moves.iter().enumerate() {
+                match m.parse::<u32>() {
+                    // A bug of the referee, that panics when the sum overflows
+                    Ok(n) => self.numbers[p] = self.numbers[p].checked_add(n).unwrap(),
+                    Err(_) => invalid_moves.push(record::InvalidMove {
+                        player: p as u32,
+                        kind: record::InvalidMoveKind::ParseError,
+                        player_move: m.clone(),
+                        legal_moves: Vec::new(),
+                    }),
+                }
+            }
+            self.turn += 1;
+            self.end_game_if_invalid_moves(2, invalid_moves)?;
+
+            if self.turn == 2 {
+                self.end_game(match self.numbers[0].cmp(&self.numbers[1]) {
+                    std::cmp::Ordering::Greater => vec![WinLossTie::Win, WinLossTie::Loss],
+                    std::cmp::Ordering::Less => vec![WinLossTie::Loss, WinLossTie::Win],
+                    std::cmp::Ordering::Equal => vec![WinLossTie::Tie, WinLossTie::Tie],
+                });
+            }
+            Ok(())
+        }
+
+        fn winners(&self) -> Option<Vec<WinLossTie>> {
+            self.winners.clone()
+        }
+
+        fn scores(&self) -> Option<Vec<f64>> {
+            Some(self.numbers.iter().map(|n| *n as f64).collect())
+        }
+
+        fn get_state(&self) -> record::GameState {
+            record::GameState {
+                board: None,
+                state: HashMap::from([("numbers".to_string(), format!("{:?}", self.numbers))]),
+            }
+        }
+
+        fn get_board_representation() -> Option<record::BoardRepresentation> {
+            None
+        }
+
+        fn end_game(&mut self, players_status: Vec<WinLossTie>) {
+            self.winners = Some(players_status);
+        }
+    }
+
+    // Plays the number given in params, or echoes the turn number it receives
+    fn number_player(
+        ctr_rcv: Receiver<bool>,
+        msg_rcv: Receiver<String>,
+        msg_snd: Sender<(String, Option<HashMap<String, String>>)>,
+        params: Option<Vec<String>>,
+    ) {
+        while ctr_rcv.recv().unwrap() {
+            let turn = msg_rcv.recv().unwrap();
+            let number = match &params {
+                Some(p) => p[0].clone(),
+                None => turn,
+            };
+            msg_snd.send((number, None)).unwrap();
+        }
+    }
+
+    // Always plays 100, but takes 30ms to answer
+    fn slow_player(
+        ctr_rcv: Receiver<bool>,
+        msg_rcv: Receiver<String>,
+        msg_snd: Sender<(String, Option<HashMap<String, String>>)>,
+        _params: Option<Vec<String>>,
+    ) {
+        while ctr_rcv.recv().unwrap() {
+            msg_rcv.recv().unwrap();
+            thread::sleep(Duration::from_millis(30));
+            msg_snd.send(("100".to_string(), None)).unwrap();
+        }
+    }
+
+    // Always plays 100, but computes for 30ms before answering
+    fn busy_player(
+        ctr_rcv: Receiver<bool>,
+        msg_rcv: Receiver<String>,
+        msg_snd: Sender<(String, Option<HashMap<String, String>>)>,
+        _params: Option<Vec<String>>,
+    ) {
+        while ctr_rcv.recv().unwrap() {
+            msg_rcv.recv().unwrap();
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_millis(30) {
+                std::hint::black_box(start);
+            }
+            msg_snd.send(("100".to_string(), None)).unwrap();
+        }
+    }
+
+    // Set when late_player stops
+    static LATE_PLAYER_STOPPED: AtomicBool = AtomicBool::new(false);
+
+    // Always plays 100, but takes 50ms to answer, and 50ms more to stop once the game is over
+    fn late_player(
+        ctr_rcv: Receiver<bool>,
+        msg_rcv: Receiver<String>,
+        msg_snd: Sender<(String, Option<HashMap<String, String>>)>,
+        _params: Option<Vec<String>>,
+    ) {
+        while ctr_rcv.recv().unwrap() {
+            msg_rcv.recv().unwrap();
+            thread::sleep(Duration::from_millis(50));
+            msg_snd.send(("100".to_string(), None)).unwrap();
+        }
+        thread::sleep(Duration::from_millis(50));
+        LATE_PLAYER_STOPPED.store(true, Ordering::SeqCst);
+    }
+
+    // Plays 10 on its first turn, then panics
+    fn crashing_player(
+        ctr_rcv: Receiver<bool>,
+        msg_rcv: Receiver<String>,
+        msg_snd: Sender<(String, Option<HashMap<String, String>>)>,
+        _params: Option<Vec<String>>,
+    ) {
+        let mut played = false;
+        while ctr_rcv.recv().unwrap() {
+            msg_rcv.recv().unwrap();
+            if played {
+                panic!("Crashing player gave up");
+            }
+            played = true;
+            msg_snd.send(("10".to_string(), None)).unwrap();
+        }
+    }
+
+    // Always plays 100, and keeps 64 MB of heap until the game is over
+    fn memory_hungry_player(
+        ctr_rcv: Receiver<bool>,
+        msg_rcv: Receiver<String>,
+        msg_snd: Sender<(String, Option<HashMap<String, String>>)>,
+        _params: Option<Vec<String>>,
+    ) {
+        let nodes = vec![1u8; 64 * 1024 * 1024];
+        while ctr_rcv.recv().unwrap() {
+            msg_rcv.recv().unwrap();
+            msg_snd.send((nodes[0].to_string(), None)).unwrap();
+        }
+    }
+
+    pub(crate) fn players() -> Vec<PlayerPlayFunction> {
+        vec![
+            PlayerPlayFunction {
+                func: &number_player,
+                params: Some(vec!["10".to_string()]),
+                name: None,
+            },
+            PlayerPlayFunction {
+                func: &number_player,
+                params: None,
+                name: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_run_parallel_same_stats_as_sequential() {
+        let sequential = run(
+            HighestNumberGame::new_with_seed,
+            &players(),
+            20,
+            None,
+            true,
+            &RunOptions::default(),
+        )
+        .unwrap()
+        .unwrap();
+
+        let parallel = run(
+            HighestNumberGame::new_with_seed,
+            &players(),
+            20,
+            None,
+            true,
+            &RunOptions {
+                nb_threads: 4,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(sequential.players_win_loss, vec![(20, 0, 0), (0, 20, 0)]);
+        assert_eq!(parallel.players_win_loss, sequential.players_win_loss);
+    }
+
+    #[test]
+    fn test_run_permut_parallel() {
+        let stats = run_permut(
+            HighestNumberGame::new_with_seed,
+            &players(),
+            5,
+            None,
+            true,
+            &RunOptions {
+                nb_threads: 3,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(stats.players_win_loss, vec![(10, 0, 0), (0, 10, 0)]);
+    }
+
+    #[test]
+    fn test_bot_registry() {
+        let mut registry = BotRegistry::new();
+        for (version, params) in [("1", None), ("2", Some(vec!["number=10".to_string()]))] {
+            registry.register(Bot {
+                name: "number",
+                version,
+                description: "Plays a number",
+                player: PlayerPlayFunction {
+                    func: &number_player,
+                    params,
+                    name: None,
+                },
+            });
+        }
+
+        let player = registry.player("number").unwrap();
+        assert_eq!(player.name, Some("number@2".to_string()));
+
+        let player = registry.player("number@1").unwrap();
+        assert_eq!(player.name, Some("number@1".to_string()));
+        assert_eq!(player.params, None);
+
+        // Named parameters replace the default ones
+        let player = registry.player("number:number=3,depth=2").unwrap();
+        assert_eq!(player.name, Some("number@2:number=3,depth=2".to_string()));
+        assert_eq!(
+            player.params,
+            Some(vec!["number=3".to_string(), "depth=2".to_string()])
+        );
+
+        assert!(registry.player("number@3").is_err());
+        assert!(registry.player("other").is_err());
+        assert!(registry.player("number:depth").is_err());
+    }
+
+    #[test]
+    fn test_run_reports_scores() {
+        // Player 0 always plays 10, player 1 echoes the turn numbers : 1 + 3 in seat 1, 0 + 2 in seat 0
+        let stats = run_permut(
+            HighestNumberGame::new_with_seed,
+            &players(),
+            1,
+            None,
+            true,
+            &RunOptions::default(),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(stats.players_scores[0].scores, vec![20.0, 20.0]);
+        assert_eq!(stats.players_scores[0].margins, vec![16.0, 18.0]);
+        assert_eq!(stats.players_scores[1].margin_mean(), -17.0);
+        assert_eq!(stats.players_scores[1].margin_percentile(100.0), -16.0);
+    }
+
+    #[test]
+    fn test_score_and_elo() {
+        let stats = RunStatistics {
+            players_win_loss: vec![(60, 40, 0), (40, 60, 0)],
+            players_crashes: vec![0, 0],
+            game_crashes: 0,
+            players_invalid_moves: vec![(0, 0, 0); 2],
+            players_response_times: vec![Default::default(); 2],
+            players_cpu_times: vec![Default::default(); 2],
+            players_scores: vec![Default::default(); 2],
+            players_peak_memory: None,
+            players_over_memory_limit: vec![0; 2],
+            sprt: None,
+            players_pair_results: None,
+        };
+
+        let (score, low, high) = stats.score(0);
+        assert!((score - 0.6).abs() < 1e-9);
+        assert!((low - 0.504).abs() < 1e-3);
+        assert!((high - 0.696).abs() < 1e-3);
+
+        let (elo, margin) = stats.elo(0);
+        assert!((elo - 70.4).abs() < 0.1);
+        assert!(margin > 60.0 && margin < 80.0);
+        assert!((stats.elo(1).0 + elo).abs() < 1e-9);
+
+        // Clean sweep
+        let stats = RunStatistics {
+            players_win_loss: vec![(10, 0, 0), (0, 10, 0)],
+            ..stats
+        };
+        assert_eq!(stats.elo(0), (f64::INFINITY, f64::INFINITY));
+        assert_eq!(stats.elo(1), (f64::NEG_INFINITY, f64::INFINITY));
+        assert!(stats.to_string().contains("Elo +inf ± inf"));
+
+        // All ties : a draw, with no uncertainty
+        let stats = RunStatistics {
+            players_win_loss: vec![(0, 0, 10), (0, 0, 10)],
+            ..stats
+        };
+        assert_eq!(stats.elo(0), (0.0, 0.0));
+        assert!(stats.to_string().contains("Elo +0 ± 0"));
+    }
+
+    #[test]
+    fn test_sprt_llr() {
+        let sprt = Sprt {
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+        };
+
+        assert_eq!(sprt.llr((0, 0, 0)), 0.0);
+        assert!(sprt.llr((550, 450, 0)) > 0.0);
+        assert!(sprt.llr((450, 550, 0)) < 0.0);
+        assert_eq!(sprt.decide((52, 48, 0)), None);
+        assert_eq!(sprt.decide((600, 400, 0)), Some(SprtDecision::Stronger));
+        assert_eq!(sprt.decide((400, 600, 0)), Some(SprtDecision::NotStronger));
+    }
+
+    #[test]
+    fn test_sprt_stops_runs_early() {
+        let options = RunOptions {
+            nb_threads: 4,
+            sprt: Some(Sprt {
+                elo0: 0.0,
+                elo1: 10.0,
+                alpha: 0.05,
+                beta: 0.05,
+            }),
+            ..Default::default()
+        };
+
+        let stats = run_permut(
+            HighestNumberGame::new_with_seed,
+            &players(),
+            500,
+            None,
+            false,
+            &options,
+        )
+        .unwrap()
+        .unwrap();
+        let (wins, losses, ties) = stats.players_win_loss[0];
+        assert_eq!(stats.sprt, Some(SprtDecision::Stronger));
+        assert!(wins + losses + ties < 100);
+
+        let mut reversed_players = players();
+        reversed_players.reverse();
+        let stats = run(
+            HighestNumberGame::new_with_seed,
+            &reversed_players,
+            500,
+            None,
+            false,
+            &options,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(stats.sprt, Some(SprtDecision::NotStronger));
+    }
+
+    #[test]
+    fn test_run_permut_paired_seeds_cancel_out_luck() {
+        let options = RunOptions {
+            nb_threads: 2,
+            seed: Some(0),
+            ..Default::default()
+        };
+
+        // Each seating gets its own seed : here player 0 always gets the lucky seat
+        let stats = run_permut(
+            LuckyGame::new_with_seed,
+            &players(),
+            10,
+            None,
+            true,
+            &options,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(stats.players_win_loss, vec![(20, 0, 0), (0, 20, 0)]);
+        assert!(stats.players_pair_results.is_none());
+
+        // Both seatings get the same seed, so each player wins once per pair
+        let stats = run_permut(
+            LuckyGame::new_with_seed,
+            &players(),
+            10,
+            None,
+            true,
+            &RunOptions {
+                paired: true,
+                ..options
+            },
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(stats.players_win_loss, vec![(10, 10, 0), (10, 10, 0)]);
+        assert_eq!(
+            stats.players_pair_results,
+            Some(vec![(0, 10, 0), (0, 10, 0)])
+        );
+    }
+
+    #[test]
+    fn test_response_time_percentiles() {
+        let times = ResponseTimes {
+            turns_ms: (1..=100).rev().map(|t| t as f64).collect(),
+            first_turns_ms: vec![500.0, 700.0],
+        };
+
+        assert_eq!(times.mean_ms(), 50.5);
+        assert_eq!(times.percentile_ms(95.0), 95.0);
+        assert_eq!(times.percentile_ms(99.0), 99.0);
+        assert_eq!(times.max_ms(), 100.0);
+        assert_eq!(times.first_turn_mean_ms(), 600.0);
+        assert_eq!(times.first_turn_max_ms(), 700.0);
+        assert_eq!(ResponseTimes::default().percentile_ms(95.0), 0.0);
+    }
+
+    #[test]
+    fn test_run_measures_response_times() {
+        let players = vec![
+            PlayerPlayFunction {
+                func: &number_player,
+                params: None,
+                name: None,
+            },
+            PlayerPlayFunction {
+                func: &slow_player,
+                params: None,
+                name: None,
+            },
+        ];
+
+        let stats = run(
+            HighestNumberGame::new_with_seed,
+            &players,
+            3,
+            None,
+            true,
+            &RunOptions::default(),
+        )
+        .unwrap()
+        .unwrap();
+
+        // Each player plays 2 turns per game : its first turn, and another one
+        let slow_times = &stats.players_response_times[1];
+        assert_eq!(slow_times.first_turns_ms.len(), 3);
+        assert_eq!(slow_times.turns_ms.len(), 3);
+        assert!(slow_times.first_turn_mean_ms() >= 30.0);
+        assert!(slow_times.percentile_ms(95.0) >= 30.0);
+        assert!(stats.players_response_times[0].max_ms() < slow_times.max_ms());
+    }
+
+    #[test]
+    fn test_players_play_simultaneously() {
+        let slow_player = PlayerPlayFunction {
+            func: &slow_player,
+            params: None,
+            name: None,
+        };
+        let players = vec![slow_player.clone(), slow_player];
+        // Each player takes 30ms : they only respect the limit if they think at the same time
+        let options = RunOptions {
+            time_limits: Some(TimeLimits {
+                first_turn: Duration::from_millis(50),
+                turn: Duration::from_millis(50),
+            }),
+            ..Default::default()
+        };
+
+        let mut game = SimultaneousNumberGame::new();
+        let record = run_single(&mut game, &players, 0, 0, true, &options)
+            .record
+            .unwrap();
+
+        assert!(record.timeout.is_none());
+        assert_eq!(record.winners, vec![WinLossTie::Tie, WinLossTie::Tie]);
+        assert_eq!(record.total_turns, 2);
+        for turn in record.turns.iter() {
+            assert_eq!(turn.moves.len(), 2);
+            assert_eq!(turn.moves[0].player, 0);
+            assert_eq!(turn.moves[1].player, 1);
+            assert!(turn.moves.iter().all(|m| m.response_time_ms < 50.0));
+        }
+        assert!(crate::replay::replay(SimultaneousNumberGame::new_with_seed, &record).is_ok());
+    }
+
+    #[test]
+    fn test_run_measures_cpu_times() {
+        let players = vec![
+            PlayerPlayFunction {
+                func: &busy_player,
+                params: None,
+                name: None,
+            },
+            PlayerPlayFunction {
+                func: &slow_player,
+                params: None,
+                name: None,
+            },
+        ];
+
+        let stats = run(
+            HighestNumberGame::new_with_seed,
+            &players,
+            2,
+            None,
+            true,
+            &RunOptions::default(),
+        )
+        .unwrap()
+        .unwrap();
+
+        // Both players take 30ms to answer, but the slow player sleeps instead of computing
+        let busy_times = &stats.players_cpu_times[0];
+        let slow_times = &stats.players_cpu_times[1];
+        assert_eq!(busy_times.turns_ms.len(), 2);
+        assert!(busy_times.mean_ms() >= 10.0, "{}", busy_times.mean_ms());
+        assert!(slow_times.max_ms() < busy_times.mean_ms());
+        assert!(stats.players_response_times[1].mean_ms() >= 30.0);
+    }
+
+    #[test]
+    fn test_serialized_players_compute_one_at_a_time() {
+        let slow_player = PlayerPlayFunction {
+            func: &slow_player,
+            params: None,
+            name: None,
+        };
+        let players = vec![slow_player.clone(), slow_player];
+        // Each player takes 30ms : the time limit of the second one starts when the first one answered
+        let options = RunOptions {
+            time_limits: Some(TimeLimits {
+                first_turn: Duration::from_millis(50),
+                turn: Duration::from_millis(50),
+            }),
+            serialized: true,
+            ..Default::default()
+        };
+
+        let start = Instant::now();
+        let mut game = SimultaneousNumberGame::new();
+        let record = run_single(&mut game, &players, 0, 0, true, &options)
+            .record
+            .unwrap();
+
+        assert!(record.timeout.is_none());
+        assert_eq!(record.total_turns, 2);
+        assert!(start.elapsed() >= Duration::from_millis(2 * 2 * 30));
+    }
+
+    #[test]
+    fn test_invalid_moves_are_counted_apart_from_crashes() {
+        let mut players = players();
+        players[0].params = Some(vec!["hello".to_string()]);
+
+        let mut game = SimultaneousNumberGame::new();
+        let record = run_single(&mut game, &players, 0, 0, true, &RunOptions::default())
+            .record
+            .unwrap();
+        assert!(record.crash.is_none());
+        assert_eq!(record.winners, vec![WinLossTie::Loss, WinLossTie::Win]);
+        assert_eq!(
+            record.invalid_moves,
+            vec![record::InvalidMove {
+                player: 0,
+                kind: record::InvalidMoveKind::ParseError,
+                player_move: "hello".to_string(),
+                legal_moves: Vec::new(),
+            }]
+        );
+        assert!(crate::replay::replay(SimultaneousNumberGame::new_with_seed, &record).is_ok());
+
+        let stats = run_permut(
+            SimultaneousNumberGame::new_with_seed,
+            &players,
+            2,
+            None,
+            true,
+            &RunOptions::default(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(stats.players_invalid_moves, vec![(4, 0, 0), (0, 0, 0)]);
+        assert_eq!(stats.players_crashes, vec![0, 0]);
+    }
+
+    #[test]
+    fn test_games_ended_by_invalid_moves_have_no_scores() {
+        let mut players = players();
+        players[1].params = Some(vec!["hello".to_string()]);
+
+        let mut game = SimultaneousNumberGame::new();
+        let result = run_single(&mut game, &players, 0, 0, true, &RunOptions::default());
+        assert_eq!(result.winners, vec![WinLossTie::Win, WinLossTie::Loss]);
+        assert!(game.scores().is_some());
+        assert_eq!(result.scores, None);
+        assert_eq!(result.record.unwrap().scores, None);
+
+        let stats = run_permut(
+            SimultaneousNumberGame::new_with_seed,
+            &players,
+            2,
+            None,
+            true,
+            &RunOptions::default(),
+        )
+        .unwrap()
+        .unwrap();
+        assert!(stats.players_scores.iter().all(|s| s.scores.is_empty()));
+    }
+
+    #[test]
+    fn test_players_over_the_memory_limit_are_flagged() {
+        let mut players = players();
+        players[0].func = &memory_hungry_player;
+        let options = RunOptions {
+            memory_limit_mb: Some(32),
+            ..Default::default()
+        };
+
+        let mut game = HighestNumberGame::new();
+        let record = run_single(&mut game, &players, 0, 0, true, &options)
+            .record
+            .unwrap();
+        let peak_memory = record.peak_memory.unwrap();
+        assert!(peak_memory[0] >= 64 * MB, "{:?}", peak_memory);
+        assert!(peak_memory[1] < MB, "{:?}", peak_memory);
+
+        let stats = run_permut(
+            HighestNumberGame::new_with_seed,
+            &players,
+            2,
+            None,
+            true,
+            &options,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(stats.players_over_memory_limit, vec![4, 0]);
+        assert!(stats.players_peak_memory.unwrap()[0] >= 64 * MB);
+    }
+
+    #[test]
+    fn test_run_streams_record() {
+        let record_dir = std::env::temp_dir().join(format!("record_test_{}", random_seed()));
+        std::fs::create_dir_all(&record_dir).unwrap();
+
+        let mut players = players();
+        players[0].name = Some("ten@1".to_string());
+        run_permut(
+            HighestNumberGame::new_with_seed,
+            &players,
+            3,
+            Some(record_dir.to_str().unwrap().to_string()),
+            false,
+            &RunOptions {
+                nb_threads: 2,
+                seed: Some(100),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let record_file = std::fs::read_dir(&record_dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let reader = record::RecordReader::open(&record_file).unwrap();
+        assert!(reader.header.board_representation.is_none());
+
+        let game_runs: Vec<record::GameRun> = reader.map(|r| r.unwrap()).collect();
+        assert_eq!(game_runs.len(), 6);
+        for (i, game_run) in game_runs.iter().enumerate() {
+            assert_eq!(game_run.run_id, i as u32);
+            assert_eq!(game_run.seed, 100 + i as u64);
+            assert_eq!(game_run.total_turns, 4);
+            assert_eq!(game_run.turns.len(), 4);
+        }
+        assert_eq!(game_runs[0].seats, vec![0, 1]);
+        assert_eq!(game_runs[1].seats, vec![1, 0]);
+        assert_eq!(game_runs[1].bots, vec![None, Some("ten@1".to_string())]);
+        assert_eq!(
+            game_runs[1].winners,
+            vec![WinLossTie::Loss, WinLossTie::Win]
+        );
+
+        std::fs::remove_dir_all(&record_dir).unwrap();
+    }
+
+    #[test]
+    fn test_player_loses_on_timeout() {
+        let players = vec![
+            PlayerPlayFunction {
+                func: &number_player,
+                params: None,
+                name: None,
+            },
+            PlayerPlayFunction {
+                func: &slow_player,
+                params: None,
+                name: None,
+            },
+        ];
+        let options = RunOptions {
+            time_limits: Some(TimeLimits {
+                first_turn: Duration::from_millis(1000),
+                turn: Duration::from_millis(10),
+            }),
+            ..Default::default()
+        };
+
+        let mut game = HighestNumberGame::new();
+        let record = run_single(&mut game, &players, 0, 0, true, &options)
+            .record
+            .unwrap();
+
+        // The slow player survives its first turn, but not its second one
+        assert_eq!(
+            game.winners(),
+            Some(vec![WinLossTie::Win, WinLossTie::Loss])
+        );
+        let timeout = record.timeout.unwrap();
+        assert_eq!(timeout.player, 1);
+        assert_eq!(timeout.turn, 3);
+        assert_eq!(timeout.time_limit_ms, 10);
+        assert!(timeout.response_time_ms.unwrap() >= 10.0);
+        assert_eq!(record.invalid_moves[0].player, 1);
+        assert_eq!(
+            record.invalid_moves[0].kind,
+            record::InvalidMoveKind::Timeout
+        );
+    }
+
+    #[test]
+    fn test_player_that_timed_out_is_waited_for() {
+        let players = vec![
+            PlayerPlayFunction {
+                func: &number_player,
+                params: None,
+                name: None,
+            },
+            PlayerPlayFunction {
+                func: &late_player,
+                params: None,
+                name: None,
+            },
+        ];
+        let options = RunOptions {
+            time_limits: Some(TimeLimits {
+                first_turn: Duration::from_millis(10),
+                turn: Duration::from_millis(10),
+            }),
+            ..Default::default()
+        };
+
+        let mut game = HighestNumberGame::new();
+        let record = run_single(&mut game, &players, 0, 0, true, &options)
+            .record
+            .unwrap();
+
+        // The thread of the player stopped before the next game
+        assert_eq!(record.timeout.unwrap().player, 1);
+        assert!(LATE_PLAYER_STOPPED.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_player_loses_on_panic() {
+        let players = vec![
+            PlayerPlayFunction {
+                func: &crashing_player,
+                params: None,
+                name: None,
+            },
+            PlayerPlayFunction {
+                func: &number_player,
+                params: None,
+                name: None,
+            },
+        ];
+
+        let mut game = HighestNumberGame::new();
+        let result = run_single(&mut game, &players, 0, 0, true, &RunOptions::default());
+
+        assert_eq!(result.winners, vec![WinLossTie::Loss, WinLossTie::Win]);
+        assert_eq!(result.crashed_player, Some(0));
+        let crash = result.record.unwrap().crash.unwrap();
+        assert_eq!(crash.player, Some(0));
+        assert_eq!(crash.turn, Some(2));
+        assert_eq!(crash.source, record::CrashSource::Player);
+        assert!(crash.message.starts_with("Crashing player gave up"));
+        assert!(!crash.backtrace.is_empty());
+    }
+
+    #[test]
+    fn test_player_loses_when_its_move_crashes_the_game() {
+        let players = vec![
+            PlayerPlayFunction {
+                func: &number_player,
+                params: None,
+                name: None,
+            },
+            PlayerPlayFunction {
+                func: &number_player,
+                params: Some(vec!["not a number".to_string()]),
+                name: None,
+            },
+        ];
+
+        let mut game = HighestNumberGame::new();
+        let result = run_single(&mut game, &players, 0, 0, true, &RunOptions::default());
+
+        assert_eq!(result.winners, vec![WinLossTie::Win, WinLossTie::Loss]);
+        let crash = result.record.unwrap().crash.unwrap();
+        assert_eq!(crash.player, Some(1));
+        assert_eq!(crash.turn, Some(1));
+        assert_eq!(crash.source, record::CrashSource::Game);
+    }
+
+    #[test]
+    fn test_game_crash_on_simultaneous_moves_blames_no_player() {
+        let mut players = players();
+        players[1].params = Some(vec![u32::MAX.to_string()]);
+
+        let mut game = SimultaneousNumberGame::new();
+        let result = run_single(&mut game, &players, 0, 0, true, &RunOptions::default());
+
+        assert_eq!(result.winners, vec![WinLossTie::Tie, WinLossTie::Tie]);
+        assert_eq!(result.crashed_player, None);
+        assert!(result.crashed_game);
+        let record = result.record.unwrap();
+        let crash = record.crash.as_ref().unwrap();
+        assert_eq!(crash.player, None);
+        assert_eq!(crash.turn, Some(1));
+        assert_eq!(crash.source, record::CrashSource::Game);
+        assert!(crate::replay::replay(SimultaneousNumberGame::new_with_seed, &record).is_ok());
+    }
+
+    #[test]
+    fn test_game_crashes_are_counted_apart_from_the_results() {
+        let mut players = players();
+        players[1].params = Some(vec![u32::MAX.to_string()]);
+
+        let stats = run_permut(
+            SimultaneousNumberGame::new_with_seed,
+            &players,
+            2,
+            None,
+            true,
+            &RunOptions {
+                paired: true,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(stats.game_crashes, 4);
+        assert_eq!(stats.players_win_loss, vec![(0, 0, 0), (0, 0, 0)]);
+        assert_eq!(stats.players_crashes, vec![0, 0]);
+        assert_eq!(stats.players_pair_results, Some(vec![(0, 0, 0), (0, 0, 0)]));
+        assert!(stats
+            .to_string()
+            .contains("Game crashes : 4 (not counted in the results)"));
+    }
+
+    #[test]
+    fn test_run_continues_after_game_crashes_outside_of_turns() {
+        // The game panics when it's built with an odd seed
+        let game_constr = |seed: u64| match seed % 2 {
+            0 => HighestNumberGame::new_with_seed(seed),
+            _ => panic!("Odd seed"),
+        };
+        let record_dir = std::env::temp_dir().join(format!("record_test_{}", random_seed()));
+        std::fs::create_dir_all(&record_dir).unwrap();
+
+        let stats = run(
+            game_constr,
+            &players(),
+            4,
+            Some(record_dir.to_str().unwrap().to_string()),
+            true,
+            &RunOptions {
+                nb_threads: 2,
+                seed: Some(0),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(stats.game_crashes, 2);
+        assert_eq!(stats.players_win_loss, vec![(2, 0, 0), (0, 2, 0)]);
+        assert_eq!(stats.players_crashes, vec![0, 0]);
+
+        let record_file = std::fs::read_dir(&record_dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let game_runs: Vec<record::GameRun> = record::RecordReader::open(&record_file)
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(game_runs.len(), 4);
+        assert!(game_runs[0].crash.is_none());
+        let crashed_run = &game_runs[1];
+        assert_eq!(crashed_run.seed, 1);
+        assert_eq!(crashed_run.total_turns, 0);
+        assert_eq!(crashed_run.winners, vec![WinLossTie::Tie, WinLossTie::Tie]);
+        let crash = crashed_run.crash.as_ref().unwrap();
+        assert_eq!(crash.player, None);
+        assert_eq!(crash.turn, None);
+        assert_eq!(crash.source, record::CrashSource::Game);
+        assert!(crash.message.starts_with("Odd seed"));
+        assert!(crate::replay::replay(game_constr, crashed_run).is_ok());
+
+        std::fs::remove_dir_all(&record_dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_continues_after_crashes() {
+        let players = vec![
+            PlayerPlayFunction {
+                func: &number_player,
+                params: None,
+                name: None,
+            },
+            PlayerPlayFunction {
+                func: &crashing_player,
+                params: None,
+                name: None,
+            },
+        ];
+
+        let stats = run_permut(
+            HighestNumberGame::new_with_seed,
+            &players,
+            5,
+            None,
+            true,
+            &RunOptions {
+                nb_threads: 2,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(stats.players_win_loss, vec![(10, 0, 0), (0, 10, 0)]);
+        assert_eq!(stats.players_crashes, vec![0, 10]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_player() {
+        let players = vec![
+            PlayerPlayFunction::process(&["sh", "-c", "while read turn; do echo 10; done"]),
+            PlayerPlayFunction {
+                func: &number_player,
+                params: None,
+                name: None,
+            },
+        ];
+
+        let mut game = HighestNumberGame::new();
+        let record = run_single(&mut game, &players, 0, 0, true, &RunOptions::default())
+            .record
+            .unwrap();
+
+        assert_eq!(
+            game.winners(),
+            Some(vec![WinLossTie::Win, WinLossTie::Loss])
+        );
+        assert_eq!(record.turns[0].moves[0].player_move, "10");
+        assert_eq!(record.turns[1].moves[0].player_move, "1");
+        assert_eq!(record.turns[2].moves[0].player_move, "10");
+    }
 }
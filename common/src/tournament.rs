@@ -0,0 +1,256 @@
+use crate::simulator::{self, PlayerPlayFunction, RunOptions};
+use crate::Game;
+use std::fmt;
+use std::io::Error;
+
+// Iterations of the rating computation, more than enough to converge for a pool of a few bots
+const RATING_ITERATIONS: usize = 1000;
+
+pub struct TournamentResult {
+    pub names: Vec<String>,
+    // head_to_head[i][j] : (wins, losses, ties) of bot i against bot j
+    pub head_to_head: Vec<Vec<(i32, i32, i32)>>,
+    // Elo rating of each bot, the average rating of the pool being 0
+    pub ratings: Vec<f64>,
+}
+
+impl TournamentResult {
+    // Bot indexes, from the highest rated to the lowest rated
+    pub fn ranking(&self) -> Vec<usize> {
+        let mut ranking: Vec<usize> = (0..self.names.len()).collect();
+        ranking.sort_by(|a, b| self.ratings[*b].total_cmp(&self.ratings[*a]));
+        ranking
+    }
+}
+
+impl fmt::Display for TournamentResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ranking = self.ranking();
+        let name_width = self.names.iter().map(|n| n.len()).max().unwrap_or(0);
+
+        // Ranked table
+        writeln!(
+            f,
+            "{:>4}  {:<w$}  {:>6}  {:>5}  {:>5}  {:>5}",
+            "Rank",
+            "Bot",
+            "Elo",
+            "W",
+            "L",
+            "T",
+            w = name_width
+        )?;
+        for (rank, b) in ranking.iter().enumerate() {
+            let (wins, losses, ties) = self.head_to_head[*b]
+                .iter()
+                .fold((0, 0, 0), |acc, r| (acc.0 + r.0, acc.1 + r.1, acc.2 + r.2));
+            writeln!(
+                f,
+                "{:>4}  {:<w$}  {:>+6.0}  {:>5}  {:>5}  {:>5}",
+                rank + 1,
+                self.names[*b],
+                self.ratings[*b],
+                wins,
+                losses,
+                ties,
+                w = name_width
+            )?;
+        }
+
+        // Head-to-head matrix : W-L-T of the row bot against the column bot, in ranking order
+        writeln!(f)?;
+        write!(f, "{:<w$}", "", w = name_width)?;
+        for b in ranking.iter() {
+            write!(f, "  {:>12}", self.names[*b])?;
+        }
+        writeln!(f)?;
+        for i in ranking.iter() {
+            write!(f, "{:<w$}", self.names[*i], w = name_width)?;
+            for j in ranking.iter() {
+                let cell = match i == j {
+                    true => "-".to_string(),
+                    false => {
+                        let (wins, losses, ties) = self.head_to_head[*i][*j];
+                        format!("{}-{}-{}", wins, losses, ties)
+                    }
+                };
+                write!(f, "  {:>12}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/*
+    Round-robin tournament between a named pool of bots, for 2-player games :
+    each pair of bots plays [nb_runs] games in each seating, with simulator::run_permut.
+    Records (if [record_path] is set) are written per pairing.
+*/
+pub fn run_tournament<GC, G>(
+    game_constr: GC,
+    bots: &[(&str, PlayerPlayFunction)],
+    nb_runs: u32,
+    record_path: Option<String>,
+    options: &RunOptions,
+) -> Result<TournamentResult, Error>
+where
+    GC: Fn(u64) -> G + Sync,
+    G: Game,
+{
+    let bot_count = bots.len();
+    let mut head_to_head = vec![vec![(0, 0, 0); bot_count]; bot_count];
+
+    // Every pairing plays all its games, so that the head-to-head matrix is complete
+    let options = RunOptions {
+        sprt: None,
+        ..options.clone()
+    };
+
+    for i in 0..bot_count {
+        for j in i + 1..bot_count {
+            println!("[TOURNAMENT] {} vs {}", bots[i].0, bots[j].0);
+            let players = vec![bots[i].1.clone(), bots[j].1.clone()];
+            let stats = simulator::run_permut(
+                &game_constr,
+                &players,
+                nb_runs,
+                record_path.clone(),
+                true,
+                &options,
+            )?
+            .unwrap();
+
+            let (wins, losses, ties) = stats.players_win_loss[0];
+            head_to_head[i][j] = (wins, losses, ties);
+            head_to_head[j][i] = (losses, wins, ties);
+        }
+    }
+
+    Ok(TournamentResult {
+        names: bots.iter().map(|(name, _)| name.to_string()).collect(),
+        ratings: elo_ratings(&head_to_head),
+        head_to_head,
+    })
+}
+
+/*
+    Maximum likelihood Elo ratings (Bradley-Terry model, a tie counting as half a win and half a loss),
+    computed with the MM algorithm. Each pairing gets a virtual tie as a prior,
+    so that a bot that won all its games still gets a finite rating.
+*/
+pub fn elo_ratings(head_to_head: &[Vec<(i32, i32, i32)>]) -> Vec<f64> {
+    let bot_count = head_to_head.len();
+    let mut strengths = vec![1.0; bot_count];
+
+    for _ in 0..RATING_ITERATIONS {
+        for i in 0..bot_count {
+            let mut score = 0.0;
+            let mut denominator = 0.0;
+            for j in 0..bot_count {
+                if i == j {
+                    continue;
+                }
+                let (wins, losses, ties) = head_to_head[i][j];
+                let games = (wins + losses + ties) as f64 + 1.0;
+                score += wins as f64 + (ties as f64 + 1.0) / 2.0;
+                denominator += games / (strengths[i] + strengths[j]);
+            }
+            if denominator > 0.0 {
+                strengths[i] = score / denominator;
+            }
+        }
+    }
+
+    let ratings: Vec<f64> = strengths.iter().map(|s| 400.0 * s.log10()).collect();
+    let mean = ratings.iter().sum::<f64>() / bot_count.max(1) as f64;
+    ratings.iter().map(|r| r - mean).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::tests::HighestNumberGame;
+    use crate::tuning::tests::named_number_player;
+
+    #[test]
+    fn test_run_tournament() {
+        let bot = |number: &str| PlayerPlayFunction {
+            func: &named_number_player,
+            params: Some(vec![format!("number={}", number)]),
+            name: None,
+        };
+        // The bot that plays the highest number wins all its games
+        let pool = [("mid", bot("5")), ("low", bot("1")), ("high", bot("9"))];
+
+        let result = run_tournament(
+            HighestNumberGame::new_with_seed,
+            &pool,
+            2,
+            None,
+            &RunOptions::default(),
+        )
+        .unwrap();
+
+        // Each pairing plays 2 games in each seating
+        assert_eq!(result.names, vec!["mid", "low", "high"]);
+        assert_eq!(
+            result.head_to_head,
+            vec![
+                vec![(0, 0, 0), (4, 0, 0), (0, 4, 0)],
+                vec![(0, 4, 0), (0, 0, 0), (0, 4, 0)],
+                vec![(4, 0, 0), (4, 0, 0), (0, 0, 0)],
+            ]
+        );
+        assert_eq!(result.ranking(), vec![2, 0, 1]);
+
+        let table = result.to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[1].starts_with("   1  high"), "{}", table);
+        assert!(lines[1].ends_with("8      0      0"), "{}", table);
+        assert!(lines[3].starts_with("   3  low"), "{}", table);
+        // The matrix rows & columns are in ranking order : high beat mid 4 times
+        assert_eq!(
+            lines[6].split_whitespace().collect::<Vec<&str>>(),
+            vec!["high", "-", "4-0-0", "4-0-0"]
+        );
+        assert_eq!(
+            lines[8].split_whitespace().collect::<Vec<&str>>(),
+            vec!["low", "0-4-0", "0-4-0", "-"]
+        );
+    }
+
+    #[test]
+    fn test_elo_ratings_are_ordered() {
+        // a beats b 60% of the time, b beats c 60% of the time
+        let head_to_head = vec![
+            vec![(0, 0, 0), (60, 40, 0), (70, 30, 0)],
+            vec![(40, 60, 0), (0, 0, 0), (60, 40, 0)],
+            vec![(30, 70, 0), (40, 60, 0), (0, 0, 0)],
+        ];
+
+        let ratings = elo_ratings(&head_to_head);
+        assert!(ratings[0] > ratings[1]);
+        assert!(ratings[1] > ratings[2]);
+        assert!(ratings.iter().sum::<f64>().abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_elo_ratings_two_bots() {
+        // With a 3 to 1 score, the rating difference is 400 * log10(3), minus the pull of the virtual tie
+        let head_to_head = vec![vec![(0, 0, 0), (75, 25, 0)], vec![(25, 75, 0), (0, 0, 0)]];
+
+        let ratings = elo_ratings(&head_to_head);
+        let expected_difference = 400.0 * (75.5f64 / 25.5).log10();
+        assert!((ratings[0] - ratings[1] - expected_difference).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_elo_ratings_undefeated_bot_is_finite() {
+        let head_to_head = vec![vec![(0, 0, 0), (10, 0, 0)], vec![(0, 10, 0), (0, 0, 0)]];
+
+        let ratings = elo_ratings(&head_to_head);
+        assert!(ratings[0].is_finite());
+        assert!(ratings[0] > ratings[1]);
+    }
+}
@@ -0,0 +1,391 @@
+use crate::simulator::{self, PlayerPlayFunction, RunOptions, RunStatistics};
+use crate::Game;
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+use std::io::Error;
+
+/*
+    Tuning of a bot's named parameters. The bot reads them from its params, as "name=value" strings
+    (e.g. "exploration=0.4"), and any parameter that isn't given keeps its default value.
+    Each setting plays against a baseline (usually the same bot with its default parameters) with simulator::run_permut.
+*/
+
+// A setting of the tuned parameters, with its results against the baseline
+pub struct Candidate {
+    pub params: Vec<(String, String)>,
+    pub stats: RunStatistics,
+}
+
+pub struct TuningResult {
+    pub candidates: Vec<Candidate>,
+    // Index of the candidate with the best score
+    pub best: usize,
+}
+
+impl TuningResult {
+    fn new(candidates: Vec<Candidate>) -> TuningResult {
+        let best = (0..candidates.len())
+            .max_by(|a, b| {
+                candidates[*a]
+                    .stats
+                    .score(0)
+                    .0
+                    .total_cmp(&candidates[*b].stats.score(0).0)
+            })
+            .unwrap_or(0);
+        TuningResult { candidates, best }
+    }
+
+    pub fn best_params(&self) -> &[(String, String)] {
+        &self.candidates[self.best].params
+    }
+}
+
+fn fmt_params(params: &[(String, String)]) -> String {
+    params
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .join(" ")
+}
+
+impl fmt::Display for TuningResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for candidate in self.candidates.iter() {
+            let (score, low, high) = candidate.stats.score(0);
+            let (elo, elo_margin) = candidate.stats.elo(0);
+            writeln!(
+                f,
+                "{} : score {:.1}% [{:.1}%, {:.1}%] | Elo {:+.0} ± {:.0}",
+                fmt_params(&candidate.params),
+                score * 100.0,
+                low * 100.0,
+                high * 100.0,
+                elo,
+                elo_margin
+            )?;
+        }
+
+        let best = &self.candidates[self.best];
+        let (_, low, high) = best.stats.score(0);
+        writeln!(
+            f,
+            "Best : {} | {}",
+            fmt_params(&best.params),
+            match (low > 0.5, high < 0.5) {
+                (true, _) => "stronger than the baseline",
+                (_, true) => "weaker than the baseline",
+                _ => "not significantly different from the baseline",
+            }
+        )
+    }
+}
+
+// Score of [player] against [baseline]
+fn play_against<GC, G>(
+    game_constr: &GC,
+    player: PlayerPlayFunction,
+    baseline: &PlayerPlayFunction,
+    nb_runs: u32,
+    options: &RunOptions,
+) -> Result<RunStatistics, Error>
+where
+    GC: Fn(u64) -> G + Sync,
+    G: Game,
+{
+    let players = vec![player, baseline.clone()];
+    Ok(simulator::run_permut(game_constr, &players, nb_runs, None, true, options)?.unwrap())
+}
+
+/*
+    Grid search : every combination of the [grid] values plays [nb_runs] games in each seating against [baseline].
+    The confidence interval of the best candidate is optimistic, as it was selected among the others :
+    confirm it with a new run (or an SPRT) before adopting it.
+*/
+pub fn grid_search<GC, G>(
+    game_constr: GC,
+    player: &PlayerPlayFunction,
+    baseline: &PlayerPlayFunction,
+    grid: &[(&str, Vec<String>)],
+    nb_runs: u32,
+    options: &RunOptions,
+) -> Result<TuningResult, Error>
+where
+    GC: Fn(u64) -> G + Sync,
+    G: Game,
+{
+    // All candidates play the same number of games, so that their scores can be compared
+    let options = RunOptions {
+        sprt: None,
+        ..options.clone()
+    };
+
+    let mut candidates = Vec::new();
+    for values in grid
+        .iter()
+        .map(|(_, values)| values.iter())
+        .multi_cartesian_product()
+    {
+        let params: Vec<(String, String)> = grid
+            .iter()
+            .zip(values)
+            .map(|((name, _), value)| (name.to_string(), value.clone()))
+            .collect();
+
+        println!("[TUNING] {}", fmt_params(&params));
+        let stats = play_against(
+            &game_constr,
+            player.with_params(&params),
+            baseline,
+            nb_runs,
+            &options,
+        )?;
+        candidates.push(Candidate { params, stats });
+    }
+
+    Ok(TuningResult::new(candidates))
+}
+
+// A numerical parameter tuned by SPSA
+#[derive(Clone)]
+pub struct SpsaParam {
+    pub name: String,
+    pub start: f64,
+    pub min: f64,
+    pub max: f64,
+    // Perturbation of the parameter in the first iteration, e.g. a tenth of its range
+    pub step: f64,
+    // The value is rounded before it's given to the bot
+    pub integer: bool,
+}
+
+impl SpsaParam {
+    fn value(&self, theta: f64) -> String {
+        match self.integer {
+            true => format!("{}", theta.round() as i64),
+            false => format!("{}", theta),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SpsaOptions {
+    pub iterations: u32,
+    // Games played in each seating by the 2 perturbed settings at each iteration
+    pub runs_per_iteration: u32,
+    // Move of a parameter, in steps, for each game won more than lost in the first iteration
+    pub learning_rate: f64,
+    // Games played in each seating by the tuned setting against the baseline, to measure its strength
+    pub verification_runs: u32,
+    pub seed: u64,
+}
+
+impl Default for SpsaOptions {
+    fn default() -> Self {
+        SpsaOptions {
+            iterations: 100,
+            runs_per_iteration: 1,
+            learning_rate: 0.1,
+            verification_runs: 100,
+            seed: 0,
+        }
+    }
+}
+
+/*
+    SPSA (simultaneous perturbation stochastic approximation) : at each iteration, all parameters are perturbed
+    at random by ±step, and the setting θ+Δ plays against θ-Δ. θ then moves towards the setting that won.
+    Steps and learning rate decrease with the iterations (with the usual exponents 0.101 and 0.602).
+    The tuned setting finally plays against [baseline], so that its strength is known with confidence.
+*/
+pub fn spsa<GC, G>(
+    game_constr: GC,
+    player: &PlayerPlayFunction,
+    baseline: &PlayerPlayFunction,
+    params: &[SpsaParam],
+    spsa_options: &SpsaOptions,
+    options: &RunOptions,
+) -> Result<TuningResult, Error>
+where
+    GC: Fn(u64) -> G + Sync,
+    G: Game,
+{
+    let options = RunOptions {
+        sprt: None,
+        ..options.clone()
+    };
+    let mut rng = StdRng::seed_from_u64(spsa_options.seed);
+    let mut theta: Vec<f64> = params.iter().map(|p| p.start).collect();
+
+    let named_params = |theta: &[f64]| -> Vec<(String, String)> {
+        params
+            .iter()
+            .zip(theta.iter())
+            .map(|(p, t)| (p.name.clone(), p.value(*t)))
+            .collect()
+    };
+    let clamp = |p: &SpsaParam, t: f64| t.max(p.min).min(p.max);
+
+    // Stability constant of the learning rate, 10% of the iterations as usual
+    let a = spsa_options.iterations as f64 / 10.0;
+
+    for k in 0..spsa_options.iterations {
+        let c_k = 1.0 / (k as f64 + 1.0).powf(0.101);
+        let a_k = spsa_options.learning_rate * ((1.0 + a) / (k as f64 + 1.0 + a)).powf(0.602);
+
+        let delta: Vec<f64> = params
+            .iter()
+            .map(|_| match rng.gen::<bool>() {
+                true => 1.0,
+                false => -1.0,
+            })
+            .collect();
+        let theta_plus: Vec<f64> = params
+            .iter()
+            .enumerate()
+            .map(|(i, p)| clamp(p, theta[i] + c_k * p.step * delta[i]))
+            .collect();
+        let theta_minus: Vec<f64> = params
+            .iter()
+            .enumerate()
+            .map(|(i, p)| clamp(p, theta[i] - c_k * p.step * delta[i]))
+            .collect();
+
+        let players = vec![
+            player.with_params(&named_params(&theta_plus)),
+            player.with_params(&named_params(&theta_minus)),
+        ];
+        let stats = simulator::run_permut(
+            &game_constr,
+            &players,
+            spsa_options.runs_per_iteration,
+            None,
+            true,
+            &options,
+        )?
+        .unwrap();
+        let (wins, losses, _) = stats.players_win_loss[0];
+
+        for (i, p) in params.iter().enumerate() {
+            theta[i] = clamp(
+                p,
+                theta[i] + a_k * c_k * p.step * (wins - losses) as f64 * delta[i],
+            );
+        }
+        println!(
+            "[TUNING] SPSA iteration {} : {}",
+            k,
+            fmt_params(&named_params(&theta))
+        );
+    }
+
+    let tuned_params = named_params(&theta);
+    let stats = play_against(
+        &game_constr,
+        player.with_params(&tuned_params),
+        baseline,
+        spsa_options.verification_runs,
+        &options,
+    )?;
+
+    Ok(TuningResult::new(vec![Candidate {
+        params: tuned_params,
+        stats,
+    }]))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::simulator::tests::HighestNumberGame;
+    use std::collections::HashMap;
+    use std::sync::mpsc::{Receiver, Sender};
+
+    // Plays the number given by its "number=N" param, 5 by default
+    pub(crate) fn named_number_player(
+        ctr_rcv: Receiver<bool>,
+        msg_rcv: Receiver<String>,
+        msg_snd: Sender<(String, Option<HashMap<String, String>>)>,
+        params: Option<Vec<String>>,
+    ) {
+        let mut number = "5".to_string();
+        for param in params.iter().flatten() {
+            if let Some(("number", value)) = param.split_once('=') {
+                number = value.to_string();
+            }
+        }
+
+        while ctr_rcv.recv().unwrap() {
+            msg_rcv.recv().unwrap();
+            msg_snd.send((number.clone(), None)).unwrap();
+        }
+    }
+
+    fn player() -> PlayerPlayFunction {
+        PlayerPlayFunction {
+            func: &named_number_player,
+            params: None,
+            name: None,
+        }
+    }
+
+    #[test]
+    fn test_grid_search() {
+        let grid = [(
+            "number",
+            vec!["1".to_string(), "20".to_string(), "5".to_string()],
+        )];
+
+        let result = grid_search(
+            HighestNumberGame::new_with_seed,
+            &player(),
+            &player(),
+            &grid,
+            5,
+            &RunOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(result.candidates.len(), 3);
+        assert_eq!(
+            result.best_params(),
+            &[("number".to_string(), "20".to_string())]
+        );
+        assert_eq!(
+            result.candidates[result.best].stats.players_win_loss[0],
+            (10, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_spsa_moves_towards_the_stronger_setting() {
+        let params = [SpsaParam {
+            name: "number".to_string(),
+            start: 5.0,
+            min: 0.0,
+            max: 20.0,
+            step: 2.0,
+            integer: true,
+        }];
+        let spsa_options = SpsaOptions {
+            iterations: 20,
+            verification_runs: 5,
+            ..Default::default()
+        };
+
+        let result = spsa(
+            HighestNumberGame::new_with_seed,
+            &player(),
+            &player(),
+            &params,
+            &spsa_options,
+            &RunOptions::default(),
+        )
+        .unwrap();
+
+        let tuned: i32 = result.best_params()[0].1.parse().unwrap();
+        assert!(tuned > 5, "{}", tuned);
+        assert_eq!(result.candidates[0].stats.players_win_loss[0], (10, 0, 0));
+    }
+}
@@ -1,7 +1,8 @@
 use common::record;
 use common::{Game, Message, StackVector, WinLossTie};
 use rand::prelude::SliceRandom;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
 use std::cmp;
 use std::collections::HashMap;
 
@@ -26,25 +27,21 @@ enum Move {
 }
 
 impl Move {
-    fn parse_move(msg: &str) -> Move {
-        match msg {
-            "WAIT" => Move::WAIT,
-            "REST" => Move::REST,
-            _ => match &msg[0..5] {
-                "BREW " => Move::BREW(parse_input!(msg[5..], u32)),
-                "LEARN" => Move::LEARN(parse_input!(msg[6..], u32)),
-                "CAST " => {
-                    let s = msg.split(" ").collect::<Vec<_>>();
-                    match s.len() {
-                        2 => Move::CAST(parse_input!(s[1], u32), 1),
-                        3 => Move::CAST(parse_input!(s[1], u32), parse_input!(s[2], u8)),
-                        _ => panic!("Couldn't parse correctly CAST move"),
-                    }
-                }
-                _ => {
-                    panic!("Cannot parse move");
-                }
+    // The move, or None if [msg] isn't a move
+    fn parse_move(msg: &str) -> Option<Move> {
+        let s = msg.trim().split(" ").collect::<Vec<_>>();
+        let id = |i: usize| s[i].parse::<u32>().ok();
+        match (s[0], s.len()) {
+            ("WAIT", 1) => Some(Move::WAIT),
+            ("REST", 1) => Some(Move::REST),
+            ("BREW", 2) => id(1).map(Move::BREW),
+            ("LEARN", 2) => id(1).map(Move::LEARN),
+            ("CAST", 2) => id(1).map(|id| Move::CAST(id, 1)),
+            ("CAST", 3) => match (id(1), s[2].parse::<u8>().ok()) {
+                (Some(id), Some(times)) => Some(Move::CAST(id, times)),
+                _ => None,
             },
+            _ => None,
         }
     }
 
@@ -316,7 +313,6 @@ fn get_all_tome_spells() -> Vec<Spell> {
     tome_spells
 }
 
-#[allow(dead_code)]
 fn find_spell(recipe: &Recipe) -> Option<Spell> {
     for spell in get_all_tome_spells().iter() {
         if spell.recipe == *recipe {
@@ -379,7 +375,6 @@ fn get_all_orders() -> Vec<Order> {
         .collect::<Vec<Order>>()
 }
 
-#[allow(dead_code)]
 fn find_order(recipe: &Recipe) -> Option<Order> {
     for order in get_all_orders().iter() {
         if order.recipe == *recipe {
@@ -521,7 +516,7 @@ fn update_counter_orders(
     /* Replace removed orders if possible */
     for _ in 0..removed_orders_count {
         if queued_orders.len() > 0 {
-            counter_orders.add(queued_orders.pop().unwrap());
+            counter_orders.push(queued_orders.pop().unwrap());
         }
     }
 
@@ -583,7 +578,7 @@ fn update_tome_spells(
     /* Replace removed spells if possible */
     for _ in 0..learnt_spells_count {
         if queued_spells.len() > 0 {
-            tome_spells.add(queued_spells.pop().unwrap());
+            tome_spells.push(queued_spells.pop().unwrap());
         }
     }
 
@@ -612,7 +607,7 @@ fn valid_moves(
     // Check which order the player can fulfill and add them as a valid move
     for order in orders.iter() {
         if cache.can_fulfill_order(order.id, player_stock_id) {
-            valid_moves.add(Move::BREW(order.id));
+            valid_moves.push(Move::BREW(order.id));
         }
     }
 
@@ -627,7 +622,7 @@ fn valid_moves(
             let times_can_cast_spell = cache.how_many_times_can_cast_spell(sp.id, player_stock_id);
             if times_can_cast_spell > 0 {
                 for n in 1..=times_can_cast_spell {
-                    valid_moves.add(Move::CAST(sp.id, n));
+                    valid_moves.push(Move::CAST(sp.id, n));
                 }
             }
         }
@@ -635,24 +630,83 @@ fn valid_moves(
 
     /* REST move */
     if all_spells_are_active == false {
-        valid_moves.add(Move::REST);
+        valid_moves.push(Move::REST);
     }
 
     /* LEARN moves */
     if tome_spells.len() > 0 {
         for t in 0..=cmp::min(player_stock[0] as usize, tome_spells.len() - 1) {
-            valid_moves.add(Move::LEARN(tome_spells[t].id));
+            valid_moves.push(Move::LEARN(tome_spells[t].id));
         }
     }
 
     // At the end, if there's no valid moves, we just send a wait
     if valid_moves.len == 0 {
-        valid_moves.add(Move::WAIT);
+        valid_moves.push(Move::WAIT);
     }
 
     valid_moves
 }
 
+// A line of the turn input, e.g. "78 CAST 2 0 0 0 0 -1 -1 1 0", split into its action id, action type and numbers
+fn parse_position_action(line: Option<&str>) -> Result<(String, Vec<i32>), String> {
+    let line = line.ok_or("The position ends too early")?;
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.len() != 11 {
+        return Err(format!("Expected 11 fields in the action line : {}", line));
+    }
+
+    let numbers = tokens[2..]
+        .iter()
+        .map(|n| n.parse::<i32>())
+        .collect::<Result<Vec<i32>, _>>()
+        .map_err(|_| format!("Invalid action line in the position : {}", line))?;
+    Ok((tokens[1].to_string(), numbers))
+}
+
+fn parse_position_numbers(line: Option<&str>, count: usize) -> Result<Vec<i32>, String> {
+    let line = line.ok_or("The position ends too early")?;
+    let numbers = line
+        .split_whitespace()
+        .map(|n| n.parse::<i32>())
+        .collect::<Result<Vec<i32>, _>>()
+        .map_err(|_| format!("Invalid line in the position : {}", line))?;
+
+    if numbers.len() != count {
+        return Err(format!(
+            "Expected {} numbers in the position line : {}",
+            count, line
+        ));
+    }
+    Ok(numbers)
+}
+
+fn parse_position_stock(line: Option<&str>) -> Result<(Stock, u32), String> {
+    let numbers = parse_position_numbers(line, 5)?;
+    if numbers[0..4].iter().any(|n| *n < 0) || numbers[0..4].iter().sum::<i32>() > 10 {
+        return Err(format!("Invalid ingredient stock {:?}", &numbers[0..4]));
+    }
+    let rupees = u32::try_from(numbers[4]).map_err(|_| format!("Invalid rupees {}", numbers[4]))?;
+    Ok((
+        [
+            numbers[0] as i8,
+            numbers[1] as i8,
+            numbers[2] as i8,
+            numbers[3] as i8,
+        ],
+        rupees,
+    ))
+}
+
+// A flag of the position, that is 0 or 1
+fn parse_position_flag(value: i32, name: &str) -> Result<bool, String> {
+    match value {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(format!("Invalid {} flag {}", name, value)),
+    }
+}
+
 #[allow(dead_code)]
 fn new_game_with_params(
     players: [Player; 2],
@@ -681,76 +735,9 @@ fn new_game_with_params(
 
 /* #endregion */
 
-impl Game for WitchesBrewGame {
-    fn new() -> Self {
-        /* Init cache */
-        let cache = Cache::new();
-
-        /* Create Player's basic spells */
-        let mut player_spells: StackVector<Spell, EXISTING_SPELL_COUNT> = StackVector::new();
-        let basic_spells = get_basic_spells();
-        for i in 0..4 {
-            player_spells.add(basic_spells[i]);
-        }
-
-        /* Create Players */
-        let player = Player {
-            move_: Move::NONE,
-            stock: [3, 0, 0, 0],
-            stock_id: cache.getStockId(&[3, 0, 0, 0]),
-            rupees: 0,
-            brewed_potions_count: 0,
-            spells: player_spells,
-        };
-
-        /* Create orders */
-        let mut all_orders = get_all_orders();
-        all_orders.shuffle(&mut thread_rng());
-
-        let mut counter_orders: StackVector<Order, 5> = StackVector::new();
-        for _ in 0..5 {
-            counter_orders.add(all_orders.pop().unwrap());
-        }
-        counter_orders.get_mut(0).bonus = 3;
-        counter_orders.get_mut(1).bonus = 1;
-
-        /* Create tome spells */
-        let mut queued_spells = get_learnable_tome_spells();
-        queued_spells.shuffle(&mut thread_rng());
-
-        let mut tome_spells: StackVector<Spell, 6> = StackVector::new();
-        for _ in 0..6 {
-            tome_spells.add(queued_spells.pop().unwrap());
-        }
-
-        let game = WitchesBrewGame {
-            players: [player.clone(), player.clone()],
-
-            queued_orders: all_orders,
-            counter_orders: counter_orders,
-            plus_3_bonus_remaining: 4,
-            plus_1_bonus_remaining: 4,
-
-            queued_spells: queued_spells,
-            tome_spells: tome_spells,
-
-            active: true,
-            active_player: 0,
-            turn: 0,
-            winners: None,
-
-            cache,
-        };
-
-        game
-    }
-
-    fn turn(&self) -> Option<Message> {
-        // If game is over, return None
-        if self.active == false {
-            return None;
-        }
-
+impl WitchesBrewGame {
+    // Input of [player_id] for the current turn
+    fn message(&self, player_id: u8) -> Message {
         let mut out: Vec<String> = Vec::new();
 
         /* (1) Output number of orders */
@@ -799,8 +786,8 @@ impl Game for WitchesBrewGame {
             ));
         }
 
-        let active_player: &Player = &self.players[self.active_player as usize];
-        let other_player: &Player = &self.players[((self.active_player + 1) % 2) as usize];
+        let active_player: &Player = &self.players[player_id as usize];
+        let other_player: &Player = &self.players[((player_id + 1) % 2) as usize];
 
         /* (3) Output the active player' spells */
         for spell in active_player.spells.slice().iter() {
@@ -863,208 +850,470 @@ impl Game for WitchesBrewGame {
         ));
 
         /* (X) Send message */
-        Some(Message {
-            player_id: self.active_player as usize,
+        Message {
+            player_id: player_id as usize,
             messages: out,
-        })
+        }
     }
 
-    fn play(&mut self, msg: String) {
-        /* (1) Parse move, assuming it is always in the right format */
-        let _move = Move::parse_move(msg.as_str());
+    // Moves that [player] can play
+    fn legal_moves(&self, player: usize) -> Vec<String> {
+        let player = &self.players[player];
+        valid_moves(
+            &self.counter_orders.slice(),
+            &self.tome_spells.slice(),
+            &player.spells.slice(),
+            &player.stock,
+            player.stock_id,
+            &self.cache,
+        )
+        .slice()
+        .iter()
+        .map(|m| m.to_string())
+        .collect()
+    }
 
-        /* (2) Record the move */
-        self.players[self.active_player as usize].move_ = _move;
+    // The move [msg] of [player], that can't be parsed
+    fn parse_error(&self, player: usize, msg: &str) -> record::InvalidMove {
+        record::InvalidMove {
+            player: player as u32,
+            kind: record::InvalidMoveKind::ParseError,
+            player_move: msg.to_string(),
+            legal_moves: self.legal_moves(player),
+        }
+    }
 
-        /* (3) If it's player'1 turn, i.e. both players have played =>  update the state */
-        if self.active_player == 1 {
-            let player0: &Player = &self.players[0];
-            let player1: &Player = &self.players[1];
+    // Update the state, once both players have played their move
+    fn resolve_moves(&mut self) -> Result<(), Vec<record::InvalidMove>> {
+        /* 3.1 Check if moves were valid */
+        let invalid_moves: Vec<record::InvalidMove> = (0..2)
+            .map(|pid| (pid, self.legal_moves(pid)))
+            .filter(|(pid, legal_moves)| {
+                !legal_moves.contains(&self.players[*pid].move_.to_string())
+            })
+            .map(|(pid, legal_moves)| record::InvalidMove {
+                player: pid as u32,
+                kind: record::InvalidMoveKind::IllegalMove,
+                player_move: self.players[pid].move_.to_string(),
+                legal_moves,
+            })
+            .collect();
+        if !invalid_moves.is_empty() {
+            return self.end_game_if_invalid_moves(2, invalid_moves);
+        }
 
-            /* 3.1 Check if moves were valid */
-            let player0_valid_moves = valid_moves(
-                &self.counter_orders.slice(),
-                &self.tome_spells.slice(),
-                &player0.spells.slice(),
-                &player0.stock,
-                player0.stock_id,
-                &self.cache,
-            );
-            let player1_valid_moves = valid_moves(
-                &self.counter_orders.slice(),
-                &self.tome_spells.slice(),
-                &player1.spells.slice(),
-                &player1.stock,
-                player1.stock_id,
-                &&self.cache,
+        /* 3.2 Update the state */
+        // For each player move
+        let mut orders_were_fullfilled = false;
+        let mut orders_to_remove_pos: [Option<usize>; 2] = [None, None];
+        let mut spells_were_learnt = false;
+        let mut spells_to_remove_pos: [Option<usize>; 2] = [None, None];
+        let mut spell_tax_payed: [Option<usize>; 2] = [None, None];
+
+        for (pid, player) in self.players.iter_mut().enumerate() {
+            match player.move_ {
+                Move::BREW(order_id) => {
+                    let fullfilled_order_pos =
+                        get_order_position(&self.counter_orders.slice(), order_id).unwrap();
+
+                    let fullfilled_order = self.counter_orders.get(fullfilled_order_pos);
+
+                    // Update the player's potion count
+                    player.brewed_potions_count += 1;
+
+                    // Update the player's rupees
+                    player.rupees += fullfilled_order.price as u32 + fullfilled_order.bonus as u32;
+
+                    // Update the player's ingredient stock
+                    brew_and_update_stock(&mut player.stock, &fullfilled_order.recipe);
+                    player.stock_id = self.cache.getStockId(&player.stock);
+
+                    // Save fullfilled orders so that I remove them later
+                    orders_were_fullfilled = true;
+                    orders_to_remove_pos[pid] = Some(fullfilled_order_pos);
+                }
+                Move::CAST(spell_id, times) => {
+                    let cast_spell_idx =
+                        get_spell_position(&player.spells.slice(), spell_id).unwrap();
+
+                    let cast_spell = player.spells.get_mut(cast_spell_idx);
+
+                    // Update the player's ingredient stock
+                    cast_and_update_stock(&mut player.stock, &cast_spell.recipe, times);
+                    player.stock_id = self.cache.getStockId(&player.stock);
+
+                    // Spell is now exhausted
+                    cast_spell.active = false;
+                }
+                Move::LEARN(spell_id) => {
+                    let learnt_spell_pos =
+                        get_spell_position(&self.tome_spells.slice(), spell_id).unwrap();
+
+                    let learnt_spell = self.tome_spells.get(learnt_spell_pos);
+
+                    // add the learnt spell to the player's spell
+                    let mut player_learnt_spell = learnt_spell.clone();
+                    player_learnt_spell.tax = 0;
+                    player.spells.push(player_learnt_spell);
+
+                    // pay the tax if needed
+                    player.stock[0] -= learnt_spell_pos as i8;
+                    // and gain any tier-0 ingredient put on the spell
+                    if learnt_spell.tax > 0 {
+                        let empty_storage = 10
+                            - player.stock[0]
+                            - player.stock[1]
+                            - player.stock[2]
+                            - player.stock[3];
+                        player.stock[0] += cmp::min(learnt_spell.tax as i8, empty_storage);
+                    }
+                    player.stock_id = self.cache.getStockId(&player.stock);
+
+                    // Save learnt spells, so that I replace them later and deal with the tax
+                    spells_were_learnt = true;
+                    spells_to_remove_pos[pid] = Some(learnt_spell_pos);
+                    spell_tax_payed[pid] = Some(learnt_spell_pos);
+                }
+                Move::REST => {
+                    for spell in player.spells.slice_mut().iter_mut() {
+                        spell.active = true;
+                    }
+                }
+                Move::NONE | Move::WAIT => {}
+            }
+        }
+
+        /* Remove fullfilled orders and create new one in their place, and update bonus */
+        if orders_were_fullfilled == true {
+            update_counter_orders(
+                &mut self.counter_orders,
+                &mut self.queued_orders,
+                &mut self.plus_3_bonus_remaining,
+                &mut self.plus_1_bonus_remaining,
+                &orders_to_remove_pos,
+            )
+        }
+
+        /* Remove learnt spells and create new one in their place, and update tax */
+        if spells_were_learnt == true {
+            update_tome_spells(
+                &mut self.tome_spells,
+                &mut self.queued_spells,
+                &spells_to_remove_pos,
+                &spell_tax_payed,
             );
+        }
 
-            let is_move0_valid = player0_valid_moves.slice().contains(&player0.move_);
-            let is_move1_valid = player1_valid_moves.slice().contains(&player1.move_);
-
-            if !is_move0_valid && !is_move1_valid {
-                eprintln!(
-                    "[GAME] Player 0's move {} & Player 1's move {} are both invalid",
-                    &player0.move_.to_string(),
-                    &player1.move_.to_string()
-                );
-                self.active = false;
-                self.winners = Some((WinLossTie::Loss, WinLossTie::Loss));
-                return;
-            } else if is_move0_valid && !is_move1_valid {
-                eprintln!(
-                    "[GAME] Player 1's move {} is invalid",
-                    &player1.move_.to_string()
-                );
-                self.active = false;
+        /* 3.3 Check terminal condition */
+        let player0: &Player = &self.players[0];
+        let player1: &Player = &self.players[1];
+
+        if player0.brewed_potions_count == 6
+            || player1.brewed_potions_count == 6
+            || self.turn == 100
+            || (self.players[0].move_ == Move::WAIT && self.players[1].move_ == Move::WAIT)
+        {
+            self.active = false;
+
+            let score0 =
+                player0.rupees + (player0.stock[1] + player0.stock[2] + player0.stock[3]) as u32;
+            let score1 =
+                player1.rupees + (player1.stock[1] + player1.stock[2] + player1.stock[3]) as u32;
+
+            if score0 > score1 {
                 self.winners = Some((WinLossTie::Win, WinLossTie::Loss));
-                return;
-            } else if !is_move0_valid && is_move1_valid {
-                eprintln!(
-                    "[GAME] Player 0's move {} is invalid",
-                    &player0.move_.to_string()
-                );
-                self.active = false;
+            } else if score0 < score1 {
                 self.winners = Some((WinLossTie::Loss, WinLossTie::Win));
-                return;
+            } else {
+                self.winners = Some((WinLossTie::Tie, WinLossTie::Tie));
             }
+        }
 
-            /* 3.2 Update the state */
-            // For each player move
-            let mut orders_were_fullfilled = false;
-            let mut orders_to_remove_pos: [Option<usize>; 2] = [None, None];
-            let mut spells_were_learnt = false;
-            let mut spells_to_remove_pos: [Option<usize>; 2] = [None, None];
-            let mut spell_tax_payed: [Option<usize>; 2] = [None, None];
+        /* 3.3 Reinit moves */
+        self.players[0].move_ = Move::NONE;
+        self.players[1].move_ = Move::NONE;
+        self.turn += 1;
+        Ok(())
+    }
+}
 
-            for (pid, player) in self.players.iter_mut().enumerate() {
-                match player.move_ {
-                    Move::BREW(order_id) => {
-                        let fullfilled_order_pos =
-                            get_order_position(&self.counter_orders.slice(), order_id).unwrap();
+impl Game for WitchesBrewGame {
+    fn new() -> Self {
+        WitchesBrewGame::new_with_seed(thread_rng().gen())
+    }
 
-                        let fullfilled_order = self.counter_orders.get(fullfilled_order_pos);
+    fn new_with_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
 
-                        // Update the player's potion count
-                        player.brewed_potions_count += 1;
+        /* Init cache */
+        let cache = Cache::new();
 
-                        // Update the player's rupees
-                        player.rupees +=
-                            fullfilled_order.price as u32 + fullfilled_order.bonus as u32;
+        /* Create Player's basic spells */
+        let mut player_spells: StackVector<Spell, EXISTING_SPELL_COUNT> = StackVector::new();
+        let basic_spells = get_basic_spells();
+        for i in 0..4 {
+            player_spells.push(basic_spells[i]);
+        }
 
-                        // Update the player's ingredient stock
-                        brew_and_update_stock(&mut player.stock, &fullfilled_order.recipe);
-                        player.stock_id = self.cache.getStockId(&player.stock);
+        /* Create Players */
+        let player = Player {
+            move_: Move::NONE,
+            stock: [3, 0, 0, 0],
+            stock_id: cache.getStockId(&[3, 0, 0, 0]),
+            rupees: 0,
+            brewed_potions_count: 0,
+            spells: player_spells,
+        };
 
-                        // Save fullfilled orders so that I remove them later
-                        orders_were_fullfilled = true;
-                        orders_to_remove_pos[pid] = Some(fullfilled_order_pos);
-                    }
-                    Move::CAST(spell_id, times) => {
-                        let cast_spell_idx =
-                            get_spell_position(&player.spells.slice(), spell_id).unwrap();
+        /* Create orders */
+        let mut all_orders = get_all_orders();
+        all_orders.shuffle(&mut rng);
 
-                        let cast_spell = player.spells.get_mut(cast_spell_idx);
+        let mut counter_orders: StackVector<Order, 5> = StackVector::new();
+        for _ in 0..5 {
+            counter_orders.push(all_orders.pop().unwrap());
+        }
+        counter_orders.get_mut(0).bonus = 3;
+        counter_orders.get_mut(1).bonus = 1;
 
-                        // Update the player's ingredient stock
-                        cast_and_update_stock(&mut player.stock, &cast_spell.recipe, times);
-                        player.stock_id = self.cache.getStockId(&player.stock);
+        /* Create tome spells */
+        let mut queued_spells = get_learnable_tome_spells();
+        queued_spells.shuffle(&mut rng);
 
-                        // Spell is now exhausted
-                        cast_spell.active = false;
-                    }
-                    Move::LEARN(spell_id) => {
-                        let learnt_spell_pos =
-                            get_spell_position(&self.tome_spells.slice(), spell_id).unwrap();
-
-                        let learnt_spell = self.tome_spells.get(learnt_spell_pos);
-
-                        // add the learnt spell to the player's spell
-                        let mut player_learnt_spell = learnt_spell.clone();
-                        player_learnt_spell.tax = 0;
-                        player.spells.add(player_learnt_spell);
-
-                        // pay the tax if needed
-                        player.stock[0] -= learnt_spell_pos as i8;
-                        // and gain any tier-0 ingredient put on the spell
-                        if learnt_spell.tax > 0 {
-                            let empty_storage = 10
-                                - player.stock[0]
-                                - player.stock[1]
-                                - player.stock[2]
-                                - player.stock[3];
-                            player.stock[0] += cmp::min(learnt_spell.tax as i8, empty_storage);
-                        }
-                        player.stock_id = self.cache.getStockId(&player.stock);
+        let mut tome_spells: StackVector<Spell, 6> = StackVector::new();
+        for _ in 0..6 {
+            tome_spells.push(queued_spells.pop().unwrap());
+        }
+
+        let game = WitchesBrewGame {
+            players: [player.clone(), player.clone()],
+
+            queued_orders: all_orders,
+            counter_orders: counter_orders,
+            plus_3_bonus_remaining: 4,
+            plus_1_bonus_remaining: 4,
+
+            queued_spells: queued_spells,
+            tome_spells: tome_spells,
+
+            active: true,
+            active_player: 0,
+            turn: 0,
+            winners: None,
+
+            cache,
+        };
+
+        game
+    }
+
+    fn new_from_position(position: &str, seed: u64) -> Result<Self, String> {
+        /*
+            [position] starts with a line "<turn> <potions brewed by player 0> <potions brewed by player 1>",
+            followed by the turn input that player 0 receives on CodinGame.
+            Orders & spells are identified by their recipe, so the ids may be the ones of a CodinGame game.
+            The orders & tome spells that are not in the position are shuffled with [seed] to make the queues.
+        */
+        let mut lines = position.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+
+        let header = parse_position_numbers(lines.next(), 3)?;
+        if header[0] < 0 || header[0] >= 100 {
+            return Err(format!("Invalid turn {}", header[0]));
+        }
+        // The game ends when a player brewed its 6th potion
+        if let Some(count) = header[1..].iter().find(|c| !(0..6).contains(*c)) {
+            return Err(format!("Invalid count of brewed potions {}", count));
+        }
+
+        let cache = Cache::new();
+
+        let mut counter_orders: StackVector<Order, 5> = StackVector::new();
+        let mut tome_spells: StackVector<Spell, 6> = StackVector::new();
+        let mut players_spells: [StackVector<Spell, EXISTING_SPELL_COUNT>; 2] =
+            [StackVector::new(), StackVector::new()];
+        let mut plus_3_bonus_remaining = 0;
+        let mut plus_1_bonus_remaining = 0;
+
+        let action_count = parse_position_numbers(lines.next(), 1)?[0];
+        for _ in 0..action_count {
+            let (action_type, numbers) = parse_position_action(lines.next())?;
+            let mut recipe: Recipe = [0; 4];
+            for (ingredient, n) in recipe.iter_mut().zip(numbers[0..4].iter()) {
+                *ingredient =
+                    i8::try_from(*n).map_err(|_| format!("Invalid recipe {:?}", &numbers[0..4]))?;
+            }
 
-                        // Save learnt spells, so that I replace them later and deal with the tax
-                        spells_were_learnt = true;
-                        spells_to_remove_pos[pid] = Some(learnt_spell_pos);
-                        spell_tax_payed[pid] = Some(learnt_spell_pos);
+            match action_type.as_str() {
+                "BREW" => {
+                    let mut order = find_order(&recipe)
+                        .ok_or(format!("No order has the recipe {:?}", recipe))?;
+                    // Each bonus is given to the first 4 orders brewed
+                    order.bonus = match numbers[5] {
+                        0 | 1 | 3 => numbers[5] as u8,
+                        b => return Err(format!("Invalid order bonus {}", b)),
+                    };
+                    if !(0..=4).contains(&numbers[6]) {
+                        return Err(format!("Invalid remaining bonus count {}", numbers[6]));
                     }
-                    Move::REST => {
-                        for spell in player.spells.slice_mut().iter_mut() {
-                            spell.active = true;
-                        }
+                    match order.bonus {
+                        3 => plus_3_bonus_remaining = numbers[6] as u8,
+                        1 => plus_1_bonus_remaining = numbers[6] as u8,
+                        _ => {}
+                    }
+                    if counter_orders.len() == 5 {
+                        return Err("There are more than 5 orders".to_string());
+                    }
+                    if get_order_position(counter_orders.slice(), order.id).is_some() {
+                        return Err(format!("Order {:?} is listed twice", recipe));
+                    }
+                    counter_orders.push(order);
+                }
+                "LEARN" => {
+                    let mut spell = find_spell(&recipe)
+                        .ok_or(format!("No spell has the recipe {:?}", recipe))?;
+                    spell.tax = u8::try_from(numbers[6])
+                        .map_err(|_| format!("Invalid tax {}", numbers[6]))?;
+                    if tome_spells.len() == 6 {
+                        return Err("There are more than 6 tome spells".to_string());
                     }
-                    Move::NONE | Move::WAIT => {}
+                    if get_spell_position(tome_spells.slice(), spell.id).is_some() {
+                        return Err(format!("Tome spell {:?} is listed twice", recipe));
+                    }
+                    tome_spells.push(spell);
+                }
+                "CAST" | "OPPONENT_CAST" => {
+                    let mut spell = find_spell(&recipe)
+                        .ok_or(format!("No spell has the recipe {:?}", recipe))?;
+                    spell.active = parse_position_flag(numbers[7], "castable")?;
+                    let p_id = match action_type.as_str() {
+                        "CAST" => 0,
+                        _ => 1,
+                    };
+                    if get_spell_position(players_spells[p_id].slice(), spell.id).is_some() {
+                        return Err(format!("Spell {:?} is listed twice", recipe));
+                    }
+                    players_spells[p_id].push(spell);
                 }
+                t => return Err(format!("Invalid action type {}", t)),
             }
+        }
 
-            /* Remove fullfilled orders and create new one in their place, and update bonus */
-            if orders_were_fullfilled == true {
-                update_counter_orders(
-                    &mut self.counter_orders,
-                    &mut self.queued_orders,
-                    &mut self.plus_3_bonus_remaining,
-                    &mut self.plus_1_bonus_remaining,
-                    &orders_to_remove_pos,
-                )
-            }
+        let (stock0, rupees0) = parse_position_stock(lines.next())?;
+        let (stock1, rupees1) = parse_position_stock(lines.next())?;
 
-            /* Remove learnt spells and create new one in their place, and update tax */
-            if spells_were_learnt == true {
-                update_tome_spells(
-                    &mut self.tome_spells,
-                    &mut self.queued_spells,
-                    &spells_to_remove_pos,
-                    &spell_tax_payed,
-                );
-            }
+        let [spells0, spells1] = players_spells;
+        let players = [
+            (stock0, rupees0, spells0, header[1]),
+            (stock1, rupees1, spells1, header[2]),
+        ]
+        .map(|(stock, rupees, spells, brewed_potions_count)| Player {
+            move_: Move::NONE,
+            stock,
+            stock_id: cache.getStockId(&stock),
+            spells,
+            rupees,
+            brewed_potions_count: brewed_potions_count as u8,
+        });
+
+        /* The queues are made of what is neither on the counter, nor in the tome, nor learnt */
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut queued_orders: Vec<Order> = get_all_orders()
+            .into_iter()
+            .filter(|o| get_order_position(counter_orders.slice(), o.id).is_none())
+            .collect();
+        queued_orders.shuffle(&mut rng);
+
+        let mut queued_spells: Vec<Spell> = get_learnable_tome_spells()
+            .into_iter()
+            .filter(|s| {
+                get_spell_position(tome_spells.slice(), s.id).is_none()
+                    && players
+                        .iter()
+                        .all(|p| get_spell_position(p.spells.slice(), s.id).is_none())
+            })
+            .collect();
+        queued_spells.shuffle(&mut rng);
+
+        Ok(WitchesBrewGame {
+            players,
+
+            queued_orders,
+            counter_orders,
+            plus_3_bonus_remaining,
+            plus_1_bonus_remaining,
+
+            queued_spells,
+            tome_spells,
 
-            /* 3.3 Check terminal condition */
-            let player0: &Player = &self.players[0];
-            let player1: &Player = &self.players[1];
-
-            if player0.brewed_potions_count == 6
-                || player1.brewed_potions_count == 6
-                || self.turn == 100
-                || (self.players[0].move_ == Move::WAIT && self.players[1].move_ == Move::WAIT)
-            {
-                self.active = false;
-
-                let score0 = player0.rupees
-                    + (player0.stock[1] + player0.stock[2] + player0.stock[3]) as u32;
-                let score1 = player1.rupees
-                    + (player1.stock[1] + player1.stock[2] + player1.stock[3]) as u32;
-
-                if score0 > score1 {
-                    self.winners = Some((WinLossTie::Win, WinLossTie::Loss));
-                } else if score0 < score1 {
-                    self.winners = Some((WinLossTie::Loss, WinLossTie::Win));
-                } else {
-                    self.winners = Some((WinLossTie::Tie, WinLossTie::Tie));
-                }
+            active: true,
+            active_player: 0,
+            turn: header[0] as u8,
+            winners: None,
+
+            cache,
+        })
+    }
+
+    fn turn(&self) -> Option<Message> {
+        // If game is over, return None
+        if self.active == false {
+            return None;
+        }
+
+        Some(self.message(self.active_player))
+    }
+
+    fn play(&mut self, msg: String) {
+        /* (1) Parse move. If it can't be parsed, the player loses */
+        let _move = match Move::parse_move(msg.as_str()) {
+            Some(_move) => _move,
+            None => {
+                let parse_error = self.parse_error(self.active_player as usize, &msg);
+                let _ = self.end_game_if_invalid_moves(2, vec![parse_error]);
+                return;
             }
+        };
+
+        /* (2) Record the move */
+        self.players[self.active_player as usize].move_ = _move;
 
-            /* 3.3 Reinit moves */
-            self.players[0].move_ = Move::NONE;
-            self.players[1].move_ = Move::NONE;
-            self.turn += 1;
+        /* (3) If it's player'1 turn, i.e. both players have played =>  update the state */
+        if self.active_player == 1 {
+            let _ = self.resolve_moves();
         }
         self.active_player = (self.active_player + 1) % 2;
     }
 
+    fn simultaneous_turn(&self) -> Option<Vec<Message>> {
+        // If game is over, return None
+        if !self.active {
+            return None;
+        }
+
+        Some(vec![self.message(0), self.message(1)])
+    }
+
+    fn play_simultaneous(&mut self, moves: Vec<String>) -> Result<(), Vec<record::InvalidMove>> {
+        /* (1) Parse the moves. The players whose move can't be parsed lose */
+        let mut parse_errors = Vec::new();
+        for (pid, msg) in moves.iter().enumerate() {
+            match Move::parse_move(msg.as_str()) {
+                Some(_move) => self.players[pid].move_ = _move,
+                None => parse_errors.push(self.parse_error(pid, msg)),
+            }
+        }
+        self.end_game_if_invalid_moves(2, parse_errors)?;
+
+        /* (2) Update the state. Illegal moves are reported as the players sent them */
+        self.resolve_moves().map_err(|mut invalid_moves| {
+            for invalid_move in invalid_moves.iter_mut() {
+                invalid_move.player_move = moves[invalid_move.player as usize].clone();
+            }
+            invalid_moves
+        })
+    }
+
     fn winners(&self) -> Option<Vec<WinLossTie>> {
         match &self.winners {
             Some(w) => Some(vec![w.0, w.1]),
@@ -1072,6 +1321,18 @@ impl Game for WitchesBrewGame {
         }
     }
 
+    fn scores(&self) -> Option<Vec<f64>> {
+        match self.winners {
+            Some(_) => Some(
+                self.players
+                    .iter()
+                    .map(|p| (p.rupees + (p.stock[1] + p.stock[2] + p.stock[3]) as u32) as f64)
+                    .collect(),
+            ),
+            None => None,
+        }
+    }
+
     fn get_state(&self) -> record::GameState {
         let mut state: HashMap<String, String> = HashMap::new();
         state.insert(String::from("Turn"), self.turn.to_string());
@@ -1237,6 +1498,11 @@ impl Game for WitchesBrewGame {
     fn get_board_representation() -> Option<record::BoardRepresentation> {
         None
     }
+
+    fn end_game(&mut self, players_status: Vec<WinLossTie>) {
+        self.active = false;
+        self.winners = Some((players_status[0], players_status[1]));
+    }
 }
 
 #[cfg(test)]
@@ -1247,18 +1513,22 @@ mod tests {
 
     #[test]
     fn test_parse_move() {
-        assert_eq!(Move::parse_move("WAIT"), Move::WAIT);
-        assert_eq!(Move::parse_move("REST"), Move::REST);
-        assert_eq!(Move::parse_move("BREW 1"), Move::BREW(1));
-        assert_eq!(Move::parse_move("BREW 10"), Move::BREW(10));
-        assert_eq!(Move::parse_move("LEARN 2"), Move::LEARN(2));
-        assert_eq!(Move::parse_move("LEARN 20"), Move::LEARN(20));
-        assert_eq!(Move::parse_move("CAST 3"), Move::CAST(3, 1));
-        assert_eq!(Move::parse_move("CAST 30"), Move::CAST(30, 1));
-        assert_eq!(Move::parse_move("CAST 3 2"), Move::CAST(3, 2));
-        assert_eq!(Move::parse_move("CAST 3 12"), Move::CAST(3, 12));
-        assert_eq!(Move::parse_move("CAST 30 2"), Move::CAST(30, 2));
-        assert_eq!(Move::parse_move("CAST 30 12"), Move::CAST(30, 12));
+        assert_eq!(Move::parse_move("WAIT"), Some(Move::WAIT));
+        assert_eq!(Move::parse_move("REST"), Some(Move::REST));
+        assert_eq!(Move::parse_move("BREW 1"), Some(Move::BREW(1)));
+        assert_eq!(Move::parse_move("BREW 10"), Some(Move::BREW(10)));
+        assert_eq!(Move::parse_move("LEARN 2"), Some(Move::LEARN(2)));
+        assert_eq!(Move::parse_move("LEARN 20"), Some(Move::LEARN(20)));
+        assert_eq!(Move::parse_move("CAST 3"), Some(Move::CAST(3, 1)));
+        assert_eq!(Move::parse_move("CAST 30"), Some(Move::CAST(30, 1)));
+        assert_eq!(Move::parse_move("CAST 3 2"), Some(Move::CAST(3, 2)));
+        assert_eq!(Move::parse_move("CAST 3 12"), Some(Move::CAST(3, 12)));
+        assert_eq!(Move::parse_move("CAST 30 2"), Some(Move::CAST(30, 2)));
+        assert_eq!(Move::parse_move("CAST 30 12"), Some(Move::CAST(30, 12)));
+        assert_eq!(Move::parse_move("CAST"), None);
+        assert_eq!(Move::parse_move("CAST x"), None);
+        assert_eq!(Move::parse_move("BREW 1 2"), None);
+        assert_eq!(Move::parse_move("GO"), None);
     }
 
     #[test]
@@ -1935,6 +2205,25 @@ mod tests {
         assert_vec_eq!(vm.slice(), &expected_moves);
     }
 
+    #[test]
+    fn test_new_with_seed_is_reproducible() {
+        // The first turn's message contains all orders & tome spells
+        let game1 = WitchesBrewGame::new_with_seed(42);
+        let game2 = WitchesBrewGame::new_with_seed(42);
+        assert_eq!(
+            game1.turn().unwrap().messages,
+            game2.turn().unwrap().messages
+        );
+
+        let queued_orders1: Vec<u32> = game1.queued_orders.iter().map(|o| o.id).collect();
+        let queued_orders2: Vec<u32> = game2.queued_orders.iter().map(|o| o.id).collect();
+        assert_eq!(queued_orders1, queued_orders2);
+
+        let queued_spells1: Vec<u32> = game1.queued_spells.iter().map(|s| s.id).collect();
+        let queued_spells2: Vec<u32> = game2.queued_spells.iter().map(|s| s.id).collect();
+        assert_eq!(queued_spells1, queued_spells2);
+    }
+
     #[test]
     fn test_2_players_get_the_same_tax_reward() {
         let cache = Cache::new();
@@ -1948,7 +2237,7 @@ mod tests {
                 let mut spells: StackVector<Spell, EXISTING_SPELL_COUNT> = StackVector::new();
                 let basic_spells = get_basic_spells();
                 for i in 0..4 {
-                    spells.add(basic_spells[i]);
+                    spells.push(basic_spells[i]);
                 }
                 spells
             },
@@ -1958,22 +2247,22 @@ mod tests {
         let players: [Player; 2] = [player.clone(), player.clone()];
 
         let mut tome_spells: StackVector<Spell, 6> = StackVector::new();
-        tome_spells.add(find_spell(&[2, 1, 0, 0]).unwrap());
-        tome_spells.add(find_spell(&[2, -3, 2, 0]).unwrap());
-        tome_spells.add(find_spell(&[0, 2, -2, 1]).unwrap());
-        tome_spells.add(find_spell(&[0, 0, 1, 0]).unwrap());
-        tome_spells.add(find_spell(&[2, 2, 0, -1]).unwrap());
-        tome_spells.add(find_spell(&[1, 1, 0, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, 1, 0, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, -3, 2, 0]).unwrap());
+        tome_spells.push(find_spell(&[0, 2, -2, 1]).unwrap());
+        tome_spells.push(find_spell(&[0, 0, 1, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, 2, 0, -1]).unwrap());
+        tome_spells.push(find_spell(&[1, 1, 0, 0]).unwrap());
         tome_spells.get_mut(0).tax = 3;
 
         let learned_spell_id = tome_spells.get(0).id;
 
         let mut counter_orders: StackVector<Order, 5> = StackVector::new();
-        counter_orders.add(find_order(&[-3, -1, -1, -1]).unwrap());
-        counter_orders.add(find_order(&[0, 0, -5, 0]).unwrap());
-        counter_orders.add(find_order(&[0, -2, 0, -2]).unwrap());
-        counter_orders.add(find_order(&[0, -5, 0, 0]).unwrap());
-        counter_orders.add(find_order(&[0, 0, -3, -2]).unwrap());
+        counter_orders.push(find_order(&[-3, -1, -1, -1]).unwrap());
+        counter_orders.push(find_order(&[0, 0, -5, 0]).unwrap());
+        counter_orders.push(find_order(&[0, -2, 0, -2]).unwrap());
+        counter_orders.push(find_order(&[0, -5, 0, 0]).unwrap());
+        counter_orders.push(find_order(&[0, 0, -3, -2]).unwrap());
 
         let mut game = new_game_with_params(players, tome_spells, counter_orders, 3, 3, 0);
 
@@ -2002,7 +2291,7 @@ mod tests {
                 let mut spells: StackVector<Spell, EXISTING_SPELL_COUNT> = StackVector::new();
                 let basic_spells = get_basic_spells();
                 for i in 0..4 {
-                    spells.add(basic_spells[i]);
+                    spells.push(basic_spells[i]);
                 }
                 spells
             },
@@ -2018,7 +2307,7 @@ mod tests {
                 let mut spells: StackVector<Spell, EXISTING_SPELL_COUNT> = StackVector::new();
                 let basic_spells = get_basic_spells();
                 for i in 0..4 {
-                    spells.add(basic_spells[i]);
+                    spells.push(basic_spells[i]);
                 }
                 spells
             },
@@ -2028,22 +2317,22 @@ mod tests {
         let players: [Player; 2] = [player0, player1];
 
         let mut tome_spells: StackVector<Spell, 6> = StackVector::new();
-        tome_spells.add(find_spell(&[2, 1, 0, 0]).unwrap());
-        tome_spells.add(find_spell(&[2, -3, 2, 0]).unwrap());
-        tome_spells.add(find_spell(&[0, 2, -2, 1]).unwrap());
-        tome_spells.add(find_spell(&[0, 0, 1, 0]).unwrap());
-        tome_spells.add(find_spell(&[2, 2, 0, -1]).unwrap());
-        tome_spells.add(find_spell(&[1, 1, 0, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, 1, 0, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, -3, 2, 0]).unwrap());
+        tome_spells.push(find_spell(&[0, 2, -2, 1]).unwrap());
+        tome_spells.push(find_spell(&[0, 0, 1, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, 2, 0, -1]).unwrap());
+        tome_spells.push(find_spell(&[1, 1, 0, 0]).unwrap());
         tome_spells.get_mut(0).tax = 3;
 
         let learned_spell_id = tome_spells.get(0).id;
 
         let mut counter_orders: StackVector<Order, 5> = StackVector::new();
-        counter_orders.add(find_order(&[-3, -1, -1, -1]).unwrap());
-        counter_orders.add(find_order(&[0, 0, -5, 0]).unwrap());
-        counter_orders.add(find_order(&[0, -2, 0, -2]).unwrap());
-        counter_orders.add(find_order(&[0, -5, 0, 0]).unwrap());
-        counter_orders.add(find_order(&[0, 0, -3, -2]).unwrap());
+        counter_orders.push(find_order(&[-3, -1, -1, -1]).unwrap());
+        counter_orders.push(find_order(&[0, 0, -5, 0]).unwrap());
+        counter_orders.push(find_order(&[0, -2, 0, -2]).unwrap());
+        counter_orders.push(find_order(&[0, -5, 0, 0]).unwrap());
+        counter_orders.push(find_order(&[0, 0, -3, -2]).unwrap());
 
         let mut game = new_game_with_params(players, tome_spells, counter_orders, 3, 3, 0);
 
@@ -2071,7 +2360,7 @@ mod tests {
                 let mut spells: StackVector<Spell, EXISTING_SPELL_COUNT> = StackVector::new();
                 let basic_spells = get_basic_spells();
                 for i in 0..4 {
-                    spells.add(basic_spells[i]);
+                    spells.push(basic_spells[i]);
                 }
                 spells
             },
@@ -2081,19 +2370,19 @@ mod tests {
         let players: [Player; 2] = [player.clone(), player.clone()];
 
         let mut tome_spells: StackVector<Spell, 6> = StackVector::new();
-        tome_spells.add(find_spell(&[2, 1, 0, 0]).unwrap());
-        tome_spells.add(find_spell(&[2, -3, 2, 0]).unwrap());
-        tome_spells.add(find_spell(&[0, 2, -2, 1]).unwrap());
-        tome_spells.add(find_spell(&[0, 0, 1, 0]).unwrap());
-        tome_spells.add(find_spell(&[2, 2, 0, -1]).unwrap());
-        tome_spells.add(find_spell(&[1, 1, 0, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, 1, 0, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, -3, 2, 0]).unwrap());
+        tome_spells.push(find_spell(&[0, 2, -2, 1]).unwrap());
+        tome_spells.push(find_spell(&[0, 0, 1, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, 2, 0, -1]).unwrap());
+        tome_spells.push(find_spell(&[1, 1, 0, 0]).unwrap());
 
         let mut counter_orders: StackVector<Order, 5> = StackVector::new();
-        counter_orders.add(find_order(&[-3, -1, -1, -1]).unwrap());
-        counter_orders.add(find_order(&[0, 0, -5, 0]).unwrap());
-        counter_orders.add(find_order(&[0, -2, 0, -2]).unwrap());
-        counter_orders.add(find_order(&[0, -5, 0, 0]).unwrap());
-        counter_orders.add(find_order(&[0, 0, -3, -2]).unwrap());
+        counter_orders.push(find_order(&[-3, -1, -1, -1]).unwrap());
+        counter_orders.push(find_order(&[0, 0, -5, 0]).unwrap());
+        counter_orders.push(find_order(&[0, -2, 0, -2]).unwrap());
+        counter_orders.push(find_order(&[0, -5, 0, 0]).unwrap());
+        counter_orders.push(find_order(&[0, 0, -3, -2]).unwrap());
         counter_orders.get_mut(0).bonus = 3;
         counter_orders.get_mut(1).bonus = 1;
         let earned_rupees = counter_orders.get(0).price;
@@ -2128,7 +2417,7 @@ mod tests {
                 let mut spells: StackVector<Spell, EXISTING_SPELL_COUNT> = StackVector::new();
                 let basic_spells = get_basic_spells();
                 for i in 0..4 {
-                    spells.add(basic_spells[i]);
+                    spells.push(basic_spells[i]);
                 }
                 spells
             },
@@ -2138,19 +2427,19 @@ mod tests {
         let players: [Player; 2] = [player.clone(), player.clone()];
 
         let mut tome_spells: StackVector<Spell, 6> = StackVector::new();
-        tome_spells.add(find_spell(&[2, 1, 0, 0]).unwrap());
-        tome_spells.add(find_spell(&[2, -3, 2, 0]).unwrap());
-        tome_spells.add(find_spell(&[0, 2, -2, 1]).unwrap());
-        tome_spells.add(find_spell(&[0, 0, 1, 0]).unwrap());
-        tome_spells.add(find_spell(&[2, 2, 0, -1]).unwrap());
-        tome_spells.add(find_spell(&[1, 1, 0, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, 1, 0, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, -3, 2, 0]).unwrap());
+        tome_spells.push(find_spell(&[0, 2, -2, 1]).unwrap());
+        tome_spells.push(find_spell(&[0, 0, 1, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, 2, 0, -1]).unwrap());
+        tome_spells.push(find_spell(&[1, 1, 0, 0]).unwrap());
 
         let mut counter_orders: StackVector<Order, 5> = StackVector::new();
-        counter_orders.add(find_order(&[-3, -1, -1, -1]).unwrap());
-        counter_orders.add(find_order(&[0, 0, -5, 0]).unwrap());
-        counter_orders.add(find_order(&[0, -2, 0, -2]).unwrap());
-        counter_orders.add(find_order(&[0, -5, 0, 0]).unwrap());
-        counter_orders.add(find_order(&[0, 0, -3, -2]).unwrap());
+        counter_orders.push(find_order(&[-3, -1, -1, -1]).unwrap());
+        counter_orders.push(find_order(&[0, 0, -5, 0]).unwrap());
+        counter_orders.push(find_order(&[0, -2, 0, -2]).unwrap());
+        counter_orders.push(find_order(&[0, -5, 0, 0]).unwrap());
+        counter_orders.push(find_order(&[0, 0, -3, -2]).unwrap());
         counter_orders.get_mut(0).bonus = 3;
         counter_orders.get_mut(1).bonus = 1;
         let earned_rupees = counter_orders.get(0).price;
@@ -2187,7 +2476,7 @@ mod tests {
                 let mut spells: StackVector<Spell, EXISTING_SPELL_COUNT> = StackVector::new();
                 let basic_spells = get_basic_spells();
                 for i in 0..4 {
-                    spells.add(basic_spells[i]);
+                    spells.push(basic_spells[i]);
                 }
                 spells
             },
@@ -2197,19 +2486,19 @@ mod tests {
         let players: [Player; 2] = [player.clone(), player.clone()];
 
         let mut tome_spells: StackVector<Spell, 6> = StackVector::new();
-        tome_spells.add(find_spell(&[2, 1, 0, 0]).unwrap());
-        tome_spells.add(find_spell(&[2, -3, 2, 0]).unwrap());
-        tome_spells.add(find_spell(&[0, 2, -2, 1]).unwrap());
-        tome_spells.add(find_spell(&[0, 0, 1, 0]).unwrap());
-        tome_spells.add(find_spell(&[2, 2, 0, -1]).unwrap());
-        tome_spells.add(find_spell(&[1, 1, 0, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, 1, 0, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, -3, 2, 0]).unwrap());
+        tome_spells.push(find_spell(&[0, 2, -2, 1]).unwrap());
+        tome_spells.push(find_spell(&[0, 0, 1, 0]).unwrap());
+        tome_spells.push(find_spell(&[2, 2, 0, -1]).unwrap());
+        tome_spells.push(find_spell(&[1, 1, 0, 0]).unwrap());
 
         let mut counter_orders: StackVector<Order, 5> = StackVector::new();
-        counter_orders.add(find_order(&[-3, -1, -1, -1]).unwrap());
-        counter_orders.add(find_order(&[0, 0, -5, 0]).unwrap());
-        counter_orders.add(find_order(&[0, -2, 0, -2]).unwrap());
-        counter_orders.add(find_order(&[0, -5, 0, 0]).unwrap());
-        counter_orders.add(find_order(&[0, 0, -3, -2]).unwrap());
+        counter_orders.push(find_order(&[-3, -1, -1, -1]).unwrap());
+        counter_orders.push(find_order(&[0, 0, -5, 0]).unwrap());
+        counter_orders.push(find_order(&[0, -2, 0, -2]).unwrap());
+        counter_orders.push(find_order(&[0, -5, 0, 0]).unwrap());
+        counter_orders.push(find_order(&[0, 0, -3, -2]).unwrap());
         counter_orders.get_mut(0).bonus = 3;
         counter_orders.get_mut(1).bonus = 1;
         let earned_rupees0 = counter_orders.get(0).price;
@@ -2245,7 +2534,7 @@ mod tests {
         for i in 0..5 {
             let order = queued_orders.pop().unwrap();
             counter_orders_ids[i] = order.id;
-            counter_orders.add(order);
+            counter_orders.push(order);
         }
 
         counter_orders.get_mut(0).bonus = 3;
@@ -2285,7 +2574,7 @@ mod tests {
         for i in 0..5 {
             let order = queued_orders.pop().unwrap();
             counter_orders_ids[i] = order.id;
-            counter_orders.add(order);
+            counter_orders.push(order);
         }
 
         counter_orders.get_mut(0).bonus = 3;
@@ -2323,7 +2612,7 @@ mod tests {
         for i in 0..5 {
             let order = queued_orders.pop().unwrap();
             counter_orders_ids[i] = order.id;
-            counter_orders.add(order);
+            counter_orders.push(order);
         }
 
         counter_orders.get_mut(0).bonus = 3;
@@ -2364,7 +2653,7 @@ mod tests {
         for i in 0..5 {
             let order = queued_orders.pop().unwrap();
             counter_orders_ids[i] = order.id;
-            counter_orders.add(order);
+            counter_orders.push(order);
         }
 
         counter_orders.get_mut(0).bonus = 3;
@@ -2403,7 +2692,7 @@ mod tests {
         for i in 0..5 {
             let order = queued_orders.pop().unwrap();
             counter_orders_ids[i] = order.id;
-            counter_orders.add(order);
+            counter_orders.push(order);
         }
 
         counter_orders.get_mut(0).bonus = 3;
@@ -2443,7 +2732,7 @@ mod tests {
         for i in 0..3 {
             let order = queued_orders.pop().unwrap();
             counter_orders_ids[i] = order.id;
-            counter_orders.add(order);
+            counter_orders.push(order);
         }
 
         queued_orders.clear();
@@ -2483,7 +2772,7 @@ mod tests {
         for i in 0..2 {
             let order = queued_orders.pop().unwrap();
             counter_orders_ids[i] = order.id;
-            counter_orders.add(order);
+            counter_orders.push(order);
         }
 
         queued_orders.clear();
@@ -2520,7 +2809,7 @@ mod tests {
         for i in 0..2 {
             let spell = queued_spells.pop().unwrap();
             tome_spells_ids[i] = spell.id;
-            tome_spells.add(spell);
+            tome_spells.push(spell);
         }
 
         queued_spells.clear();
@@ -2548,7 +2837,7 @@ mod tests {
         for i in 0..3 {
             let spell = queued_spells.pop().unwrap();
             tome_spells_ids[i] = spell.id;
-            tome_spells.add(spell);
+            tome_spells.push(spell);
         }
 
         queued_spells.clear();
@@ -2566,4 +2855,183 @@ mod tests {
         assert_eq!(tome_spells.len(), 1);
         assert_eq!(tome_spells.get(0).tax, 2);
     }
+
+    #[test]
+    fn test_new_from_position_same_input_as_original_game() {
+        let mut game = WitchesBrewGame::new_with_seed(5);
+
+        // Both players learn the first tome spell, then cast their first basic spell
+        let learnt_spell_id = game.tome_spells.get(0).id;
+        for _ in 0..2 {
+            game.play(format!("LEARN {}", learnt_spell_id));
+        }
+        for _ in 0..2 {
+            game.play(String::from("CAST 42"));
+        }
+
+        let mut position = vec![String::from("2 0 0")];
+        position.extend(game.turn().unwrap().messages);
+        let mut game_from_position =
+            WitchesBrewGame::new_from_position(&position.join("\n"), 0).unwrap();
+
+        assert_eq!(
+            game_from_position.turn().unwrap().messages,
+            game.turn().unwrap().messages
+        );
+
+        // Both games continue identically, as long as no order or tome spell is taken
+        for player_move in ["CAST 43", "REST", "REST", "CAST 43"] {
+            game.play(String::from(player_move));
+            game_from_position.play(String::from(player_move));
+            assert_eq!(
+                game_from_position.turn().unwrap().messages,
+                game.turn().unwrap().messages
+            );
+        }
+    }
+
+    #[test]
+    fn test_simultaneous_turn_same_as_sequential_turns() {
+        let mut game = WitchesBrewGame::new_with_seed(5);
+        let mut simultaneous_game = WitchesBrewGame::new_with_seed(5);
+
+        let learnt_spell_id = game.tome_spells.get(1).id;
+        for moves in [
+            [
+                format!("LEARN {}", learnt_spell_id),
+                String::from("CAST 42"),
+            ],
+            [String::from("CAST 43"), String::from("REST")],
+        ] {
+            let messages: Vec<Vec<String>> = simultaneous_game
+                .simultaneous_turn()
+                .unwrap()
+                .into_iter()
+                .map(|m| m.messages)
+                .collect();
+            let mut sequential_messages = Vec::new();
+            for player_move in moves.iter() {
+                sequential_messages.push(game.turn().unwrap().messages);
+                game.play(player_move.clone());
+            }
+            assert_eq!(messages, sequential_messages);
+
+            simultaneous_game.play_simultaneous(moves.to_vec()).unwrap();
+            assert_eq!(simultaneous_game.get_state().state, game.get_state().state);
+        }
+        assert!(game.winners().is_none());
+    }
+
+    #[test]
+    fn test_play_simultaneous_reports_invalid_moves() {
+        let mut game = WitchesBrewGame::new_with_seed(5);
+        let invalid_moves = game
+            .play_simultaneous(vec![String::from("BREW 9999"), String::from("CAST 42")])
+            .unwrap_err();
+        assert_eq!(invalid_moves.len(), 1);
+        assert_eq!(invalid_moves[0].player, 0);
+        assert_eq!(invalid_moves[0].kind, record::InvalidMoveKind::IllegalMove);
+        assert_eq!(invalid_moves[0].player_move, "BREW 9999");
+        assert!(invalid_moves[0]
+            .legal_moves
+            .contains(&String::from("CAST 42")));
+        assert_eq!(
+            game.winners(),
+            Some(vec![WinLossTie::Loss, WinLossTie::Win])
+        );
+
+        // A move that can't be parsed loses, whatever the other move
+        let mut game = WitchesBrewGame::new_with_seed(5);
+        let invalid_moves = game
+            .play_simultaneous(vec![String::from("BREW 9999"), String::from("BREWING")])
+            .unwrap_err();
+        assert_eq!(invalid_moves.len(), 1);
+        assert_eq!(invalid_moves[0].player, 1);
+        assert_eq!(invalid_moves[0].kind, record::InvalidMoveKind::ParseError);
+        assert_eq!(
+            game.winners(),
+            Some(vec![WinLossTie::Win, WinLossTie::Loss])
+        );
+    }
+
+    #[test]
+    fn test_new_from_position_invalid() {
+        assert!(WitchesBrewGame::new_from_position("", 0).is_err());
+        assert!(
+            WitchesBrewGame::new_from_position("0 0 0\n1\n0 BREW -2 -2 0 0 6 0 0 0 0", 0).is_err()
+        );
+        assert!(WitchesBrewGame::new_from_position(
+            "0 0 0\n1\n0 BREW -9 0 0 0 6 0 0 0 0\n3 0 0 0 0\n3 0 0 0 0",
+            0
+        )
+        .is_err());
+
+        // The first turn input, whose fields are replaced : 5 orders, 6 tome spells, the spells of the players,
+        // then the stock & rupees of each player
+        let mut position = vec![String::from("0 0 0")];
+        position.extend(WitchesBrewGame::new_with_seed(5).turn().unwrap().messages);
+        let rupees_line = position.len() - 2;
+        let with_fields = |fields: &[(usize, usize, &str)]| {
+            let mut position = position.clone();
+            for (line, field, value) in fields.iter() {
+                let mut tokens: Vec<&str> = position[*line].split_whitespace().collect();
+                tokens[*field] = value;
+                position[*line] = tokens.join(" ");
+            }
+            WitchesBrewGame::new_from_position(&position.join("\n"), 0)
+        };
+        assert!(with_fields(&[]).is_ok());
+
+        assert!(with_fields(&[(0, 1, "6")]).is_err());
+        assert!(with_fields(&[(0, 2, "-1")]).is_err());
+        assert!(with_fields(&[(2, 2, "254")]).is_err());
+        assert!(with_fields(&[(2, 7, "2")]).is_err());
+        assert!(with_fields(&[(2, 7, "3"), (2, 8, "-1")]).is_err());
+        assert!(with_fields(&[(7, 8, "-1")]).is_err());
+        assert!(with_fields(&[(13, 9, "2")]).is_err());
+        assert!(with_fields(&[(rupees_line, 4, "-1")]).is_err());
+
+        let mut duplicate_order = position.clone();
+        duplicate_order[3] = duplicate_order[2].clone();
+        assert!(WitchesBrewGame::new_from_position(&duplicate_order.join("\n"), 0).is_err());
+    }
+
+    #[test]
+    fn test_recorded_runs_replay_identically() {
+        use common::replay;
+        use common::simulator::{self, PlayerPlayFunction, RunOptions};
+
+        let record_dir =
+            std::env::temp_dir().join(format!("witches_brew_replay_{}", std::process::id()));
+        std::fs::create_dir_all(&record_dir).unwrap();
+
+        let players = vec![
+            PlayerPlayFunction {
+                func: &crate::player_random::play,
+                params: None,
+                name: None,
+            };
+            2
+        ];
+        simulator::run(
+            WitchesBrewGame::new_with_seed,
+            &players,
+            3,
+            Some(record_dir.to_str().unwrap().to_string()),
+            false,
+            &RunOptions {
+                seed: Some(7),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        for record_file in std::fs::read_dir(&record_dir).unwrap() {
+            let divergences =
+                replay::replay_record(WitchesBrewGame::new_with_seed, record_file.unwrap().path())
+                    .unwrap();
+            assert!(divergences.is_empty(), "{:?}", divergences);
+        }
+        std::fs::remove_dir_all(&record_dir).unwrap();
+    }
 }
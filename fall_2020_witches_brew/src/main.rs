@@ -1,78 +1,85 @@
 mod game_witches_brew;
 mod scripts;
-use common::simulator;
-use common::simulator::PlayerPlayFunction;
-use common::Game;
+use common::cli::{self, Script};
+use common::simulator::{Bot, BotRegistry, PlayerPlayFunction, RunOptions, TimeLimits};
 use game_witches_brew::WitchesBrewGame;
 mod player_beam_4;
 mod player_beam_5;
 mod player_random;
 mod player_random_brewer;
-use std::time::Instant;
+use std::time::Duration;
 
-fn run() {
-    const STATS: bool = true;
-    const RECORD: bool = false;
-    const RUN_PERMUT: bool = false;
-    const RUNS: u32 = 10;
-
-    let record_path = "C:/Users/hhour/Desktop/codingame-rust/fall_2020_witches_brew/output";
-
-    let players: Vec<PlayerPlayFunction> = vec![
-        PlayerPlayFunction {
+// e.g. cargo run --release -- run beam beam@4 --runs 100 --permut --paired
+fn main() {
+    let mut registry = BotRegistry::new();
+    registry.register(Bot {
+        name: "beam",
+        version: "4",
+        description: "Beam search over the player's own moves",
+        player: PlayerPlayFunction {
+            func: &player_beam_4::play,
+            params: None,
+            name: None,
+        },
+    });
+    registry.register(Bot {
+        name: "beam",
+        version: "5",
+        description: "Beam search over the player's own moves, BFS in the endgame (params : beam_size, time_limit_ms, tier0_factor..tier3_factor, rupees_factor)",
+        player: PlayerPlayFunction {
             func: &player_beam_5::play,
             params: None,
+            name: None,
         },
-        PlayerPlayFunction {
-            func: &player_beam_4::play,
+    });
+    registry.register(Bot {
+        name: "random_brewer",
+        version: "1",
+        description: "Brews when it can, otherwise plays a random valid move",
+        player: PlayerPlayFunction {
+            func: &player_random_brewer::play,
             params: None,
+            name: None,
         },
-    ];
-
-    let start = Instant::now();
+    });
+    registry.register(Bot {
+        name: "random",
+        version: "1",
+        description: "Plays a random valid move",
+        player: PlayerPlayFunction {
+            func: &player_random::play,
+            params: None,
+            name: None,
+        },
+    });
 
-    let result;
-    if RUN_PERMUT == true {
-        result = simulator::run_permut(
-            WitchesBrewGame::new,
-            &players,
-            RUNS,
-            match RECORD {
-                true => Some(record_path.to_string()),
-                false => None,
+    let scripts = [
+        Script {
+            name: "state_graph",
+            description: "<file path> : write the DOT graph of the stock states",
+            run: |args| {
+                let file_path = args.first().ok_or("Missing file path")?;
+                scripts::state_graph::print_state_graph(file_path);
+                Ok(())
             },
-            STATS,
-        );
-    } else {
-        result = simulator::run(
-            WitchesBrewGame::new,
-            &players,
-            RUNS,
-            match RECORD {
-                true => Some(record_path.to_string()),
-                false => None,
+        },
+        Script {
+            name: "calculate_mem_usage",
+            description: "Print the memory taken by the search tree nodes & the cache",
+            run: |_| {
+                scripts::calculate_mem_usage::run();
+                Ok(())
             },
-            STATS,
-        );
-    }
-
-    let duration = start.elapsed();
-    println!(
-        "Ran {} games in {:?}",
-        match RUN_PERMUT {
-            true => RUNS * 2,
-            false => RUNS,
         },
-        duration
-    );
+    ];
 
-    if STATS == true {
-        let stats = result.unwrap().unwrap();
-        println!("Win statistics : {:?}", stats.players_win_loss);
-    }
-}
+    let options = RunOptions {
+        time_limits: Some(TimeLimits {
+            first_turn: Duration::from_millis(1000),
+            turn: Duration::from_millis(50),
+        }),
+        ..Default::default()
+    };
 
-fn main() {
-    run();
-    //scripts::calculate_mem_usage::run();
+    cli::main::<WitchesBrewGame>(&registry, &scripts, &options);
 }
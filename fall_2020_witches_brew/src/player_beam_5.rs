@@ -365,21 +365,30 @@ mod game {
         new_state
     }
 
-    pub fn eval(state: &State) -> f32 {
-        const TIER0_FACTOR: f32 = 1.0;
-        const TIER1_FACTOR: f32 = 2.0;
-        const TIER2_FACTOR: f32 = 3.0;
-        const TIER3_FACTOR: f32 = 4.0;
-        const RUPEES_FACTOR: f32 = 2.0;
+    #[derive(Clone, Copy)]
+    pub struct EvalFactors {
+        pub tiers: [f32; 4],
+        pub rupees: f32,
+    }
+
+    impl Default for EvalFactors {
+        fn default() -> Self {
+            EvalFactors {
+                tiers: [1.0, 2.0, 3.0, 4.0],
+                rupees: 2.0,
+            }
+        }
+    }
 
+    pub fn eval(state: &State, factors: &EvalFactors) -> f32 {
         if state.player.brewed_potions_count == 6 {
             10000.0
         } else {
-            RUPEES_FACTOR * state.player.rupees as f32
-                + TIER0_FACTOR * state.player.stock[0] as f32
-                + TIER1_FACTOR * state.player.stock[1] as f32
-                + TIER2_FACTOR * state.player.stock[2] as f32
-                + TIER3_FACTOR * state.player.stock[3] as f32
+            factors.rupees * state.player.rupees as f32
+                + factors.tiers[0] * state.player.stock[0] as f32
+                + factors.tiers[1] * state.player.stock[1] as f32
+                + factors.tiers[2] * state.player.stock[2] as f32
+                + factors.tiers[3] * state.player.stock[3] as f32
         }
     }
 
@@ -773,7 +782,24 @@ mod beam {
     use std::time::Instant;
 
     const MAX_NODE_COUNT: usize = 1_200_000;
-    const TIME_LIMIT_MS: u128 = 49;
+
+    // Search parameters, that can be changed by the player's params
+    #[derive(Clone, Copy)]
+    pub struct Params {
+        pub beam_size: usize,
+        pub time_limit_ms: u128,
+        pub eval_factors: game::EvalFactors,
+    }
+
+    impl Default for Params {
+        fn default() -> Self {
+            Params {
+                beam_size: 1000,
+                time_limit_ms: 49,
+                eval_factors: game::EvalFactors::default(),
+            }
+        }
+    }
 
     pub enum SEARCH_ALGO {
         BEAM,
@@ -809,13 +835,15 @@ mod beam {
     pub struct Beam {
         arr: Vec<Node>,
         len: usize,
+        params: Params,
     }
 
     impl Beam {
-        pub fn new() -> Self {
+        pub fn new(params: Params) -> Self {
             Self {
                 arr: vec![Node::default(); MAX_NODE_COUNT],
                 len: 0,
+                params,
             }
         }
 
@@ -905,18 +933,21 @@ mod beam {
             start_state: game::State,
             cache: &game::Cache,
         ) -> Vec<(game::Move, f32)> {
-            const BEAM_SIZE: usize = 1000;
+            let beam_size = self.params.beam_size;
+            let eval_factors = self.params.eval_factors;
 
             let start = Instant::now();
             self.init(start_state);
 
-            let mut frontier: Vec<usize> = Vec::with_capacity(BEAM_SIZE);
+            let mut frontier: Vec<usize> = Vec::with_capacity(beam_size);
             frontier.push(0);
 
             let mut max_eval = -f32::INFINITY;
             let mut most_valuable_node_idx = 0;
 
-            'main: while (start.elapsed().as_millis() < TIME_LIMIT_MS) && (frontier.len() > 0) {
+            'main: while (start.elapsed().as_millis() < self.params.time_limit_ms)
+                && (frontier.len() > 0)
+            {
                 let mut frontier_temp: Vec<(usize, f32)> = Vec::new();
                 let mut min_eval_temp: f32 = f32::INFINITY;
 
@@ -936,7 +967,7 @@ mod beam {
                             child_first: None,
                             child_count: 0,
                             depth: node.depth + 1,
-                            eval: Beam::eval(&state, node.depth),
+                            eval: Beam::eval(&state, node.depth, &eval_factors),
                         })
                         .collect::<Vec<Node>>();
 
@@ -946,7 +977,7 @@ mod beam {
                     }
 
                     /* Remove children whose score is so low, they will never be added to the frontier */
-                    if frontier_temp.len() > BEAM_SIZE {
+                    if frontier_temp.len() > beam_size {
                         children.retain(|c| c.eval > min_eval_temp);
                     }
 
@@ -974,11 +1005,11 @@ mod beam {
                     }
                 }
 
-                /* Select the top BEAM_SIZE frontier nodes and add them to the frontier */
+                /* Select the top [beam_size] frontier nodes and add them to the frontier */
                 frontier_temp.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
                 frontier.clear();
 
-                for i in 0..std::cmp::min(frontier_temp.len(), BEAM_SIZE) {
+                for i in 0..std::cmp::min(frontier_temp.len(), beam_size) {
                     frontier.push(frontier_temp[i].0);
                 }
             }
@@ -1033,10 +1064,10 @@ mod beam {
             }
         }
 
-        fn eval(state: &game::State, node_depth: usize) -> f32 {
+        fn eval(state: &game::State, node_depth: usize, factors: &game::EvalFactors) -> f32 {
             const PATH_LEN_FACTOR: f32 = 0.99;
 
-            game::eval(state) * PATH_LEN_FACTOR.powi(node_depth as i32)
+            game::eval(state, factors) * PATH_LEN_FACTOR.powi(node_depth as i32)
         }
     }
 }
@@ -1054,7 +1085,32 @@ pub fn play(
     let mut player_brewed_potion_count: [u8; 2] = [0, 0];
 
     let cache = game::Cache::new();
-    let mut beam: beam::Beam = beam::Beam::new();
+    /* Named parameters "name=value" given by the simulator, e.g. to tune them */
+    let mut beam_params = beam::Params::default();
+    for param in params.iter().flatten() {
+        match param.split_once('=') {
+            Some(("beam_size", value)) => beam_params.beam_size = parse_input!(value, usize),
+            Some(("time_limit_ms", value)) => beam_params.time_limit_ms = parse_input!(value, u128),
+            Some(("tier0_factor", value)) => {
+                beam_params.eval_factors.tiers[0] = parse_input!(value, f32)
+            }
+            Some(("tier1_factor", value)) => {
+                beam_params.eval_factors.tiers[1] = parse_input!(value, f32)
+            }
+            Some(("tier2_factor", value)) => {
+                beam_params.eval_factors.tiers[2] = parse_input!(value, f32)
+            }
+            Some(("tier3_factor", value)) => {
+                beam_params.eval_factors.tiers[3] = parse_input!(value, f32)
+            }
+            Some(("rupees_factor", value)) => {
+                beam_params.eval_factors.rupees = parse_input!(value, f32)
+            }
+            _ => panic!("Unknown parameter {}", param),
+        }
+    }
+
+    let mut beam: beam::Beam = beam::Beam::new(beam_params);
 
     // game loop
     while ctr_rcv.recv().unwrap() == true {
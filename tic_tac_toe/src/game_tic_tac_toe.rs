@@ -3,12 +3,6 @@ use common::{Game, Message, WinLossTie};
 use std::collections::HashMap;
 use std::fmt;
 
-macro_rules! parse_input {
-    ($x:expr, $t:ident) => {
-        $x.trim().parse::<$t>().unwrap()
-    };
-}
-
 #[derive(Debug, PartialEq)]
 enum MoveResult {
     NormalMove,
@@ -336,85 +330,27 @@ valid_moves
 vec_board    
 }
 
-}
-
-impl Game for TicTacToeGame {
-    fn new() -> Self {
-        TicTacToeGame {
-            p_boards: [0; 2],
-            p_squares: [0; 2],
-            locked_squares: 0,
-
-            active: true,
-            active_player: 0,
-            turn: 0,
-
-            last_move: 0,
-            last_move_result: None,
-            winners: None,
-        }
-    }
-
-    fn turn(&self) -> Option<Message> {
-        // If game is over, return None
-        if self.active == false {
-            return None;
-        }
-
-        let mut out: Vec<String> = Vec::new();
-
-        // (1) Output last move
-        if self.last_move == 0 {
-            out.push(String::from("-1 -1"));
-        }
-        else {
-            let m = TicTacToeGame::move81_to_movetuple(self.last_move);
-            out.push(format!("{} {}", m.0, m.1));
-        }
-
-        // (2) Output # of valid moves
-        let valid_moves = TicTacToeGame::moves81_to_movetuples(TicTacToeGame::valid_moves(&self.p_boards, self.locked_squares, self.last_move));
-
-        out.push(valid_moves.len().to_string());
+    // Plays the move [msg] of the active player. If it can't be parsed or isn't valid, the player loses
+    fn play_move(&mut self, msg: &str) -> Result<(), Vec<record::InvalidMove>> {
+        // (1) Extract move information
+        let _move = msg.trim().split(" ").map(|s| s.parse::<u8>()).collect::<Vec<_>>();
+        let (row, col) = match _move.as_slice() {
+            [Ok(row), Ok(col)] => (*row, *col),
+            _ => return self.end_game_on_invalid_move(record::InvalidMoveKind::ParseError, msg),
+        };
 
-        // (3) Output valid moves
-        for m in valid_moves.iter() {
-            out.push(format!("{} {}", m.0, m.1));
+        // (2) Check if move is valid
+        let valid_moves =
+            TicTacToeGame::valid_moves(&self.p_boards, self.locked_squares, self.last_move);
+        if row >= 9 || col >= 9 || valid_moves & TicTacToeGame::movetuple_to_move81((row, col)) == 0 {
+            return self.end_game_on_invalid_move(record::InvalidMoveKind::IllegalMove, msg);
         }
 
-        Some(Message {
-            player_id: self.active_player as usize,
-            messages: out,
-        })
-    }
-
-    fn play(&mut self, msg: String) {
-        // (1) Extract move information
-        let _move = msg.split(" ").collect::<Vec<_>>();
-        let row = parse_input!(_move[0], u8);
-        let col = parse_input!(_move[1], u8);
-
         let move81 = TicTacToeGame::movetuple_to_move81((row, col));
         let square81 = TicTacToeGame::square_of_move81(move81);
 
         let pid = self.active_player;
 
-        // (2) Check if move is valid
-        let valid_moves =
-            TicTacToeGame::valid_moves(&self.p_boards, self.locked_squares, self.last_move);
-        if valid_moves & move81 == 0 {
-            eprintln!("[GAME] Received invalid move {}", msg);
-            self.last_move_result = Some(MoveResult::InvalidMove);
-            self.active = false;
-            self.winners = if pid == 0 {
-                Some((WinLossTie::Loss, WinLossTie::Win))
-            } else {
-                Some((WinLossTie::Win, WinLossTie::Loss))
-            };
-
-            return;
-        }
-
         self.last_move_result = Some(MoveResult::NormalMove);
         // (3) Perform move and update game state
         //  (3.1) Place move on board
@@ -465,6 +401,86 @@ impl Game for TicTacToeGame {
         if self.active == true {
             self.active_player = (self.active_player + 1) % 2;
         }
+        Ok(())
+    }
+
+    // Ends the game because of the move [msg] of the active player, that made it lose
+    fn end_game_on_invalid_move(&mut self, kind: record::InvalidMoveKind, msg: &str) -> Result<(), Vec<record::InvalidMove>> {
+        let valid_moves = TicTacToeGame::moves81_to_movetuples(TicTacToeGame::valid_moves(&self.p_boards, self.locked_squares, self.last_move));
+
+        self.last_move_result = Some(MoveResult::InvalidMove);
+        let invalid_move = record::InvalidMove {
+            player: self.active_player as u32,
+            kind,
+            player_move: msg.to_string(),
+            legal_moves: valid_moves.iter().map(|m| format!("{} {}", m.0, m.1)).collect(),
+        };
+        self.end_game_if_invalid_moves(2, vec![invalid_move])
+    }
+}
+
+impl Game for TicTacToeGame {
+    fn new() -> Self {
+        TicTacToeGame {
+            p_boards: [0; 2],
+            p_squares: [0; 2],
+            locked_squares: 0,
+
+            active: true,
+            active_player: 0,
+            turn: 0,
+
+            last_move: 0,
+            last_move_result: None,
+            winners: None,
+        }
+    }
+
+    fn new_with_seed(_seed: u64) -> Self {
+        // The initial board is always empty
+        TicTacToeGame::new()
+    }
+
+    fn turn(&self) -> Option<Message> {
+        // If game is over, return None
+        if self.active == false {
+            return None;
+        }
+
+        let mut out: Vec<String> = Vec::new();
+
+        // (1) Output last move
+        if self.last_move == 0 {
+            out.push(String::from("-1 -1"));
+        }
+        else {
+            let m = TicTacToeGame::move81_to_movetuple(self.last_move);
+            out.push(format!("{} {}", m.0, m.1));
+        }
+
+        // (2) Output # of valid moves
+        let valid_moves = TicTacToeGame::moves81_to_movetuples(TicTacToeGame::valid_moves(&self.p_boards, self.locked_squares, self.last_move));
+
+        out.push(valid_moves.len().to_string());
+
+        // (3) Output valid moves
+        for m in valid_moves.iter() {
+            out.push(format!("{} {}", m.0, m.1));
+        }
+
+        Some(Message {
+            player_id: self.active_player as usize,
+            messages: out,
+        })
+    }
+
+    fn play(&mut self, msg: String) {
+        let _ = self.play_move(&msg);
+    }
+
+    fn play_simultaneous(&mut self, moves: Vec<String>) -> Result<(), Vec<record::InvalidMove>> {
+        // A single player plays each turn
+        self.play_move(&moves[0])
     }
 
     fn winners(&self) -> Option<Vec<WinLossTie>> {
@@ -474,8 +490,16 @@ impl Game for TicTacToeGame {
         }
     }
 
+    // Number of small squares won by each player
+    fn scores(&self) -> Option<Vec<f64>> {
+        match self.winners {
+            Some(_) => Some(self.p_squares.iter().map(|s| (s.count_ones() / 9) as f64).collect()),
+            None => None,
+        }
+    }
+
     fn get_state(&self) -> record::GameState {
-        let mut board: Vec<Vec<String>> = Vec::new();
+        let mut board: Vec<Vec<record::CellState>> = Vec::new();
 
         // Create Record Board
         let board_p0: Vec<Vec<bool>> = TicTacToeGame::to_vector(self.p_boards[0]);
@@ -485,7 +509,7 @@ impl Game for TicTacToeGame {
         let locked_squares :Vec<Vec<bool>> = TicTacToeGame::to_vector(self.locked_squares);
 
         for r in 0..9 {
-            let mut row: Vec<String> = Vec::new();
+            let mut row: Vec<record::CellState> = Vec::new();
             for c in 0..9 {
                 let mut cell_state = String::new();
 
@@ -515,18 +539,21 @@ impl Game for TicTacToeGame {
                     cell_state.push('.');
                 }
 
-                row.push(cell_state);
+                row.push(record::CellState {
+                    cell_state,
+                    tooltip: None,
+                });
             }
             board.push(row);
         }
 
         // Record other state variables
-        let mut state: HashMap<&str, String> = HashMap::new();
-        state.insert("turn", self.turn.to_string());
-        state.insert("active", self.active.to_string());
-        state.insert("active_player", self.active_player.to_string());
+        let mut state: HashMap<String, String> = HashMap::new();
+        state.insert("turn".to_string(), self.turn.to_string());
+        state.insert("active".to_string(), self.active.to_string());
+        state.insert("active_player".to_string(), self.active_player.to_string());
         state.insert(
-            "last_move",
+            "last_move".to_string(),
             match self.last_move {
                 0 => String::from("None"),
                 _ => {
@@ -535,25 +562,28 @@ impl Game for TicTacToeGame {
             },
         );
         state.insert(
-            "last_move_result",
+            "last_move_result".to_string(),
             match &self.last_move_result {
                 None => String::from("None"),
                 Some(mr) => mr.to_string(),
             },
         );
 
-        state.insert("p_board[0]", format!("{:0>81b}", self.p_boards[0]));
-        state.insert("p_squares[0]", format!("{:0>81b}", self.p_squares[0]));
-        state.insert("p_board[1]", format!("{:0>81b}", self.p_boards[1]));
-        state.insert("p_squares[1]", format!("{:0>81b}", self.p_squares[1]));
+        state.insert("p_board[0]".to_string(), format!("{:0>81b}", self.p_boards[0]));
+        state.insert("p_squares[0]".to_string(), format!("{:0>81b}", self.p_squares[0]));
+        state.insert("p_board[1]".to_string(), format!("{:0>81b}", self.p_boards[1]));
+        state.insert("p_squares[1]".to_string(), format!("{:0>81b}", self.p_squares[1]));
 
 
-        state.insert("locked_squares", format!("{:0>81b}", self.locked_squares));
+        state.insert("locked_squares".to_string(), format!("{:0>81b}", self.locked_squares));
 
-        record::GameState { board, state }
+        record::GameState {
+            board: Some(board),
+            state,
+        }
     }
 
-    fn get_board_representation() -> record::BoardRepresentation {
+    fn get_board_representation() -> Option<record::BoardRepresentation> {
         let mut classes: Vec<HashMap<char, record::CellClass>> = Vec::new();
 
         // First position
@@ -648,11 +678,15 @@ impl Game for TicTacToeGame {
 
         classes.push(class_styles);
 
-        record::BoardRepresentation {
-            rows: 9,
-            cols: 9,
+        Some(record::BoardRepresentation {
+            board_type: record::BoardType::SQUARE(9, 9),
             classes,
-        }
+        })
+    }
+
+    fn end_game(&mut self, players_status: Vec<WinLossTie>) {
+        self.active = false;
+        self.winners = Some((players_status[0], players_status[1]));
     }
 }
 
@@ -946,5 +980,22 @@ mod tests {
         assert_eq!(expected_moves,valid_moves);
 
     }
+
+    #[test]
+    fn test_play_simultaneous_reports_invalid_moves() {
+        let mut game = TicTacToeGame::new();
+        game.play(String::from("4 4"));
+
+        let invalid_moves = game.play_simultaneous(vec![String::from("0 0")]).unwrap_err();
+        assert_eq!(invalid_moves[0].player, 1);
+        assert_eq!(invalid_moves[0].kind, record::InvalidMoveKind::IllegalMove);
+        assert_eq!(invalid_moves[0].legal_moves.len(), 8);
+        assert_vec_eq!(game.winners().unwrap(), vec![WinLossTie::Win, WinLossTie::Loss]);
+
+        let mut game = TicTacToeGame::new();
+        let invalid_moves = game.play_simultaneous(vec![String::from("4")]).unwrap_err();
+        assert_eq!(invalid_moves[0].kind, record::InvalidMoveKind::ParseError);
+        assert_vec_eq!(game.winners().unwrap(), vec![WinLossTie::Loss, WinLossTie::Win]);
+    }
     
 }
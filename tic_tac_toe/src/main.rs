@@ -1,51 +1,51 @@
 mod game_tic_tac_toe;
 use game_tic_tac_toe::TicTacToeGame;
 mod player_mcts_6;
+mod player_mcts_7;
 mod player_random;
-use common::simulator;
-use common::simulator::PlayerPlayFunction;
-use common::Game;
-
-use std::time::Instant;
-
-#[allow(unused_must_use)]
+use common::cli;
+use common::simulator::{Bot, BotRegistry, PlayerPlayFunction, RunOptions};
 
+// e.g. cargo run --release -- run mcts random --runs 100 --permut
 fn main() {
-    const STATS: bool = true;
-    const RECORD: bool = false;
-    const RUNS: u32 = 2;
-
-    let record_path = "C:/Users/hhour/Desktop/codingame-rust/tic_tac_toe/output";
-
-    let players: Vec<PlayerPlayFunction> = vec![
-        PlayerPlayFunction {
+    let mut registry = BotRegistry::new();
+    registry.register(Bot {
+        name: "mcts",
+        version: "6",
+        description: "MCTS searching for the whole 100ms of a turn",
+        player: PlayerPlayFunction {
             func: &player_mcts_6::play,
             params: None,
+            name: None,
         },
-        PlayerPlayFunction {
-            func: &player_mcts_6::play,
+    });
+    registry.register(Bot {
+        name: "mcts",
+        version: "7",
+        description: "MCTS of common::mcts with tree reuse, on the model of mcts@6 (params : exploration, time_limit_ms)",
+        player: PlayerPlayFunction {
+            func: &player_mcts_7::play,
             params: None,
+            name: None,
         },
-    ];
-
-    let start = Instant::now();
-
-    let result = simulator::run(
-        TicTacToeGame::new,
-        &players,
-        RUNS,
-        match RECORD {
-            true => Some(record_path.to_string()),
-            false => None,
+    });
+    registry.register(Bot {
+        name: "random",
+        version: "1",
+        description: "Plays a random valid move",
+        player: PlayerPlayFunction {
+            func: &player_random::play,
+            params: None,
+            name: None,
         },
-        STATS,
-    );
+    });
 
-    let duration = start.elapsed();
-    println!("Ran {} games in {:?}", RUNS * 2, duration);
+    // player_mcts_6 searches for the whole 100ms of a turn, so it would always time out.
+    // Set Some(TimeLimits { first_turn: 1000ms, turn: 100ms }) to enforce CodinGame's limits
+    let options = RunOptions {
+        time_limits: None,
+        ..Default::default()
+    };
 
-    if STATS == true {
-        let stats = result.unwrap().unwrap();
-        println!("Win statistics : {:?}", stats.players_win_loss);
-    }
+    cli::main::<TicTacToeGame>(&registry, &[], &options);
 }
@@ -763,7 +763,7 @@ mod conv {
 }
 
 #[allow(unused_variables, unused_assignments, unused_must_use)]
-pub fn play(ctr_rcv: Receiver<bool>, msg_rcv: Receiver<String>, msg_snd: Sender<String>, params : Option<Vec<String>>) {
+pub fn play(ctr_rcv: Receiver<bool>, msg_rcv: Receiver<String>, msg_snd: Sender<(String, Option<std::collections::HashMap<String, String>>)>, params : Option<Vec<String>>) {
     let mut state = game::new();
     let mut my_pid = 1; // Assume that I'm player 1
     let mut opp_pid = 0;
@@ -822,7 +822,7 @@ pub fn play(ctr_rcv: Receiver<bool>, msg_rcv: Receiver<String>, msg_snd: Sender<
 
         // (5) Send the move
         let best_move = conv::move81_to_movetuple(best_move);
-        msg_snd.send(format!("{} {}", best_move.0, best_move.1));
+        msg_snd.send((format!("{} {}", best_move.0, best_move.1), None));
 
     }
 }
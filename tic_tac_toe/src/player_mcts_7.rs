@@ -0,0 +1,561 @@
+use common::mcts::{self, Mcts, MctsOptions};
+use common::search::{GameModel, GameScore};
+use rand::Rng;
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::Duration;
+
+macro_rules! parse_input {
+    ($x:expr, $t:ident) => {
+        $x.trim().parse::<$t>().unwrap()
+    };
+}
+
+/*
+    The model of player_mcts_6, searched by the MCTS engine of common (with its tree reuse)
+*/
+
+mod game {
+
+    use super::{GameModel, GameScore, Rng};
+
+    pub type Move = u128;
+
+    pub struct TicTacToeModel;
+
+    #[derive(Clone, Debug)]
+    pub struct State {
+        /*
+        2D array
+        [pid] => [u16,u16,u16,u16,u16,u16,u16,u16,u16] (9 squares)
+            Each u16 correspond to a 9-bit representation of a square.
+        */
+        p_boards: [u128; 2],
+        // A 2D array : [player_id] => 8-bit number representing which squares are won by a player
+        p_squares: [u128; 2],
+        // represent which squares are locked
+        locked_squares: u128,
+
+        active: bool,
+        active_player: u8, // player who's turn is to do the next move
+        turn: u8,
+
+        last_move: u128,
+        winners: Option<(WinLossTie, WinLossTie)>,
+    }
+
+    pub fn new() -> State {
+        State {
+            p_boards: [0; 2],
+            p_squares: [0; 2],
+            locked_squares: 0,
+
+            active: true,
+            active_player: 0,
+            turn: 0,
+
+            last_move: 0,
+            winners: None,
+        }
+    }
+
+    pub fn update_state(state: &mut State, player: u8, move_: Move) {
+        /*
+            Update the state with the move done by the player.
+            Assume that it's the player's turn and the move is always legal
+        */
+
+        // (1) Place move on board
+        let square81 = square_of_move81(move_);
+
+        state.p_boards[player as usize] |= move_;
+
+        // (2) Check if the player won the square
+        let mut a_square_was_filled = false;
+        if won_the_square(state.p_boards[player as usize], square81) {
+            // Update the player's square status
+            state.p_squares[player as usize] |= square81;
+            // Update the locked square status
+            state.locked_squares |= square81;
+            a_square_was_filled = true;
+        }
+        // (3.3) If the player didn't win the square, check if it's filled
+        else if (state.p_boards[0] | state.p_boards[1]) & square81 == square81 {
+            state.locked_squares |= square81;
+            a_square_was_filled = true;
+        }
+        // (4) Check if it's a global winning move or a tie
+        if a_square_was_filled {
+            if won_the_board(state.p_squares[player as usize]) {
+                state.active = false;
+                state.winners = if player == 0 {
+                    Some((WinLossTie::Win, WinLossTie::Loss))
+                } else {
+                    Some((WinLossTie::Loss, WinLossTie::Win))
+                }
+            } else if state.locked_squares == 0b111111111_111111111_111111111_111111111_111111111_111111111_111111111_111111111_111111111 {
+                state.active = false;
+                let won_squares = [
+                    state.p_squares[0].count_ones(),
+                    state.p_squares[1].count_ones(),
+                ];
+                if won_squares[0] > won_squares[1] {
+                    state.winners = Some((WinLossTie::Win, WinLossTie::Loss));
+                } else if won_squares[0] < won_squares[1] {
+                    state.winners = Some((WinLossTie::Loss, WinLossTie::Win));
+                } else {
+                    state.winners = Some((WinLossTie::Tie, WinLossTie::Tie));
+                }
+            }
+        }
+
+        state.turn += 1;
+        state.last_move = move_;
+
+        if state.active {
+            state.active_player = (state.active_player + 1) % 2;
+        }
+    }
+
+    pub fn is_terminal(state: &State) -> bool {
+        !state.active
+    }
+
+    pub fn get_scores(state: &State) -> GameScore {
+        match state.winners {
+            Some((WinLossTie::Win, WinLossTie::Loss)) => [1.0, 0.0, 0.0, 0.0],
+            Some((WinLossTie::Loss, WinLossTie::Win)) => [0.0, 1.0, 0.0, 0.0],
+            Some((WinLossTie::Tie, WinLossTie::Tie)) => [0.5, 0.5, 0.0, 0.0],
+            _ => panic!(),
+        }
+    }
+
+    // Mask of the valid moves of the active player
+    fn valid_moves81(state: &State) -> u128 {
+        let p_boards = &state.p_boards;
+        let locked_squares = state.locked_squares;
+
+        let valid_moves81 = (!(p_boards[0] | p_boards[1] | locked_squares)) & 0b111111111_111111111_111111111_111111111_111111111_111111111_111111111_111111111_111111111;
+
+        match state.last_move {
+            0 => valid_moves81,
+            last_move => {
+                let next_square = square_pointed_by_move81(last_move);
+
+                // If next_square is not a locked square
+                if next_square & locked_squares == 0 {
+                    valid_moves81 & next_square
+                } else {
+                    valid_moves81
+                }
+            }
+        }
+    }
+
+    impl GameModel for TicTacToeModel {
+        type State = State;
+        type Move = Move;
+
+        fn valid_moves(&self, state: &State, moves: &mut Vec<Move>) -> u8 {
+            let valid_moves81 = valid_moves81(state);
+
+            moves.clear();
+            let mut m = 0b1;
+            for _ in 0..81 {
+                if valid_moves81 & m > 0 {
+                    moves.push(m);
+                }
+                m <<= 1;
+            }
+
+            state.active_player
+        }
+
+        fn update_state(&self, state: &mut State, player: u8, move_: Move) {
+            update_state(state, player, move_)
+        }
+
+        fn is_terminal(&self, state: &State) -> bool {
+            is_terminal(state)
+        }
+
+        fn get_scores(&self, state: &State) -> GameScore {
+            get_scores(state)
+        }
+
+        // Picks the n-th bit of the mask, without listing the moves
+        fn random_valid_move<R: Rng>(&self, state: &State, rng: &mut R) -> (u8, Move) {
+            let mut valid_moves81 = valid_moves81(state);
+            for _ in 0..rng.gen_range(0..valid_moves81.count_ones()) {
+                valid_moves81 &= valid_moves81 - 1;
+            }
+
+            (
+                state.active_player,
+                valid_moves81 & valid_moves81.wrapping_neg(),
+            )
+        }
+    }
+
+    /* #region(collapsed) [Private game functions] */
+    #[derive(Clone, Debug)]
+    enum WinLossTie {
+        Win,
+        Loss,
+        Tie,
+    }
+
+    fn square_of_move81(move_: u128) -> u128 {
+        match move_ {
+            1208925819614629174706176
+            | 604462909807314587353088
+            | 302231454903657293676544
+            | 151115727451828646838272
+            | 75557863725914323419136
+            | 37778931862957161709568
+            | 18889465931478580854784
+            | 9444732965739290427392
+            | 4722366482869645213696 => 2413129272746388704198656,
+            2361183241434822606848
+            | 1180591620717411303424
+            | 590295810358705651712
+            | 295147905179352825856
+            | 147573952589676412928
+            | 73786976294838206464
+            | 36893488147419103232
+            | 18446744073709551616
+            | 9223372036854775808 => 4713143110832790437888,
+            4611686018427387904 | 2305843009213693952 | 1152921504606846976
+            | 576460752303423488 | 288230376151711744 | 144115188075855872 | 72057594037927936
+            | 36028797018963968 | 18014398509481984 => 9205357638345293824,
+            9007199254740992 | 4503599627370496 | 2251799813685248 | 1125899906842624
+            | 562949953421312 | 281474976710656 | 140737488355328 | 70368744177664
+            | 35184372088832 => 17979214137393152,
+            17592186044416 | 8796093022208 | 4398046511104 | 2199023255552 | 1099511627776
+            | 549755813888 | 274877906944 | 137438953472 | 68719476736 => 35115652612096,
+            34359738368 | 17179869184 | 8589934592 | 4294967296 | 2147483648 | 1073741824
+            | 536870912 | 268435456 | 134217728 => 68585259008,
+            67108864 | 33554432 | 16777216 | 8388608 | 4194304 | 2097152 | 1048576 | 524288
+            | 262144 => 133955584,
+            131072 | 65536 | 32768 | 16384 | 8192 | 4096 | 2048 | 1024 | 512 => 261632,
+            256 | 128 | 64 | 32 | 16 | 8 | 4 | 2 | 1 => 511,
+            _ => panic!(),
+        }
+    }
+
+    fn square_pointed_by_move81(move_: u128) -> u128 {
+        match move_ {
+            1208925819614629174706176
+            | 2361183241434822606848
+            | 4611686018427387904
+            | 9007199254740992
+            | 17592186044416
+            | 34359738368
+            | 67108864
+            | 131072
+            | 256 => 2413129272746388704198656,
+            604462909807314587353088
+            | 1180591620717411303424
+            | 2305843009213693952
+            | 4503599627370496
+            | 8796093022208
+            | 17179869184
+            | 33554432
+            | 65536
+            | 128 => 4713143110832790437888,
+            302231454903657293676544
+            | 590295810358705651712
+            | 1152921504606846976
+            | 2251799813685248
+            | 4398046511104
+            | 8589934592
+            | 16777216
+            | 32768
+            | 64 => 9205357638345293824,
+            151115727451828646838272
+            | 295147905179352825856
+            | 576460752303423488
+            | 1125899906842624
+            | 2199023255552
+            | 4294967296
+            | 8388608
+            | 16384
+            | 32 => 17979214137393152,
+            75557863725914323419136
+            | 147573952589676412928
+            | 288230376151711744
+            | 562949953421312
+            | 1099511627776
+            | 2147483648
+            | 4194304
+            | 8192
+            | 16 => 35115652612096,
+            37778931862957161709568
+            | 73786976294838206464
+            | 144115188075855872
+            | 281474976710656
+            | 549755813888
+            | 1073741824
+            | 2097152
+            | 4096
+            | 8 => 68585259008,
+            18889465931478580854784
+            | 36893488147419103232
+            | 72057594037927936
+            | 140737488355328
+            | 274877906944
+            | 536870912
+            | 1048576
+            | 2048
+            | 4 => 133955584,
+            9444732965739290427392
+            | 18446744073709551616
+            | 36028797018963968
+            | 70368744177664
+            | 137438953472
+            | 268435456
+            | 524288
+            | 1024
+            | 2 => 261632,
+            4722366482869645213696
+            | 9223372036854775808
+            | 18014398509481984
+            | 35184372088832
+            | 68719476736
+            | 134217728
+            | 262144
+            | 512
+            | 1 => 511,
+            _ => panic!(),
+        }
+    }
+
+    fn won_the_square(p_board81: u128, square81: u128) -> bool {
+        let sq_idx = match square81 {
+            2413129272746388704198656 => 0,
+            4713143110832790437888 => 1,
+            9205357638345293824 => 2,
+            17979214137393152 => 3,
+            35115652612096 => 4,
+            68585259008 => 5,
+            133955584 => 6,
+            261632 => 7,
+            511 => 8,
+            _ => panic!(),
+        };
+
+        const ALL_WINNING_CONFIGURATIONS: [[u128; 8]; 9] = [
+        [0b111000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000111000_000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000111_000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b100100100_000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b010010010_000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b001001001_000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b100010001_000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b001010100_000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000],
+
+        [0b000000000_111000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000111000_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000111_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_100100100_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_010010010_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_001001001_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_100010001_000000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_001010100_000000000_000000000_000000000_000000000_000000000_000000000_000000000],
+
+        [0b000000000_000000000_111000000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000111000_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000111_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_100100100_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_010010010_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_001001001_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_100010001_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_001010100_000000000_000000000_000000000_000000000_000000000_000000000],
+
+        [0b000000000_000000000_000000000_111000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000111000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000111_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_100100100_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_010010010_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_001001001_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_100010001_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_001010100_000000000_000000000_000000000_000000000_000000000,],
+
+        [0b000000000_000000000_000000000_000000000_111000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000111000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000111_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_100100100_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_010010010_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_001001001_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_100010001_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_001010100_000000000_000000000_000000000_000000000,],
+
+        [0b000000000_000000000_000000000_000000000_000000000_111000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000111000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000111_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_100100100_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_010010010_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_001001001_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_100010001_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_001010100_000000000_000000000_000000000,],
+
+        [0b000000000_000000000_000000000_000000000_000000000_000000000_111000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000111000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000111_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_100100100_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_010010010_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_001001001_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_100010001_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_001010100_000000000_000000000,],
+
+        [0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_111000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_000111000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000111_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_100100100_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_010010010_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_001001001_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_100010001_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_001010100_000000000,],
+
+        [0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000_111000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000_000111000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000111,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000_100100100,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000_010010010,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000_001001001,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000_100010001,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_000000000_000000000_001010100,]
+        ];
+
+        let winning_configurations = ALL_WINNING_CONFIGURATIONS[sq_idx as usize];
+
+        for wc in winning_configurations {
+            if p_board81 & wc == wc {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn won_the_board(p_squares81: u128) -> bool {
+        const WINNING_CONFIGURATIONS :[u128;8] = [0b111111111_111111111_111111111_000000000_000000000_000000000_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_111111111_111111111_111111111_000000000_000000000_000000000,
+        0b000000000_000000000_000000000_000000000_000000000_000000000_111111111_111111111_111111111,
+        0b111111111_000000000_000000000_111111111_000000000_000000000_111111111_000000000_000000000,
+        0b000000000_111111111_000000000_000000000_111111111_000000000_000000000_111111111_000000000,
+        0b000000000_000000000_111111111_000000000_000000000_111111111_000000000_000000000_111111111,
+        0b111111111_000000000_000000000_000000000_111111111_000000000_000000000_000000000_111111111,
+        0b000000000_000000000_111111111_000000000_111111111_000000000_111111111_000000000_000000000];
+
+        for wc in WINNING_CONFIGURATIONS {
+            if p_squares81 & wc == wc {
+                return true;
+            }
+        }
+        false
+    }
+
+    /* #endregion */
+}
+
+mod conv {
+
+    use super::game;
+
+    pub fn movetuple_to_move81(move_: (u8, u8)) -> game::Move {
+        let (r, c) = (move_.0 % 3, move_.1 % 3);
+        let (sq_r, sq_c) = (move_.0 / 3, move_.1 / 3);
+
+        0b1 << ((((2 - sq_r) * 3 + (2 - sq_c)) * 9) + ((2 - r) * 3 + (2 - c)))
+    }
+
+    pub fn move81_to_movetuple(move_: game::Move) -> (u8, u8) {
+        // Bit 80 is the first cell, and cells are numbered square by square
+        let i = 80 - move_.trailing_zeros() as u8;
+        let (square, cell) = (i / 9, i % 9);
+
+        ((square / 3) * 3 + cell / 3, (square % 3) * 3 + cell % 3)
+    }
+}
+
+const TIME_LIMIT_MS: u64 = 95;
+const EXPLORATION: f32 = 0.41;
+
+pub fn play(
+    ctr_rcv: Receiver<bool>,
+    msg_rcv: Receiver<String>,
+    msg_snd: Sender<(String, Option<std::collections::HashMap<String, String>>)>,
+    params: Option<Vec<String>>,
+) {
+    /* Named parameters "name=value" given by the simulator, e.g. to tune them */
+    let mut options = MctsOptions {
+        exploration: EXPLORATION,
+        time_limit: Some(Duration::from_millis(TIME_LIMIT_MS)),
+        ..Default::default()
+    };
+    for param in params.iter().flatten() {
+        match param.split_once('=') {
+            Some(("exploration", value)) => options.exploration = parse_input!(value, f32),
+            Some(("time_limit_ms", value)) => {
+                options.time_limit = Some(Duration::from_millis(parse_input!(value, u64)))
+            }
+            _ => panic!("Unknown parameter {}", param),
+        }
+    }
+
+    let model = game::TicTacToeModel;
+    let mut state = game::new();
+    let mut my_pid = 1; // Assume that I'm player 1
+
+    let mut mcts: Mcts<game::TicTacToeModel> = Mcts::new(options);
+    // Moves played since the last search, to move down the tree
+    let mut previous_moves: Vec<game::Move> = Vec::new();
+
+    while ctr_rcv.recv().unwrap() {
+        // (1) Read inputs
+        let input_line = msg_rcv.recv().unwrap();
+        let inputs = input_line.split(' ').collect::<Vec<_>>();
+        let opponent_row = parse_input!(inputs[0], i32);
+        let opponent_col = parse_input!(inputs[1], i32);
+
+        // The valid actions are given by the model
+        let input_line = msg_rcv.recv().unwrap();
+        let valid_action_count = parse_input!(input_line, i32);
+        for _ in 0..valid_action_count {
+            msg_rcv.recv().unwrap();
+        }
+
+        // (2) Update my game state
+        if opponent_row == -1 {
+            // I know now I'm player 0 and it's the first move
+            my_pid = 0;
+        } else {
+            let opponent_move = conv::movetuple_to_move81((opponent_row as u8, opponent_col as u8));
+            model.update_state(&mut state, 1 - my_pid, opponent_move);
+            previous_moves.push(opponent_move);
+        }
+
+        // (3) Determine the next best action, reusing the tree of the previous turn
+        let reused = mcts.advance(&previous_moves);
+        let best_move = mcts.search(&model, &state, &mut mcts::random_rollout);
+        eprintln!(
+            "[MCTS P7] {} simulations, {} nodes in the arena{}",
+            mcts.simulations(),
+            mcts.node_count(),
+            match reused {
+                true => "",
+                false => " (new tree)",
+            }
+        );
+
+        // (4) Update state with my action
+        model.update_state(&mut state, my_pid, best_move);
+        previous_moves.clear();
+        previous_moves.push(best_move);
+
+        // (5) Send the move
+        let best_move = conv::move81_to_movetuple(best_move);
+        msg_snd
+            .send((format!("{} {}", best_move.0, best_move.1), None))
+            .unwrap();
+    }
+}
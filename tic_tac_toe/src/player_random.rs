@@ -12,7 +12,12 @@ macro_rules! parse_input {
  * the standard input according to the problem statement.
  **/
 #[allow(unused_variables, unused_assignments, unused_must_use, dead_code)]
-pub fn play(ctr_rcv: Receiver<bool>, msg_rcv: Receiver<String>, msg_snd: Sender<String>) {
+pub fn play(
+    ctr_rcv: Receiver<bool>,
+    msg_rcv: Receiver<String>,
+    msg_snd: Sender<(String, Option<std::collections::HashMap<String, String>>)>,
+    params: Option<Vec<String>>,
+) {
     // game loop
     while ctr_rcv.recv().unwrap() == true {
         let mut input_line = String::new();
@@ -36,6 +41,6 @@ pub fn play(ctr_rcv: Receiver<bool>, msg_rcv: Receiver<String>, msg_snd: Sender<
         }
 
         let chosen_move = valid_actions.choose(&mut rand::thread_rng()).unwrap();
-        msg_snd.send(format!("{} {}", chosen_move.0, chosen_move.1));
+        msg_snd.send((format!("{} {}", chosen_move.0, chosen_move.1), None));
     }
 }
@@ -1,7 +1,8 @@
 use common::record;
 use common::{Game, Message, StackVector, WinLossTie};
 use rand::prelude::SliceRandom;
-use rand::{thread_rng, Rng};
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 
@@ -33,21 +34,17 @@ impl Default for Move {
 }
 
 impl Move {
-    fn parse_move(msg: &str) -> Move {
-        if msg == "WAIT" {
-            return Move::WAIT;
-        } else if msg.starts_with("GROW") {
-            return Move::GROW(msg[5..].parse().unwrap());
-        } else if msg.starts_with("COMPLETE") {
-            return Move::COMPLETE(msg[9..].parse().unwrap());
-        } else if msg.starts_with("SEED") {
-            let mut s_iter = msg.split_whitespace();
-            s_iter.next();
-            let tree_pos: u8 = s_iter.next().unwrap().parse().unwrap();
-            let seed_pos: u8 = s_iter.next().unwrap().parse().unwrap();
-            return Move::SEED(tree_pos, seed_pos);
-        } else {
-            panic!("Cannot parse move");
+    // The move, or None if [msg] isn't a move
+    fn parse_move(msg: &str) -> Option<Move> {
+        match msg.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["WAIT"] => Some(Move::WAIT),
+            ["GROW", cell] => cell.parse().ok().map(Move::GROW),
+            ["COMPLETE", cell] => cell.parse().ok().map(Move::COMPLETE),
+            ["SEED", tree_pos, seed_pos] => match (tree_pos.parse(), seed_pos.parse()) {
+                (Ok(tree_pos), Ok(seed_pos)) => Some(Move::SEED(tree_pos, seed_pos)),
+                _ => None,
+            },
+            _ => None,
         }
     }
 }
@@ -105,6 +102,8 @@ pub struct WoodSpiritGame {
     active: bool,
     active_player: u8,
     winners: Option<(WinLossTie, WinLossTie)>,
+    // Players that already received the initialization input (the cells)
+    initialized_players: [bool; 2],
 
     //Cache
     cache: Cache,
@@ -261,6 +260,38 @@ fn valid_moves(
     valid_moves
 }
 
+// Parses a line of a position, that should contain at least [count] numbers
+fn parse_position_line(line: Option<&str>, count: usize) -> Result<Vec<i32>, String> {
+    let line = line.ok_or("The position ends too early")?;
+    let numbers = line
+        .split_whitespace()
+        .map(|n| n.parse::<i32>())
+        .collect::<Result<Vec<i32>, _>>()
+        .map_err(|_| format!("Invalid line in the position : {}", line))?;
+
+    if numbers.len() < count {
+        return Err(format!(
+            "Expected {} numbers in the position line : {}",
+            count, line
+        ));
+    }
+    Ok(numbers)
+}
+
+// A number of the position that can't be negative
+fn parse_position_count(value: i32, name: &str) -> Result<u32, String> {
+    u32::try_from(value).map_err(|_| format!("Invalid {} {}", name, value))
+}
+
+// A flag of the position, that is 0 or 1
+fn parse_position_flag(value: i32, name: &str) -> Result<bool, String> {
+    match value {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(format!("Invalid {} flag {}", name, value)),
+    }
+}
+
 fn init_with_params(
     players_initial_small_trees: &[[usize; 2]; 2],
     invalid_cells: &[usize],
@@ -315,6 +346,7 @@ fn init_with_params(
         active_player: 0,
         active: true,
         winners: None,
+        initialized_players: [false; 2],
 
         cache,
     }
@@ -437,8 +469,10 @@ impl Cache {
                     visited = visited_new;
                 }
                 result.remove(&cell_pos);
-                seedable_neighbors[cell_pos][tree_size - 1] =
-                    result.into_iter().collect::<Vec<usize>>();
+                // Sorted, so that valid moves are always listed in the same order for the same seed
+                let mut result = result.into_iter().collect::<Vec<usize>>();
+                result.sort();
+                seedable_neighbors[cell_pos][tree_size - 1] = result;
             }
         }
 
@@ -499,110 +533,218 @@ impl Cache {
 
 /* #endregion */
 
-impl Game for WoodSpiritGame {
-    fn new() -> Self {
-        fn generate_random_cell_pairs() -> Vec<(usize, usize)> {
-            let mut cell_pairs: Vec<(usize, usize)> = Vec::new();
+impl WoodSpiritGame {
+    fn valid_moves(&self, p_id: usize) -> StackVector<Move, MAX_VALID_MOVES> {
+        let player = &self.players[p_id];
+        valid_moves(
+            &self.board,
+            p_id as u8,
+            player.sun,
+            player.seed_count,
+            player.small_tree_count,
+            player.medium_tree_count,
+            player.large_tree_count,
+            &self.cache,
+        )
+    }
 
-            let mut high_quality_cells: Vec<usize> = (0..=6).collect::<Vec<usize>>();
-            high_quality_cells.shuffle(&mut thread_rng());
+    // Moves that [p_id] can play
+    fn legal_moves(&self, p_id: usize) -> Vec<String> {
+        self.valid_moves(p_id)
+            .slice()
+            .iter()
+            .map(|m| m.to_string())
+            .collect()
+    }
 
-            for i in 0..3 {
-                cell_pairs.push((high_quality_cells[i * 2], high_quality_cells[i * 2 + 1]));
-            }
+    // The move [msg] of [p_id], that can't be parsed
+    fn parse_error(&self, p_id: usize, msg: &str) -> record::InvalidMove {
+        record::InvalidMove {
+            player: p_id as u32,
+            kind: record::InvalidMoveKind::ParseError,
+            player_move: msg.to_string(),
+            legal_moves: self.legal_moves(p_id),
+        }
+    }
 
-            let mut medium_quality_cells: Vec<usize> = (7..=18).collect::<Vec<usize>>();
-            medium_quality_cells.shuffle(&mut thread_rng());
+    // Records the move of the active player. Once all the awake players have played, their moves are resolved together
+    fn play_move(&mut self, move_: Move) -> Result<(), Vec<record::InvalidMove>> {
+        /* (2) Record the move */
+        self.players[self.active_player as usize].move_ = Some(move_);
+        self.initialized_players[self.active_player as usize] = true;
 
-            for i in 0..6 {
-                cell_pairs.push((medium_quality_cells[i * 2], medium_quality_cells[i * 2 + 1]));
+        /* (3) Check if both players have played, so that we update the game */
+        if (self.players[0].move_.is_some() && self.players[1].move_.is_some())
+            || (self.players[0].move_.is_some() && self.players[1].is_asleep == true)
+            || (self.players[0].is_asleep == true && self.players[1].move_.is_some())
+        {
+            /* 3.1 Check if moves are valid */
+            let invalid_moves: Vec<record::InvalidMove> = (0..2)
+                .filter(|p_id| {
+                    let player = &self.players[*p_id];
+                    !player.is_asleep
+                        && !self
+                            .valid_moves(*p_id)
+                            .slice()
+                            .contains(&player.move_.unwrap())
+                })
+                .map(|p_id| record::InvalidMove {
+                    player: p_id as u32,
+                    kind: record::InvalidMoveKind::IllegalMove,
+                    player_move: self.players[p_id].move_.unwrap().to_string(),
+                    legal_moves: self.legal_moves(p_id),
+                })
+                .collect();
+            if !invalid_moves.is_empty() {
+                return self.end_game_if_invalid_moves(2, invalid_moves);
             }
 
-            let mut low_quality_cells: Vec<usize> = (19..=36).collect::<Vec<usize>>();
-            low_quality_cells.shuffle(&mut thread_rng());
+            /* (3.2) Update the state */
+            let mut completed_trees_count = 0;
+            let player_moves = [self.players[0].move_.clone(), self.players[1].move_.clone()];
 
-            for i in 0..9 {
-                cell_pairs.push((low_quality_cells[i * 2], low_quality_cells[i * 2 + 1]));
-            }
+            for (p_id, player) in self.players.iter_mut().enumerate() {
+                if player.is_asleep == false {
+                    match player.move_.unwrap() {
+                        Move::SEED(tree_pos, seed_pos) => match player_moves[(p_id + 1) % 2] {
+                            Some(Move::SEED(o_tree_pos, o_seed_pos)) if o_seed_pos == seed_pos => {
+                                let tree_cell = self.board[tree_pos as usize].as_mut().unwrap();
+                                tree_cell.is_dormant = true;
+                            }
+                            _ => {
+                                player.sun -= player.seed_count as u32;
+                                player.seed_count += 1;
+                                let tree_cell = self.board[tree_pos as usize].as_mut().unwrap();
+                                tree_cell.is_dormant = true;
+                                self.board[seed_pos as usize] = Some(Cell {
+                                    player: p_id as u8,
+                                    tree: Tree::SEED,
+                                    is_dormant: true,
+                                });
+                            }
+                        },
+                        Move::GROW(cell_pos) => {
+                            let cell = self.board[cell_pos as usize].as_mut().unwrap();
+                            match cell.tree {
+                                Tree::SEED => {
+                                    player.sun -= 1 + player.small_tree_count as u32;
+                                    player.seed_count -= 1;
+                                    player.small_tree_count += 1;
+                                    cell.tree = Tree::SMALL_TREE;
+                                    cell.is_dormant = true;
+                                }
+                                Tree::SMALL_TREE => {
+                                    player.sun -= 3 + player.medium_tree_count as u32;
+                                    player.small_tree_count -= 1;
+                                    player.medium_tree_count += 1;
+                                    cell.tree = Tree::MEDIUM_TREE;
+                                    cell.is_dormant = true;
+                                }
+                                Tree::MEDIUM_TREE => {
+                                    player.sun -= 7 + player.large_tree_count as u32;
+                                    player.medium_tree_count -= 1;
+                                    player.large_tree_count += 1;
+                                    cell.tree = Tree::LARGE_TREE;
+                                    cell.is_dormant = true;
+                                }
+                                _ => panic!("This code should not be reached"),
+                            }
+                        }
+                        Move::COMPLETE(cell_pos) => {
+                            player.sun -= 4;
+                            player.score += self.nutrient as u32
+                                + match self.cache.get_soil_richness(cell_pos as usize) {
+                                    SoilRichness::UNUSABLE => panic!(),
+                                    SoilRichness::LOW_QUALITY => 0,
+                                    SoilRichness::MEDIUM_QUALITY => 2,
+                                    SoilRichness::HIGH_QUALITY => 4,
+                                };
+                            player.large_tree_count -= 1;
+                            self.board[cell_pos as usize] = None;
+                            completed_trees_count += 1;
+                        }
+                        Move::WAIT => {
+                            player.is_asleep = true;
+                        }
+                    }
+                }
 
-            cell_pairs.shuffle(&mut thread_rng());
-            cell_pairs
+                player.move_ = None;
+            }
+            self.nutrient = self.nutrient.saturating_sub(completed_trees_count);
+            self.turn_during_day += 1;
+            self.turn += 1;
         }
 
-        let mut board: [Option<Cell>; 37] = [None; 37];
-        let mut soil_richness: [SoilRichness; 37] = get_initial_soil_richness();
-
-        /* Place the initial 2 small trees for each player*/
-        let mut cell_pairs = generate_random_cell_pairs();
-        for _ in 0..2 {
-            let cells = cell_pairs.pop().unwrap();
-            board[cells.0] = Some(Cell {
-                player: 0,
-                tree: Tree::SMALL_TREE,
-                is_dormant: false,
-            });
+        /* (4) If both players are asleep, update the day, else set the next active player */
+        if self.players[0].is_asleep == true && self.players[1].is_asleep == true {
+            self.day += 1;
+            self.turn_during_day = 0;
+            self.players[0].move_ = None;
+            self.players[1].move_ = None;
+            self.players[0].is_asleep = false;
+            self.players[1].is_asleep = false;
+            self.active_player = 0;
 
-            board[cells.1] = Some(Cell {
-                player: 1,
-                tree: Tree::SMALL_TREE,
-                is_dormant: false,
-            });
-        }
+            // Reactivate all trees and update shadows
+            for (cell_pos, cell) in self.board.iter_mut().enumerate() {
+                if let Some(c) = cell {
+                    c.is_dormant = false;
+                }
+            }
 
-        /* Chosse invalid cells */
-        let invalid_cells_count: usize = thread_rng().gen_range(0..=5);
-        for _ in 0..invalid_cells_count {
-            let cells = cell_pairs.pop().unwrap();
-            soil_richness[cells.0] = SoilRichness::UNUSABLE;
-            soil_richness[cells.1] = SoilRichness::UNUSABLE;
+            // let the players collect sun points
+            if self.day < 24 {
+                let gained_sun_points = gained_sun_points(
+                    &self.board,
+                    &get_spookied_cells(&self.board, self.day, &self.cache),
+                );
+                self.players[0].sun += gained_sun_points[0];
+                self.players[1].sun += gained_sun_points[1];
+            }
+        } else {
+            let next_player = (self.active_player + 1) % 2;
+            if self.players[next_player as usize].is_asleep == false {
+                self.active_player = next_player;
+            }
         }
 
-        /* Update shadows */
-        let cache = Cache::new(soil_richness);
-
-        /* Create players */
-        let mut players = [Player {
-            move_: None,
-            sun: 0,
-            score: 0,
-            seed_count: 0,
-            small_tree_count: 2,
-            medium_tree_count: 0,
-            large_tree_count: 0,
-            is_asleep: false,
-        }; 2];
-
-        let gained_sun_points = gained_sun_points(&board, &get_spookied_cells(&board, 0, &cache));
-        players[0].sun += gained_sun_points[0];
-        players[1].sun += gained_sun_points[1];
-
-        /* Output Game */
+        /* (5) Check terminal conditions */
+        if self.day == 24 {
+            let player0 = &self.players[0];
+            let player1 = &self.players[1];
 
-        WoodSpiritGame {
-            board: board,
-            players: players,
-            nutrient: 20,
-            day: 0,
-            turn_during_day: 0,
-            turn: 0,
+            let score0 = player0.score + player0.sun / 3;
+            let score1 = player1.score + player1.sun / 3;
 
-            active_player: 0,
-            active: true,
-            winners: None,
+            if score0 > score1 {
+                self.end_game(vec![WinLossTie::Win, WinLossTie::Loss]);
+            } else if score0 < score1 {
+                self.end_game(vec![WinLossTie::Loss, WinLossTie::Win]);
+            } else {
+                let tree_count0 =
+                    player0.small_tree_count + player0.medium_tree_count + player0.large_tree_count;
+                let tree_count1 =
+                    player1.small_tree_count + player1.medium_tree_count + player1.large_tree_count;
 
-            cache,
+                if tree_count0 > tree_count1 {
+                    self.end_game(vec![WinLossTie::Win, WinLossTie::Loss]);
+                } else if tree_count0 < tree_count1 {
+                    self.end_game(vec![WinLossTie::Loss, WinLossTie::Win]);
+                } else {
+                    self.end_game(vec![WinLossTie::Tie, WinLossTie::Tie]);
+                }
+            }
         }
-    }
 
-    fn turn(&self) -> Option<Message> {
-        // If game is over, return None
-        if self.active == false {
-            return None;
-        }
+        Ok(())
+    }
 
+    // Input of [player_id] for the current turn
+    fn message(&self, player_id: u8) -> Message {
         let mut out: Vec<String> = Vec::new();
 
-        if self.turn == 0 {
+        if self.initialized_players[player_id as usize] == false {
             out.push("37".to_string());
 
             for c in 0..37 {
@@ -643,8 +785,8 @@ impl Game for WoodSpiritGame {
             }
         }
 
-        let active_player = &self.players[self.active_player as usize];
-        let other_player = &self.players[((self.active_player + 1) % 2) as usize];
+        let active_player = &self.players[player_id as usize];
+        let other_player = &self.players[((player_id + 1) % 2) as usize];
 
         out.push(format!("{}", self.day));
         out.push(format!("{}", self.nutrient));
@@ -680,7 +822,7 @@ impl Game for WoodSpiritGame {
                         Tree::MEDIUM_TREE => 2,
                         Tree::LARGE_TREE => 3,
                     },
-                    match c.player == self.active_player {
+                    match c.player == player_id {
                         true => 1,
                         false => 0,
                     },
@@ -695,7 +837,7 @@ impl Game for WoodSpiritGame {
 
         let valid_moves = valid_moves(
             &self.board,
-            self.active_player,
+            player_id,
             active_player.sun,
             active_player.seed_count,
             active_player.small_tree_count,
@@ -704,193 +846,304 @@ impl Game for WoodSpiritGame {
             &self.cache,
         );
 
-        out.push(format!("{}", valid_moves.len()));
+        out.push(format!("{}", valid_moves.len()));
+
+        for vm in valid_moves.slice().iter() {
+            out.push(format!("{}", vm));
+        }
+
+        Message {
+            player_id: player_id as usize,
+            messages: out,
+        }
+    }
+}
+
+impl Game for WoodSpiritGame {
+    fn new() -> Self {
+        WoodSpiritGame::new_with_seed(thread_rng().gen())
+    }
+
+    fn new_with_seed(seed: u64) -> Self {
+        fn generate_random_cell_pairs(rng: &mut StdRng) -> Vec<(usize, usize)> {
+            let mut cell_pairs: Vec<(usize, usize)> = Vec::new();
+
+            let mut high_quality_cells: Vec<usize> = (0..=6).collect::<Vec<usize>>();
+            high_quality_cells.shuffle(rng);
+
+            for i in 0..3 {
+                cell_pairs.push((high_quality_cells[i * 2], high_quality_cells[i * 2 + 1]));
+            }
+
+            let mut medium_quality_cells: Vec<usize> = (7..=18).collect::<Vec<usize>>();
+            medium_quality_cells.shuffle(rng);
+
+            for i in 0..6 {
+                cell_pairs.push((medium_quality_cells[i * 2], medium_quality_cells[i * 2 + 1]));
+            }
+
+            let mut low_quality_cells: Vec<usize> = (19..=36).collect::<Vec<usize>>();
+            low_quality_cells.shuffle(rng);
+
+            for i in 0..9 {
+                cell_pairs.push((low_quality_cells[i * 2], low_quality_cells[i * 2 + 1]));
+            }
+
+            cell_pairs.shuffle(rng);
+            cell_pairs
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut board: [Option<Cell>; 37] = [None; 37];
+        let mut soil_richness: [SoilRichness; 37] = get_initial_soil_richness();
+
+        /* Place the initial 2 small trees for each player*/
+        let mut cell_pairs = generate_random_cell_pairs(&mut rng);
+        for _ in 0..2 {
+            let cells = cell_pairs.pop().unwrap();
+            board[cells.0] = Some(Cell {
+                player: 0,
+                tree: Tree::SMALL_TREE,
+                is_dormant: false,
+            });
+
+            board[cells.1] = Some(Cell {
+                player: 1,
+                tree: Tree::SMALL_TREE,
+                is_dormant: false,
+            });
+        }
+
+        /* Chosse invalid cells */
+        let invalid_cells_count: usize = rng.gen_range(0..=5);
+        for _ in 0..invalid_cells_count {
+            let cells = cell_pairs.pop().unwrap();
+            soil_richness[cells.0] = SoilRichness::UNUSABLE;
+            soil_richness[cells.1] = SoilRichness::UNUSABLE;
+        }
+
+        /* Update shadows */
+        let cache = Cache::new(soil_richness);
+
+        /* Create players */
+        let mut players = [Player {
+            move_: None,
+            sun: 0,
+            score: 0,
+            seed_count: 0,
+            small_tree_count: 2,
+            medium_tree_count: 0,
+            large_tree_count: 0,
+            is_asleep: false,
+        }; 2];
+
+        let gained_sun_points = gained_sun_points(&board, &get_spookied_cells(&board, 0, &cache));
+        players[0].sun += gained_sun_points[0];
+        players[1].sun += gained_sun_points[1];
 
-        for vm in valid_moves.slice().iter() {
-            out.push(format!("{}", vm));
-        }
+        /* Output Game */
 
-        Some(Message {
-            player_id: self.active_player as usize,
-            messages: out,
-        })
-    }
+        WoodSpiritGame {
+            board: board,
+            players: players,
+            nutrient: 20,
+            day: 0,
+            turn_during_day: 0,
+            turn: 0,
 
-    fn play(&mut self, msg: String) {
-        /* (1) Parse move, assuming it is always in the right format */
-        let move_ = Move::parse_move(msg.as_str());
+            active_player: 0,
+            active: true,
+            winners: None,
+            initialized_players: [false; 2],
 
-        /* (2) Record the move */
-        self.players[self.active_player as usize].move_ = Some(move_);
+            cache,
+        }
+    }
 
-        /* (3) Check if both players have played, so that we update the game */
-        if (self.players[0].move_.is_some() && self.players[1].move_.is_some())
-            || (self.players[0].move_.is_some() && self.players[1].is_asleep == true)
-            || (self.players[0].is_asleep == true && self.players[1].move_.is_some())
-        {
-            /* 3.1 Check if moves are valid */
-            let mut player_did_a_valid_move = [true, true];
+    fn new_from_position(position: &str, _seed: u64) -> Result<Self, String> {
+        /*
+            [position] is the input that player 0 receives on CodinGame when it's its turn to play :
+            the initialization input (the cells), followed by the turn input. The valid moves at the end are optional.
+            The opponent hasn't played yet in the current round (or is asleep).
+        */
+        let mut lines = position.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+
+        let cell_count = parse_position_line(lines.next(), 1)?[0];
+        if cell_count != 37 {
+            return Err(format!("Expected 37 cells, got {}", cell_count));
+        }
 
-            for (p_id, player) in self.players.iter().enumerate() {
-                if player.is_asleep == false {
-                    player_did_a_valid_move[p_id] = valid_moves(
-                        &self.board,
-                        p_id as u8,
-                        player.sun,
-                        player.seed_count,
-                        player.small_tree_count,
-                        player.medium_tree_count,
-                        player.large_tree_count,
-                        &self.cache,
-                    )
-                    .slice()
-                    .contains(&player.move_.unwrap());
-                }
+        let mut soil_richness = [SoilRichness::UNUSABLE; 37];
+        let mut listed_cells = [false; 37];
+        for _ in 0..37 {
+            let cell = parse_position_line(lines.next(), 2)?;
+            if cell[0] < 0 || cell[0] >= 37 {
+                return Err(format!("Invalid cell index {}", cell[0]));
             }
-
-            if self.end_game_if_invalid_move(
-                &vec![self.players[0].move_, self.players[1].move_],
-                &player_did_a_valid_move,
-            ) == true
-            {
-                return;
+            if listed_cells[cell[0] as usize] {
+                return Err(format!("Cell {} is listed twice", cell[0]));
             }
+            listed_cells[cell[0] as usize] = true;
+            soil_richness[cell[0] as usize] = match cell[1] {
+                0 => SoilRichness::UNUSABLE,
+                1 => SoilRichness::LOW_QUALITY,
+                2 => SoilRichness::MEDIUM_QUALITY,
+                3 => SoilRichness::HIGH_QUALITY,
+                r => return Err(format!("Invalid richness {}", r)),
+            };
+        }
 
-            /* (3.2) Update the state */
-            let mut completed_trees_count = 0;
-            let player_moves = [self.players[0].move_.clone(), self.players[1].move_.clone()];
+        // The game ends after day 23, and the nutrients start at 20 and only decrease
+        let day = parse_position_line(lines.next(), 1)?[0];
+        if !(0..24).contains(&day) {
+            return Err(format!("Invalid day {}", day));
+        }
+        let nutrient = parse_position_line(lines.next(), 1)?[0];
+        if !(0..=20).contains(&nutrient) {
+            return Err(format!("Invalid nutrient {}", nutrient));
+        }
+        let my_sun_score = parse_position_line(lines.next(), 2)?;
+        let opp_sun_score_asleep = parse_position_line(lines.next(), 3)?;
 
-            for (p_id, player) in self.players.iter_mut().enumerate() {
-                if player.is_asleep == false {
-                    match player.move_.unwrap() {
-                        Move::SEED(tree_pos, seed_pos) => match player_moves[(p_id + 1) % 2] {
-                            Some(Move::SEED(o_tree_pos, o_seed_pos)) if o_seed_pos == seed_pos => {
-                                let tree_cell = self.board[tree_pos as usize].as_mut().unwrap();
-                                tree_cell.is_dormant = true;
-                            }
-                            _ => {
-                                player.sun -= player.seed_count as u32;
-                                player.seed_count += 1;
-                                let tree_cell = self.board[tree_pos as usize].as_mut().unwrap();
-                                tree_cell.is_dormant = true;
-                                self.board[seed_pos as usize] = Some(Cell {
-                                    player: p_id as u8,
-                                    tree: Tree::SEED,
-                                    is_dormant: true,
-                                });
-                            }
-                        },
-                        Move::GROW(cell_pos) => {
-                            let cell = self.board[cell_pos as usize].as_mut().unwrap();
-                            match cell.tree {
-                                Tree::SEED => {
-                                    player.sun -= 1 + player.small_tree_count as u32;
-                                    player.seed_count -= 1;
-                                    player.small_tree_count += 1;
-                                    cell.tree = Tree::SMALL_TREE;
-                                    cell.is_dormant = true;
-                                }
-                                Tree::SMALL_TREE => {
-                                    player.sun -= 3 + player.medium_tree_count as u32;
-                                    player.small_tree_count -= 1;
-                                    player.medium_tree_count += 1;
-                                    cell.tree = Tree::MEDIUM_TREE;
-                                    cell.is_dormant = true;
-                                }
-                                Tree::MEDIUM_TREE => {
-                                    player.sun -= 7 + player.large_tree_count as u32;
-                                    player.medium_tree_count -= 1;
-                                    player.large_tree_count += 1;
-                                    cell.tree = Tree::LARGE_TREE;
-                                    cell.is_dormant = true;
-                                }
-                                _ => panic!("This code should not be reached"),
-                            }
-                        }
-                        Move::COMPLETE(cell_pos) => {
-                            player.sun -= 4;
-                            player.score += self.nutrient as u32
-                                + match self.cache.get_soil_richness(cell_pos as usize) {
-                                    SoilRichness::UNUSABLE => panic!(),
-                                    SoilRichness::LOW_QUALITY => 0,
-                                    SoilRichness::MEDIUM_QUALITY => 2,
-                                    SoilRichness::HIGH_QUALITY => 4,
-                                };
-                            player.large_tree_count -= 1;
-                            self.board[cell_pos as usize] = None;
-                            completed_trees_count += 1;
-                        }
-                        Move::WAIT => {
-                            player.is_asleep = true;
-                        }
-                    }
-                }
+        let mut players = [Player {
+            move_: None,
+            sun: 0,
+            score: 0,
+            seed_count: 0,
+            small_tree_count: 0,
+            medium_tree_count: 0,
+            large_tree_count: 0,
+            is_asleep: false,
+        }; 2];
+        players[0].sun = parse_position_count(my_sun_score[0], "sun")?;
+        players[0].score = parse_position_count(my_sun_score[1], "score")?;
+        players[1].sun = parse_position_count(opp_sun_score_asleep[0], "sun")?;
+        players[1].score = parse_position_count(opp_sun_score_asleep[1], "score")?;
+        players[1].is_asleep = parse_position_flag(opp_sun_score_asleep[2], "asleep")?;
 
-                player.move_ = None;
+        let mut board: [Option<Cell>; 37] = [None; 37];
+        let tree_count = parse_position_line(lines.next(), 1)?[0];
+        for _ in 0..tree_count {
+            let tree = parse_position_line(lines.next(), 4)?;
+            if tree[0] < 0 || tree[0] >= 37 {
+                return Err(format!("Invalid cell index {}", tree[0]));
             }
-            self.nutrient = self.nutrient.saturating_sub(completed_trees_count);
-            self.turn_during_day += 1;
-            self.turn += 1;
+            if board[tree[0] as usize].is_some() {
+                return Err(format!("Cell {} has 2 trees", tree[0]));
+            }
+            let p_id = match parse_position_flag(tree[2], "is mine")? {
+                true => 0,
+                false => 1,
+            };
+            let is_dormant = parse_position_flag(tree[3], "dormant")?;
+            let player = &mut players[p_id];
+            board[tree[0] as usize] = Some(Cell {
+                player: p_id as u8,
+                tree: match tree[1] {
+                    0 => {
+                        player.seed_count += 1;
+                        Tree::SEED
+                    }
+                    1 => {
+                        player.small_tree_count += 1;
+                        Tree::SMALL_TREE
+                    }
+                    2 => {
+                        player.medium_tree_count += 1;
+                        Tree::MEDIUM_TREE
+                    }
+                    3 => {
+                        player.large_tree_count += 1;
+                        Tree::LARGE_TREE
+                    }
+                    t => return Err(format!("Invalid tree size {}", t)),
+                },
+                is_dormant,
+            });
         }
 
-        /* (4) If both players are asleep, update the day, else set the next active player */
-        if self.players[0].is_asleep == true && self.players[1].is_asleep == true {
-            self.day += 1;
-            self.turn_during_day = 0;
-            self.players[0].move_ = None;
-            self.players[1].move_ = None;
-            self.players[0].is_asleep = false;
-            self.players[1].is_asleep = false;
-            self.active_player = 0;
+        Ok(WoodSpiritGame {
+            board,
+            players,
+            nutrient: nutrient as u8,
+            day: day as u8,
+            turn_during_day: 0,
+            turn: 0,
 
-            // Reactivate all trees and update shadows
-            for (cell_pos, cell) in self.board.iter_mut().enumerate() {
-                if let Some(c) = cell {
-                    c.is_dormant = false;
-                }
-            }
+            active_player: 0,
+            active: true,
+            winners: None,
+            initialized_players: [false; 2],
 
-            // let the players collect sun points
-            if self.day < 24 {
-                let gained_sun_points = gained_sun_points(
-                    &self.board,
-                    &get_spookied_cells(&self.board, self.day, &self.cache),
-                );
-                self.players[0].sun += gained_sun_points[0];
-                self.players[1].sun += gained_sun_points[1];
-            }
-        } else {
-            let next_player = (self.active_player + 1) % 2;
-            if self.players[next_player as usize].is_asleep == false {
-                self.active_player = next_player;
-            }
+            cache: Cache::new(soil_richness),
+        })
+    }
+
+    fn turn(&self) -> Option<Message> {
+        // If game is over, return None
+        if self.active == false {
+            return None;
         }
 
-        /* (5) Check terminal conditions */
-        if self.day == 24 {
-            let player0 = &self.players[0];
-            let player1 = &self.players[1];
+        Some(self.message(self.active_player))
+    }
 
-            let score0 = player0.score + player0.sun / 3;
-            let score1 = player1.score + player1.sun / 3;
+    // The players that are awake play simultaneously
+    fn simultaneous_turn(&self) -> Option<Vec<Message>> {
+        // If game is over, return None
+        if !self.active {
+            return None;
+        }
 
-            if score0 > score1 {
-                self.end_game(vec![WinLossTie::Win, WinLossTie::Loss]);
-            } else if score0 < score1 {
-                self.end_game(vec![WinLossTie::Loss, WinLossTie::Win]);
-            } else {
-                let tree_count0 =
-                    player0.small_tree_count + player0.medium_tree_count + player0.large_tree_count;
-                let tree_count1 =
-                    player1.small_tree_count + player1.medium_tree_count + player1.large_tree_count;
+        Some(
+            (0..2)
+                .filter(|p| !self.players[*p as usize].is_asleep)
+                .map(|p| self.message(p))
+                .collect(),
+        )
+    }
 
-                if tree_count0 > tree_count1 {
-                    self.end_game(vec![WinLossTie::Win, WinLossTie::Loss]);
-                } else if tree_count0 < tree_count1 {
-                    self.end_game(vec![WinLossTie::Loss, WinLossTie::Win]);
-                } else {
-                    self.end_game(vec![WinLossTie::Tie, WinLossTie::Tie]);
-                }
+    fn play_simultaneous(&mut self, moves: Vec<String>) -> Result<(), Vec<record::InvalidMove>> {
+        let players: Vec<usize> = (0..2).filter(|p| !self.players[*p].is_asleep).collect();
+
+        /* (1) Parse the moves. The players whose move can't be parsed lose */
+        let mut parsed_moves = Vec::new();
+        let mut parse_errors = Vec::new();
+        for (p_id, msg) in players.iter().zip(moves.iter()) {
+            match Move::parse_move(msg.as_str()) {
+                Some(move_) => parsed_moves.push(move_),
+                None => parse_errors.push(self.parse_error(*p_id, msg)),
+            }
+        }
+        self.end_game_if_invalid_moves(2, parse_errors)?;
+
+        /* (2) Play the moves, the last one resolves them all. Illegal moves are reported as the players sent them */
+        let mut result = Ok(());
+        for move_ in parsed_moves {
+            result = self.play_move(move_);
+        }
+        result.map_err(|mut invalid_moves| {
+            for invalid_move in invalid_moves.iter_mut() {
+                let i = players
+                    .iter()
+                    .position(|p| *p == invalid_move.player as usize)
+                    .unwrap();
+                invalid_move.player_move = moves[i].clone();
+            }
+            invalid_moves
+        })
+    }
+
+    fn play(&mut self, msg: String) {
+        /* (1) Parse move. If it can't be parsed, the player loses */
+        match Move::parse_move(msg.as_str()) {
+            Some(move_) => {
+                let _ = self.play_move(move_);
+            }
+            None => {
+                let parse_error = self.parse_error(self.active_player as usize, &msg);
+                let _ = self.end_game_if_invalid_moves(2, vec![parse_error]);
             }
         }
     }
@@ -902,6 +1155,18 @@ impl Game for WoodSpiritGame {
         }
     }
 
+    fn scores(&self) -> Option<Vec<f64>> {
+        match self.winners {
+            Some(_) => Some(
+                self.players
+                    .iter()
+                    .map(|p| (p.score + p.sun / 3) as f64)
+                    .collect(),
+            ),
+            None => None,
+        }
+    }
+
     fn get_state(&self) -> record::GameState {
         /* (1) Output Board */
         fn board_pos_to_cell_id(r: usize, c: usize) -> Option<usize> {
@@ -1312,6 +1577,10 @@ impl Game for WoodSpiritGame {
         self.active = false;
         self.winners = Some((players_status[0], players_status[1]));
     }
+
+    fn reset_players(&mut self) {
+        self.initialized_players = [false; 2];
+    }
 }
 
 #[cfg(test)]
@@ -2137,6 +2406,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_new_with_seed_is_reproducible() {
+        // The first turn's message contains the soil richness of all cells and all trees
+        let game1 = WoodSpiritGame::new_with_seed(42);
+        let game2 = WoodSpiritGame::new_with_seed(42);
+        assert_eq!(
+            game1.turn().unwrap().messages,
+            game2.turn().unwrap().messages
+        );
+    }
+
     #[test]
     fn test_cells_are_dormant_after_both_players_seed() {
         let players_initial_small_trees = [[21, 32], [20, 35]];
@@ -2173,4 +2453,168 @@ mod tests {
         assert_eq!(game.players[0].sun, 2);
         assert_eq!(game.players[1].sun, 2);
     }
+
+    #[test]
+    fn test_new_from_position_same_input_as_original_game() {
+        // Play a few rounds, until it's player 0's turn again
+        let mut game = WoodSpiritGame::new_with_seed(3);
+        for m in ["WAIT", "WAIT", "WAIT", "WAIT"] {
+            game.play(m.to_string());
+        }
+        assert_eq!(game.active_player, 0);
+
+        // The position is what player 0 would receive from its first turn, with the current turn's input
+        let mut first_turn_input = WoodSpiritGame::new_with_seed(3).turn().unwrap().messages;
+        first_turn_input.truncate(38);
+        let turn_input = game.turn().unwrap().messages;
+        let position = [first_turn_input, turn_input.clone()].concat().join("\n");
+
+        let mut position_game = WoodSpiritGame::new_from_position(&position, 0).unwrap();
+        let position_input = position_game.turn().unwrap().messages;
+        assert_eq!(position_input[38..], turn_input[..]);
+
+        // Both games go on the same way
+        for m in ["WAIT", "WAIT"] {
+            game.play(m.to_string());
+            position_game.play(m.to_string());
+        }
+        assert_eq!(
+            position_game.turn().unwrap().messages,
+            game.turn().unwrap().messages
+        );
+    }
+
+    #[test]
+    fn test_new_from_position_invalid() {
+        assert!(WoodSpiritGame::new_from_position("37\n0 3", 0).is_err());
+        assert!(WoodSpiritGame::new_from_position("12", 0).is_err());
+
+        // The first turn input, whose lines are replaced : 37 cells, then the day, the nutrients,
+        // the sun & score of each player and the trees
+        let position = WoodSpiritGame::new_with_seed(3).turn().unwrap().messages;
+        let with_lines = |lines: &[(usize, &str)]| {
+            let mut position = position.clone();
+            for (i, line) in lines.iter() {
+                position[*i] = line.to_string();
+            }
+            WoodSpiritGame::new_from_position(&position.join("\n"), 0)
+        };
+        assert!(with_lines(&[]).is_ok());
+
+        assert!(with_lines(&[(2, position[1].as_str())]).is_err());
+        assert!(with_lines(&[(38, "24")]).is_err());
+        assert!(with_lines(&[(38, "-1")]).is_err());
+        assert!(with_lines(&[(39, "-1")]).is_err());
+        assert!(with_lines(&[(39, "300")]).is_err());
+        assert!(with_lines(&[(40, "-2 0")]).is_err());
+        assert!(with_lines(&[(40, "0 -3")]).is_err());
+        assert!(with_lines(&[(41, "0 0 2")]).is_err());
+        assert!(with_lines(&[(42, "1"), (43, "0 1 2 0")]).is_err());
+        assert!(with_lines(&[(42, "1"), (43, "0 1 1 5")]).is_err());
+        assert!(with_lines(&[(42, "2"), (43, "0 1 1 0"), (44, "0 1 0 0")]).is_err());
+        assert!(with_lines(&[(42, "2"), (43, "0 1 1 0"), (44, "1 1 0 0")]).is_ok());
+    }
+
+    #[test]
+    fn test_recorded_runs_replay_identically() {
+        use common::replay;
+        use common::simulator::{self, PlayerPlayFunction, RunOptions};
+
+        let record_dir =
+            std::env::temp_dir().join(format!("wood_spirit_replay_{}", std::process::id()));
+        std::fs::create_dir_all(&record_dir).unwrap();
+
+        let players = vec![
+            PlayerPlayFunction {
+                func: &crate::player_random::play,
+                params: None,
+                name: None,
+            };
+            2
+        ];
+        simulator::run(
+            WoodSpiritGame::new_with_seed,
+            &players,
+            3,
+            Some(record_dir.to_str().unwrap().to_string()),
+            false,
+            &RunOptions {
+                seed: Some(7),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        for record_file in std::fs::read_dir(&record_dir).unwrap() {
+            let divergences =
+                replay::replay_record(WoodSpiritGame::new_with_seed, record_file.unwrap().path())
+                    .unwrap();
+            assert!(divergences.is_empty(), "{:?}", divergences);
+        }
+        std::fs::remove_dir_all(&record_dir).unwrap();
+    }
+
+    #[test]
+    fn test_simultaneous_turn_same_as_sequential_turns() {
+        let mut game = WoodSpiritGame::new_with_seed(0);
+        let mut simultaneous_game = WoodSpiritGame::new_with_seed(0);
+
+        // Player 1 goes to sleep on the first turn : player 0 then plays alone
+        let mut players_per_turn = Vec::new();
+        for _ in 0..2 {
+            let mut moves = Vec::new();
+            for message in simultaneous_game.simultaneous_turn().unwrap() {
+                let sequential_message = game.turn().unwrap();
+                assert_eq!(sequential_message.player_id, message.player_id);
+                assert_eq!(sequential_message.messages, message.messages);
+
+                let player_move = match message.player_id {
+                    0 => message
+                        .messages
+                        .iter()
+                        .find(|m| m.starts_with("GROW") || m.starts_with("SEED"))
+                        .unwrap()
+                        .clone(),
+                    _ => "WAIT".to_string(),
+                };
+                game.play(player_move.clone());
+                moves.push(player_move);
+            }
+
+            players_per_turn.push(moves.len());
+            simultaneous_game.play_simultaneous(moves).unwrap();
+            assert_eq!(simultaneous_game.get_state().state, game.get_state().state);
+        }
+        assert_eq!(players_per_turn, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_play_simultaneous_reports_invalid_moves() {
+        let mut game = WoodSpiritGame::new_with_seed(0);
+        let invalid_moves = game
+            .play_simultaneous(vec!["COMPLETE 0".to_string(), "WAIT".to_string()])
+            .unwrap_err();
+        assert_eq!(invalid_moves.len(), 1);
+        assert_eq!(invalid_moves[0].player, 0);
+        assert_eq!(invalid_moves[0].kind, record::InvalidMoveKind::IllegalMove);
+        assert_eq!(invalid_moves[0].player_move, "COMPLETE 0");
+        assert!(invalid_moves[0].legal_moves.contains(&"WAIT".to_string()));
+        assert_eq!(
+            game.winners(),
+            Some(vec![WinLossTie::Loss, WinLossTie::Win])
+        );
+
+        // A move that can't be parsed loses, whatever the other move
+        let mut game = WoodSpiritGame::new_with_seed(0);
+        let invalid_moves = game
+            .play_simultaneous(vec!["COMPLETE 0".to_string(), "GROW".to_string()])
+            .unwrap_err();
+        assert_eq!(invalid_moves.len(), 1);
+        assert_eq!(invalid_moves[0].player, 1);
+        assert_eq!(invalid_moves[0].kind, record::InvalidMoveKind::ParseError);
+        assert_eq!(
+            game.winners(),
+            Some(vec![WinLossTie::Win, WinLossTie::Loss])
+        );
+    }
 }
@@ -1,75 +1,64 @@
-use common::simulator;
-use common::simulator::PlayerPlayFunction;
-use common::Game;
-use std::time::Instant;
 mod game_wood_spirit;
+mod player_duct_5;
 mod player_mcts_3;
 mod player_mcts_4;
 mod player_random;
+use common::cli;
+use common::simulator::{Bot, BotRegistry, PlayerPlayFunction, RunOptions, TimeLimits};
 use game_wood_spirit::WoodSpiritGame;
+use std::time::Duration;
 
-fn run() {
-    const STATS: bool = true;
-    const RECORD: bool = false;
-    const RUN_PERMUT: bool = true;
-    const RUNS: u32 = 10;
-
-    let record_path = "C:/Users/hhour/Desktop/codingame-rust/wood_spirit/output";
-
-    let players: Vec<PlayerPlayFunction> = vec![
-        PlayerPlayFunction {
+// e.g. cargo run --release -- run mcts mcts@3 --runs 100 --permut --paired
+fn main() {
+    let mut registry = BotRegistry::new();
+    registry.register(Bot {
+        name: "mcts",
+        version: "3",
+        description: "MCTS with random rollouts",
+        player: PlayerPlayFunction {
+            func: &player_mcts_3::play,
+            params: None,
+            name: None,
+        },
+    });
+    registry.register(Bot {
+        name: "mcts",
+        version: "4",
+        description: "MCTS with random rollouts, exploration coefficient 0.41 (params : exploration, time_limit_ms)",
+        player: PlayerPlayFunction {
             func: &player_mcts_4::play,
             params: None,
+            name: None,
         },
-        PlayerPlayFunction {
-            func: &player_mcts_3::play,
+    });
+    registry.register(Bot {
+        name: "duct",
+        version: "5",
+        description: "Decoupled UCT : MCTS with simultaneous moves, random rollouts (params : exploration, time_limit_ms)",
+        player: PlayerPlayFunction {
+            func: &player_duct_5::play,
             params: None,
+            name: None,
         },
-    ];
-
-    let start = Instant::now();
-
-    let result;
-    if RUN_PERMUT == true {
-        result = simulator::run_permut(
-            WoodSpiritGame::new,
-            &players,
-            RUNS,
-            match RECORD {
-                true => Some(record_path.to_string()),
-                false => None,
-            },
-            STATS,
-        );
-    } else {
-        result = simulator::run(
-            WoodSpiritGame::new,
-            &players,
-            RUNS,
-            match RECORD {
-                true => Some(record_path.to_string()),
-                false => None,
-            },
-            STATS,
-        );
-    }
-
-    let duration = start.elapsed();
-    println!(
-        "Ran {} games in {:?}",
-        match RUN_PERMUT {
-            true => RUNS * 2,
-            false => RUNS,
+    });
+    registry.register(Bot {
+        name: "random",
+        version: "1",
+        description: "Plays a random valid move",
+        player: PlayerPlayFunction {
+            func: &player_random::play,
+            params: None,
+            name: None,
         },
-        duration
-    );
+    });
 
-    if STATS == true {
-        let stats = result.unwrap().unwrap();
-        println!("Win statistics : {:?}", stats.players_win_loss);
-    }
-}
+    let options = RunOptions {
+        time_limits: Some(TimeLimits {
+            first_turn: Duration::from_millis(1000),
+            turn: Duration::from_millis(100),
+        }),
+        ..Default::default()
+    };
 
-fn main() {
-    run();
+    cli::main::<WoodSpiritGame>(&registry, &[], &options);
 }
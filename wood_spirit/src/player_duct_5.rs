@@ -0,0 +1,1075 @@
+use std::sync::mpsc::{Receiver, Sender};
+
+use self::game::SoilRichness;
+
+macro_rules! parse_input {
+    ($x:expr, $t:ident) => {
+        $x.trim().parse::<$t>().unwrap()
+    };
+}
+
+#[allow(non_snake_case)]
+#[derive(Clone, Copy)]
+pub struct StackVector<T: Copy + Clone + Default, const MAX_SIZE: usize> {
+    pub arr: [T; MAX_SIZE],
+    pub len: usize,
+}
+
+#[allow(dead_code)]
+impl<T: Copy + Clone + Default, const MAX_SIZE: usize> StackVector<T, MAX_SIZE> {
+    pub fn new() -> Self {
+        Self {
+            arr: [T::default(); MAX_SIZE],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, e: T) {
+        self.arr[self.len] = e;
+        self.len += 1;
+    }
+
+    pub fn slice(&self) -> &[T] {
+        &self.arr[0..self.len]
+    }
+
+    pub fn slice_mut(&mut self) -> &mut [T] {
+        &mut self.arr[0..self.len]
+    }
+
+    pub fn get(&self, idx: usize) -> &T {
+        &self.arr[idx]
+    }
+
+    pub fn get_mut(&mut self, idx: usize) -> &mut T {
+        &mut self.arr[idx]
+    }
+
+    pub fn remove(&mut self, idx: usize) -> T {
+        let removed_element = self.arr[idx];
+
+        for i in idx..self.len - 1 {
+            self.arr[i] = self.arr[i + 1];
+        }
+        self.len -= 1;
+
+        removed_element
+    }
+
+    pub fn remove_multi<const NB_ELEMENTS: usize>(
+        &mut self,
+        mut indices: [usize; NB_ELEMENTS],
+    ) -> [T; NB_ELEMENTS] {
+        let mut removed_elements: [T; NB_ELEMENTS] = [Default::default(); NB_ELEMENTS];
+
+        indices.sort();
+        for i in 0..NB_ELEMENTS {
+            removed_elements[i] = self.remove(indices[i] - i);
+        }
+
+        removed_elements
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn from(v: &[T]) -> StackVector<T, MAX_SIZE> {
+        let mut sv: StackVector<T, MAX_SIZE> = StackVector::new();
+
+        for e in v.iter() {
+            sv.push(e.clone());
+        }
+        sv
+    }
+}
+
+mod game {
+
+    use super::StackVector;
+    use rand::seq::SliceRandom;
+    use rand::Rng;
+    use std::collections::HashSet;
+    use std::fmt;
+
+    /* A player with T trees on a board with F free cells (T + F <= 37) has at most T * F SEED moves,
+    T GROW or COMPLETE moves and WAIT : 19 * 18 + 19 + 1 at most */
+    pub const MAX_VALID_MOVES: usize = 362;
+
+    pub type GameScore = [f32; 4];
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum WinLossTie {
+        Win,
+        Loss,
+        Tie,
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum Move {
+        GROW(u8),
+        COMPLETE(u8),
+        SEED(u8, u8),
+        WAIT,
+    }
+
+    impl fmt::Display for Move {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Move::GROW(t) => write!(f, "GROW {}", t),
+                Move::COMPLETE(t) => write!(f, "COMPLETE {}", t),
+                Move::SEED(t, c) => write!(f, "SEED {} {}", t, c),
+                Move::WAIT => write!(f, "WAIT"),
+            }
+        }
+    }
+
+    impl Default for Move {
+        fn default() -> Self {
+            Move::WAIT
+        }
+    }
+
+    #[allow(non_camel_case_types)]
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Hash, Debug)]
+    pub enum Tree {
+        SEED = 0,
+        SMALL_TREE = 1,
+        MEDIUM_TREE = 2,
+        LARGE_TREE = 3,
+    }
+
+    #[allow(non_camel_case_types)]
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub enum SoilRichness {
+        UNUSABLE,
+        LOW_QUALITY,
+        MEDIUM_QUALITY,
+        HIGH_QUALITY,
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Cell {
+        pub player: u8,
+        pub tree: Tree,
+        pub is_dormant: bool,
+    }
+
+    #[derive(Clone, Copy, Default, Debug)]
+    pub struct Player {
+        pub sun: u32,
+        pub score: u32,
+
+        pub seed_count: u8,
+        pub small_tree_count: u8,
+        pub medium_tree_count: u8,
+        pub large_tree_count: u8,
+
+        pub is_asleep: bool,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct State {
+        pub board: [Option<Cell>; 37],
+        pub players: [Player; 2],
+
+        pub nutrient: u8,
+
+        pub day: u8,
+        pub turn_during_day: u8,
+
+        pub active: bool,
+        pub winners: Option<(WinLossTie, WinLossTie)>,
+    }
+
+    /* #region [Public functions] */
+    pub fn valid_moves(
+        state: &State,
+        p_id: u8,
+        cache: &Cache,
+    ) -> StackVector<Move, MAX_VALID_MOVES> {
+        let board = &state.board;
+        let p_sun = state.players[p_id as usize].sun;
+        let p_seed_count = state.players[p_id as usize].seed_count;
+        let p_small_tree_count = state.players[p_id as usize].small_tree_count;
+        let p_medium_tree_count = state.players[p_id as usize].medium_tree_count;
+        let p_large_tree_count = state.players[p_id as usize].large_tree_count;
+
+        let mut valid_moves: StackVector<Move, MAX_VALID_MOVES> = StackVector::new();
+
+        // Only seed when there's no seed on the board, and while the seeds can still grow
+        let can_seed = p_seed_count == 0 && state.day <= 21;
+
+        for (cell_pos, cell) in board.iter().enumerate() {
+            match cell {
+                Some(c) => {
+                    if c.player == p_id && !c.is_dormant {
+                        match c.tree {
+                            Tree::SEED => {
+                                if p_sun >= 1 + p_small_tree_count as u32 {
+                                    valid_moves.push(Move::GROW(cell_pos as u8));
+                                }
+                            }
+                            Tree::SMALL_TREE => {
+                                if p_sun >= 3 + p_medium_tree_count as u32 {
+                                    valid_moves.push(Move::GROW(cell_pos as u8));
+                                }
+
+                                if can_seed {
+                                    for neighbor in
+                                        cache.get_seedable_neighbors(cell_pos, Tree::SMALL_TREE)
+                                    {
+                                        if board[*neighbor].is_none() {
+                                            valid_moves
+                                                .push(Move::SEED(cell_pos as u8, *neighbor as u8));
+                                        }
+                                    }
+                                }
+                            }
+                            Tree::MEDIUM_TREE => {
+                                if p_sun >= 7 + p_large_tree_count as u32 {
+                                    valid_moves.push(Move::GROW(cell_pos as u8))
+                                }
+
+                                if can_seed {
+                                    for neighbor in
+                                        cache.get_seedable_neighbors(cell_pos, Tree::MEDIUM_TREE)
+                                    {
+                                        if board[*neighbor].is_none() {
+                                            valid_moves
+                                                .push(Move::SEED(cell_pos as u8, *neighbor as u8));
+                                        }
+                                    }
+                                }
+                            }
+                            Tree::LARGE_TREE => {
+                                if p_sun >= 4 && state.day >= 11 {
+                                    valid_moves.push(Move::COMPLETE(cell_pos as u8))
+                                }
+                                if can_seed {
+                                    for neighbor in
+                                        cache.get_seedable_neighbors(cell_pos, Tree::LARGE_TREE)
+                                    {
+                                        if board[*neighbor].is_none() {
+                                            valid_moves
+                                                .push(Move::SEED(cell_pos as u8, *neighbor as u8));
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                None => {}
+            }
+        }
+
+        valid_moves.push(Move::WAIT);
+        valid_moves
+    }
+
+    pub fn random_valid_move<R: Rng>(
+        state: &State,
+        player: u8,
+        cache: &Cache,
+        rng: &mut R,
+    ) -> Move {
+        *valid_moves(state, player, cache)
+            .slice()
+            .choose(rng)
+            .unwrap()
+    }
+
+    /*
+        Plays a turn : the moves of both players are resolved together, as the game does.
+        [moves] has the move of each player, None if the player is asleep
+    */
+    pub fn update_state(state: &mut State, moves: [Option<Move>; 2], cache: &Cache) {
+        /* (1) Update the state */
+        let mut completed_trees_count = 0;
+
+        for (p_id, player) in state.players.iter_mut().enumerate() {
+            if player.is_asleep {
+                continue;
+            }
+            match moves[p_id].unwrap() {
+                Move::SEED(tree_pos, seed_pos) => match moves[(p_id + 1) % 2] {
+                    // Both players seed the same cell : the seeds aren't planted, but the trees are used
+                    Some(Move::SEED(_, o_seed_pos)) if o_seed_pos == seed_pos => {
+                        let tree_cell = state.board[tree_pos as usize].as_mut().unwrap();
+                        tree_cell.is_dormant = true;
+                    }
+                    _ => {
+                        player.sun -= player.seed_count as u32;
+                        player.seed_count += 1;
+                        let tree_cell = state.board[tree_pos as usize].as_mut().unwrap();
+                        tree_cell.is_dormant = true;
+                        state.board[seed_pos as usize] = Some(Cell {
+                            player: p_id as u8,
+                            tree: Tree::SEED,
+                            is_dormant: true,
+                        });
+                    }
+                },
+                Move::GROW(cell_pos) => {
+                    let cell = state.board[cell_pos as usize].as_mut().unwrap();
+                    match cell.tree {
+                        Tree::SEED => {
+                            player.sun -= 1 + player.small_tree_count as u32;
+                            player.seed_count -= 1;
+                            player.small_tree_count += 1;
+                            cell.tree = Tree::SMALL_TREE;
+                            cell.is_dormant = true;
+                        }
+                        Tree::SMALL_TREE => {
+                            player.sun -= 3 + player.medium_tree_count as u32;
+                            player.small_tree_count -= 1;
+                            player.medium_tree_count += 1;
+                            cell.tree = Tree::MEDIUM_TREE;
+                            cell.is_dormant = true;
+                        }
+                        Tree::MEDIUM_TREE => {
+                            player.sun -= 7 + player.large_tree_count as u32;
+                            player.medium_tree_count -= 1;
+                            player.large_tree_count += 1;
+                            cell.tree = Tree::LARGE_TREE;
+                            cell.is_dormant = true;
+                        }
+                        _ => panic!("This code should not be reached"),
+                    }
+                }
+                Move::COMPLETE(cell_pos) => {
+                    player.sun -= 4;
+                    player.score += state.nutrient as u32
+                        + match cache.get_soil_richness(cell_pos as usize) {
+                            SoilRichness::UNUSABLE => panic!(),
+                            SoilRichness::LOW_QUALITY => 0,
+                            SoilRichness::MEDIUM_QUALITY => 2,
+                            SoilRichness::HIGH_QUALITY => 4,
+                        };
+                    player.large_tree_count -= 1;
+                    state.board[cell_pos as usize] = None;
+                    completed_trees_count += 1;
+                }
+                Move::WAIT => {
+                    player.is_asleep = true;
+                }
+            }
+        }
+        state.nutrient = state.nutrient.saturating_sub(completed_trees_count);
+        state.turn_during_day += 1;
+
+        /* (2) If both players are asleep, update the day */
+        if state.players[0].is_asleep && state.players[1].is_asleep {
+            state.day += 1;
+            state.turn_during_day = 0;
+            state.players[0].is_asleep = false;
+            state.players[1].is_asleep = false;
+
+            // Reactivate all trees and update shadows
+            for c in state.board.iter_mut().flatten() {
+                c.is_dormant = false;
+            }
+
+            // let the players collect sun points
+            if state.day < 24 {
+                let gained_sun_points = gained_sun_points(
+                    &state.board,
+                    &get_spookied_cells(&state.board, state.day, cache),
+                );
+                state.players[0].sun += gained_sun_points[0];
+                state.players[1].sun += gained_sun_points[1];
+            }
+        }
+
+        /* (3) Check terminal conditions */
+        if state.day == 24 {
+            let player0 = &state.players[0];
+            let player1 = &state.players[1];
+
+            let score0 = player0.score + player0.sun / 3;
+            let score1 = player1.score + player1.sun / 3;
+
+            if score0 > score1 {
+                state.active = false;
+                state.winners = Some((WinLossTie::Win, WinLossTie::Loss));
+            } else if score0 < score1 {
+                state.active = false;
+                state.winners = Some((WinLossTie::Loss, WinLossTie::Win));
+            } else {
+                let tree_count0 =
+                    player0.small_tree_count + player0.medium_tree_count + player0.large_tree_count;
+                let tree_count1 =
+                    player1.small_tree_count + player1.medium_tree_count + player1.large_tree_count;
+
+                if tree_count0 > tree_count1 {
+                    state.active = false;
+                    state.winners = Some((WinLossTie::Win, WinLossTie::Loss));
+                } else if tree_count0 < tree_count1 {
+                    state.active = false;
+                    state.winners = Some((WinLossTie::Loss, WinLossTie::Win));
+                } else {
+                    state.active = false;
+                    state.winners = Some((WinLossTie::Tie, WinLossTie::Tie));
+                }
+            }
+        }
+    }
+
+    /* #endregion */
+
+    /* #region(collapsed) [Private functions] */
+    fn gained_sun_points(board: &[Option<Cell>; 37], spookied_cells: &[bool; 37]) -> [u32; 2] {
+        let mut gained_sun_points_per_players = [0; 2];
+        for (cell_pos, cell) in board.iter().enumerate() {
+            if let Some(c) = cell {
+                if !spookied_cells[cell_pos] {
+                    gained_sun_points_per_players[c.player as usize] += match c.tree {
+                        Tree::SEED => 0,
+                        Tree::SMALL_TREE => 1,
+                        Tree::MEDIUM_TREE => 2,
+                        Tree::LARGE_TREE => 3,
+                    }
+                }
+            }
+        }
+
+        gained_sun_points_per_players
+    }
+
+    fn get_spookied_cells(board: &[Option<Cell>; 37], day: u8, cache: &Cache) -> [bool; 37] {
+        let mut spookied_cells = [false; 37];
+
+        for (cell_pos, cell) in board.iter().enumerate() {
+            if let Some(c) = cell {
+                if c.tree != Tree::SEED {
+                    let shadowed_cells = cache.get_shadowed_cells(cell_pos, c.tree, day as usize);
+                    for shadowed_cell_pos in shadowed_cells {
+                        if let Some(shadowed_cell) = &board[*shadowed_cell_pos] {
+                            if c.tree >= shadowed_cell.tree {
+                                spookied_cells[*shadowed_cell_pos] = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        spookied_cells
+    }
+
+    pub fn is_terminal(state: &State) -> bool {
+        !state.active
+    }
+
+    pub fn get_scores(state: &State) -> GameScore {
+        match state.winners {
+            Some((WinLossTie::Win, WinLossTie::Loss)) => [1.0, 0.0, 0.0, 0.0],
+            Some((WinLossTie::Loss, WinLossTie::Win)) => [0.0, 1.0, 0.0, 0.0],
+            Some((WinLossTie::Tie, WinLossTie::Tie)) => [0.5, 0.5, 0.0, 0.0],
+            _ => panic!(),
+        }
+    }
+
+    /* #endregion */
+
+    /* #region(collapsed) [Cache] */
+    pub struct Cache {
+        soil_richness: [SoilRichness; 37],
+        seedable_neighbors: Vec<Vec<Vec<usize>>>, //[[Vec<usize>;3];37] : [cell_pos][tree size] => vector of seedable positions
+        shadowed_cells: Vec<Vec<Vec<Vec<usize>>>>, // [Vec<usize>;3;6;37]: [cell_pos][day][tree size] => vector of shadowed cell positions
+    }
+
+    impl Cache {
+        pub fn new(soil_richness: [SoilRichness; 37]) -> Self {
+            let cell_neighbors = Cache::init_cell_neighbors();
+            let seedable_neighbors =
+                Cache::init_seedable_neighbors(&cell_neighbors, &soil_richness);
+            let shadowed_cells = Cache::init_shadowed_cells(&cell_neighbors);
+            Self {
+                soil_richness,
+                seedable_neighbors,
+                shadowed_cells,
+            }
+        }
+
+        fn init_cell_neighbors() -> [[[Option<usize>; 3]; 6]; 37] {
+            let distance_1_neighbors: [[isize; 6]; 37] = [
+                [1, 2, 3, 4, 5, 6],
+                [7, 8, 2, 0, 6, 18],
+                [8, 9, 10, 3, 0, 1],
+                [2, 10, 11, 12, 4, 0],
+                [0, 3, 12, 13, 14, 5],
+                [6, 0, 4, 14, 15, 16],
+                [18, 1, 0, 5, 16, 17],
+                [19, 20, 8, 1, 18, 36],
+                [20, 21, 9, 2, 1, 7],
+                [21, 22, 23, 10, 2, 8],
+                [9, 23, 24, 11, 3, 2],
+                [10, 24, 25, 26, 12, 3],
+                [3, 11, 26, 27, 13, 4],
+                [4, 12, 27, 28, 29, 14],
+                [5, 4, 13, 29, 30, 15],
+                [16, 5, 14, 30, 31, 32],
+                [17, 6, 5, 15, 32, 33],
+                [35, 18, 6, 16, 33, 34],
+                [36, 7, 1, 6, 17, 35],
+                [-1, -1, 20, 7, 36, -1],
+                [-1, -1, 21, 8, 7, 19],
+                [-1, -1, 22, 9, 8, 20],
+                [-1, -1, -1, 23, 9, 21],
+                [22, -1, -1, 24, 10, 9],
+                [23, -1, -1, 25, 11, 10],
+                [24, -1, -1, -1, 26, 11],
+                [11, 25, -1, -1, 27, 12],
+                [12, 26, -1, -1, 28, 13],
+                [13, 27, -1, -1, -1, 29],
+                [14, 13, 28, -1, -1, 30],
+                [15, 14, 29, -1, -1, 31],
+                [32, 15, 30, -1, -1, -1],
+                [33, 16, 15, 31, -1, -1],
+                [34, 17, 16, 32, -1, -1],
+                [-1, 35, 17, 33, -1, -1],
+                [-1, 36, 18, 17, 34, -1],
+                [-1, 19, 7, 18, 35, -1],
+            ];
+
+            let mut cell_neighbors: [[[Option<usize>; 3]; 6]; 37] = [[[None; 3]; 6]; 37];
+
+            for cell_pos in 0..37 {
+                for direction in 0..6 {
+                    for distance in 0..3 {
+                        let mut neighbor_cell: Option<usize> = Some(cell_pos);
+                        for _ in 0..=distance {
+                            let neighbor_cell_temp =
+                                distance_1_neighbors[neighbor_cell.unwrap()][direction];
+                            if neighbor_cell_temp != -1 {
+                                neighbor_cell = Some(neighbor_cell_temp as usize);
+                            } else {
+                                neighbor_cell = None;
+                                break;
+                            }
+                        }
+                        cell_neighbors[cell_pos][direction][distance] = neighbor_cell;
+                    }
+                }
+            }
+
+            cell_neighbors
+        }
+
+        fn init_seedable_neighbors(
+            cell_neighbors: &[[[Option<usize>; 3]; 6]; 37],
+            soil_richness: &[SoilRichness; 37],
+        ) -> Vec<Vec<Vec<usize>>> {
+            let mut seedable_neighbors: Vec<Vec<Vec<usize>>> = vec![vec![vec![]; 3]; 37];
+
+            for cell_pos in 0..37 {
+                for tree_size in 1..=3 {
+                    let mut result: HashSet<usize> = HashSet::new();
+
+                    let mut visited: Vec<usize> = Vec::new();
+                    visited.push(cell_pos);
+
+                    for _ in 1..=tree_size {
+                        let mut visited_new: Vec<usize> = Vec::new();
+                        for c in visited {
+                            for direction in 0..6 {
+                                let neighbor = cell_neighbors[c][direction][0];
+                                if let Some(n) = neighbor {
+                                    if soil_richness[n] != SoilRichness::UNUSABLE {
+                                        result.insert(n);
+                                    }
+                                    visited_new.push(n);
+                                }
+                            }
+                        }
+                        visited = visited_new;
+                    }
+                    result.remove(&cell_pos);
+                    // remove cells that are at distance 1
+                    for direction in 0..6 {
+                        let neighbor = cell_neighbors[cell_pos][direction][0];
+                        if let Some(n) = neighbor {
+                            result.remove(&n);
+                        }
+                    }
+
+                    seedable_neighbors[cell_pos][tree_size - 1] =
+                        result.into_iter().collect::<Vec<usize>>();
+                }
+            }
+
+            seedable_neighbors
+        }
+
+        fn init_shadowed_cells(
+            cell_neighbors: &[[[Option<usize>; 3]; 6]; 37],
+        ) -> Vec<Vec<Vec<Vec<usize>>>> {
+            let mut shadowed_cells: Vec<Vec<Vec<Vec<usize>>>> = vec![vec![vec![vec![]; 3]; 6]; 37];
+            for cell_pos in 0..37 {
+                for day in 0..6 {
+                    for tree_size in 1..=3 {
+                        for distance in 1..=tree_size {
+                            let shadowed_cell = cell_neighbors[cell_pos][day][distance - 1];
+                            if let Some(shadowed_cell_pos) = shadowed_cell {
+                                shadowed_cells[cell_pos][day][tree_size - 1]
+                                    .push(shadowed_cell_pos);
+                            }
+                        }
+                    }
+                }
+            }
+
+            shadowed_cells
+        }
+
+        fn get_soil_richness(&self, cell_pos: usize) -> SoilRichness {
+            self.soil_richness[cell_pos]
+        }
+
+        fn get_seedable_neighbors(&self, tree_pos: usize, tree: Tree) -> &[usize] {
+            let tree_size: usize = match tree {
+                Tree::SEED => 0,
+                Tree::SMALL_TREE => 1,
+                Tree::MEDIUM_TREE => 2,
+                Tree::LARGE_TREE => 3,
+            };
+
+            &self.seedable_neighbors[tree_pos][tree_size - 1]
+        }
+
+        fn get_shadowed_cells(&self, tree_pos: usize, tree: Tree, day: usize) -> &[usize] {
+            let tree_size: usize = match tree {
+                Tree::SEED => 0,
+                Tree::SMALL_TREE => 1,
+                Tree::MEDIUM_TREE => 2,
+                Tree::LARGE_TREE => 3,
+            };
+
+            // Here I assume that there will never be a seed
+            &self.shadowed_cells[tree_pos][day % 6][tree_size - 1]
+        }
+    }
+
+    /* #endregion */
+}
+
+mod duct {
+
+    use super::game;
+    use rand::rngs::ThreadRng;
+    use rand::seq::SliceRandom;
+    use std::collections::HashMap;
+    use std::time::Instant;
+
+    /*
+        Decoupled UCT : MCTS for the simultaneous moves of the game. A node is a state where the awake players
+        move together, and each player has its own statistics of its moves at the node. Each player chooses its move
+        with UCB on its own statistics, without knowing the move of the other, and the pair of moves (the joint move)
+        leads to a child. The children are created the first time their joint move is played : a node can have
+        thousands of them (one for each pair of moves), so they're found with a map rather than a list.
+    */
+
+    const MAX_NODE_COUNT: usize = 300_000;
+    const MAX_MOVE_STATS_COUNT: usize = 3_000_000;
+    pub const TIME_LIMIT_MS: u128 = 98;
+    // Higher than in the sequential MCTS : each player has to keep trying its moves against the opponent's
+    // moves, whose statistics change as the search goes (1.5 was the best of 0.2 - 2.5 against player_mcts_4)
+    pub const EXPLORATION: f32 = 1.5;
+
+    // Statistics of a move of a player at a node
+    #[derive(Clone, Copy, Default)]
+    struct MoveStats {
+        move_: game::Move,
+        visits: u32,
+        score: f32, // total score of the player when it chose this move
+    }
+
+    #[derive(Clone, Copy, Default)]
+    struct Node {
+        joint_move: [u16; 2], // index of the move of each player, among its moves at the parent, that lead to this node
+
+        parent: Option<usize>,
+        expanded: bool,
+
+        // Moves of each player at this node, in the move statistics arena. A player that is asleep has none
+        stats_first: [usize; 2],
+        stats_count: [u16; 2],
+
+        visits: u32,
+    }
+
+    pub struct DUCT {
+        arr: Vec<Node>,
+        len: usize,
+        stats: Vec<MoveStats>,
+        stats_len: usize,
+        children: HashMap<(usize, [u16; 2]), usize>, // (parent, joint move) => child
+        nb_simulations: u32,
+        rng: ThreadRng,
+
+        exploration: f32,
+        time_limit_ms: u128,
+    }
+
+    pub fn new(exploration: f32, time_limit_ms: u128) -> DUCT {
+        DUCT {
+            arr: vec![Default::default(); MAX_NODE_COUNT],
+            len: 0,
+            stats: vec![Default::default(); MAX_MOVE_STATS_COUNT],
+            stats_len: 0,
+            children: HashMap::with_capacity(MAX_NODE_COUNT),
+            nb_simulations: 0,
+            rng: rand::thread_rng(),
+
+            exploration,
+            time_limit_ms,
+        }
+    }
+
+    impl DUCT {
+        pub fn best_move(&mut self, root_state: &game::State, cache: &game::Cache) -> game::Move {
+            /*
+                Find the best move of player 0, starting from State [root_state]
+            */
+            let start = Instant::now();
+            self.init();
+
+            loop {
+                let mut state = root_state.clone();
+
+                let leaf_node_idx = self.select(&mut state, cache);
+
+                let score = self.simulate(&mut state, cache);
+
+                self.backpropagate(leaf_node_idx, score);
+
+                self.nb_simulations += 1;
+
+                if start.elapsed().as_millis() >= self.time_limit_ms
+                    || self.len >= MAX_NODE_COUNT
+                    || self.stats_len > MAX_MOVE_STATS_COUNT - 2 * game::MAX_VALID_MOVES
+                {
+                    break;
+                }
+            }
+
+            eprintln!(
+                "[DUCT P5] End. Sending best move after expanding {} nodes and running {} simulations in {:?}",
+                self.len, self.nb_simulations, start.elapsed()
+            );
+
+            // When time is up, choose the move that was chosen the most. Its mean score depends on the moves
+            // of the opponent it was played against, which changed during the search
+            let root = &self.arr[0];
+            self.stats[root.stats_first[0]..root.stats_first[0] + root.stats_count[0] as usize]
+                .iter()
+                .max_by_key(|s| s.visits)
+                .unwrap()
+                .move_
+        }
+
+        fn init(&mut self) {
+            // Re-initialize the node tree
+            self.arr[0] = Default::default();
+            self.len = 1;
+            self.stats_len = 0;
+            self.children.clear();
+            self.nb_simulations = 0;
+        }
+
+        fn select(&mut self, state: &mut game::State, cache: &game::Cache) -> usize {
+            /*
+                Go down the tree, each player choosing its move with UCB, until a joint move that was never played.
+                Its node is created and returned. A node is expanded (its moves are listed) the second time it's reached.
+                On the way update the state.
+            */
+            let mut node_idx = 0;
+
+            while !game::is_terminal(state) {
+                if !self.arr[node_idx].expanded {
+                    self.expand(node_idx, state, cache);
+                }
+
+                let joint_move = [self.select_move(node_idx, 0), self.select_move(node_idx, 1)];
+                let node = &self.arr[node_idx];
+                let moves = [0, 1].map(|p| match node.stats_count[p] {
+                    0 => None,
+                    _ => Some(self.stats[node.stats_first[p] + joint_move[p] as usize].move_),
+                });
+                game::update_state(state, moves, cache);
+
+                match self.find_child(node_idx, joint_move) {
+                    Some(child_idx) => node_idx = child_idx,
+                    None => return self.create_child(node_idx, joint_move),
+                }
+            }
+
+            node_idx
+        }
+
+        fn select_move(&self, node_idx: usize, player: usize) -> u16 {
+            /* Index of the move of [player] with the largest UCB. The moves were shuffled, so the first
+            move with ucb=INF is a random one */
+            let node = &self.arr[node_idx];
+            let moves = &self.stats[node.stats_first[player]
+                ..node.stats_first[player] + node.stats_count[player] as usize];
+
+            let mut max_ucb: f32 = -f32::INFINITY;
+            let mut max_ucb_move_idx = 0;
+            for (i, m) in moves.iter().enumerate() {
+                let ucb = DUCT::ucb(node.visits, m.score, m.visits, self.exploration);
+                if ucb == f32::INFINITY {
+                    return i as u16;
+                } else if ucb > max_ucb {
+                    max_ucb = ucb;
+                    max_ucb_move_idx = i;
+                }
+            }
+
+            max_ucb_move_idx as u16
+        }
+
+        fn ucb(parent_visit: u32, score: f32, visits: u32, exploration: f32) -> f32 {
+            match visits {
+                0 => f32::INFINITY,
+                _ => {
+                    (score / visits as f32)
+                        + exploration * ((parent_visit as f32).ln() / (visits as f32)).sqrt()
+                }
+            }
+        }
+
+        fn expand(&mut self, node_idx: usize, state: &game::State, cache: &game::Cache) {
+            /* List the moves of each awake player, in a random order */
+            for p in 0..2 {
+                if state.players[p].is_asleep {
+                    self.arr[node_idx].stats_count[p] = 0;
+                    continue;
+                }
+
+                let mut valid_moves = game::valid_moves(state, p as u8, cache);
+                valid_moves.slice_mut().shuffle(&mut self.rng);
+
+                self.arr[node_idx].stats_first[p] = self.stats_len;
+                self.arr[node_idx].stats_count[p] = valid_moves.len() as u16;
+                for m in valid_moves.slice() {
+                    self.stats[self.stats_len] = MoveStats {
+                        move_: *m,
+                        visits: 0,
+                        score: 0.0,
+                    };
+                    self.stats_len += 1;
+                }
+            }
+
+            self.arr[node_idx].expanded = true;
+        }
+
+        fn find_child(&self, node_idx: usize, joint_move: [u16; 2]) -> Option<usize> {
+            self.children.get(&(node_idx, joint_move)).copied()
+        }
+
+        fn create_child(&mut self, parent: usize, joint_move: [u16; 2]) -> usize {
+            let child_idx = self.len;
+            self.arr[child_idx] = Node {
+                joint_move,
+                parent: Some(parent),
+                ..Default::default()
+            };
+            self.children.insert((parent, joint_move), child_idx);
+            self.len += 1;
+
+            child_idx
+        }
+
+        fn simulate(&mut self, state: &mut game::State, cache: &game::Cache) -> game::GameScore {
+            // Simulate the game until the end, both players choosing a random move each turn
+            while !game::is_terminal(state) {
+                let moves = [0, 1].map(|p| match state.players[p as usize].is_asleep {
+                    true => None,
+                    false => Some(game::random_valid_move(state, p, cache, &mut self.rng)),
+                });
+
+                game::update_state(state, moves, cache);
+            }
+
+            // Get the result
+            game::get_scores(state)
+        }
+
+        fn backpropagate(&mut self, leaf_node_idx: usize, score: game::GameScore) {
+            /* Each node on the path gets a visit, and the moves that lead to it get the score of their player */
+            let mut node_idx = leaf_node_idx;
+            loop {
+                self.arr[node_idx].visits += 1;
+
+                let node = self.arr[node_idx];
+                match node.parent {
+                    Some(parent_idx) => {
+                        let parent = &self.arr[parent_idx];
+                        for (p, p_score) in score.iter().take(2).enumerate() {
+                            if parent.stats_count[p] > 0 {
+                                let move_stats = &mut self.stats
+                                    [parent.stats_first[p] + node.joint_move[p] as usize];
+                                move_stats.visits += 1;
+                                move_stats.score += p_score;
+                            }
+                        }
+                        node_idx = parent_idx;
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+}
+
+pub fn play(
+    ctr_rcv: Receiver<bool>,
+    msg_rcv: Receiver<String>,
+    msg_snd: Sender<(String, Option<std::collections::HashMap<String, String>>)>,
+    params: Option<Vec<String>>,
+) {
+    let mut soil_richness: [game::SoilRichness; 37] = [SoilRichness::UNUSABLE; 37];
+
+    let input_line = msg_rcv.recv().unwrap();
+    let number_of_cells = parse_input!(input_line, i32); // 37
+    for _ in 0..number_of_cells as usize {
+        let input_line = msg_rcv.recv().unwrap();
+        let inputs = input_line.split(' ').collect::<Vec<_>>();
+        let index = parse_input!(inputs[0], usize); // 0 is the center cell, the next cells spiral outwards
+        let richness = parse_input!(inputs[1], i32); // 0 if the cell is unusable, 1-3 for usable cells
+                                                     // The neighbouring cells are known by the cache
+
+        soil_richness[index] = match richness {
+            0 => SoilRichness::UNUSABLE,
+            1 => SoilRichness::LOW_QUALITY,
+            2 => SoilRichness::MEDIUM_QUALITY,
+            3 => SoilRichness::HIGH_QUALITY,
+            _ => panic!(),
+        }
+    }
+
+    /* State variables that have to be maintained as they are not sent by the game */
+    let mut prev_day = -1;
+    let mut turn_during_day = 0;
+
+    let cache = game::Cache::new(soil_richness);
+    /* Named parameters "name=value" given by the simulator, e.g. to tune them */
+    let mut exploration = duct::EXPLORATION;
+    let mut time_limit_ms = duct::TIME_LIMIT_MS;
+    for param in params.iter().flatten() {
+        match param.split_once('=') {
+            Some(("exploration", value)) => exploration = parse_input!(value, f32),
+            Some(("time_limit_ms", value)) => time_limit_ms = parse_input!(value, u128),
+            _ => panic!("Unknown parameter {}", param),
+        }
+    }
+
+    let mut duct: duct::DUCT = duct::new(exploration, time_limit_ms);
+
+    // game loop
+    while ctr_rcv.recv().unwrap() {
+        let mut board: [Option<game::Cell>; 37] = [None; 37];
+        let mut players: [game::Player; 2] = [game::Player::default(); 2];
+
+        let input_line = msg_rcv.recv().unwrap();
+        let day = parse_input!(input_line, u8); // the game lasts 24 days: 0-23
+        let input_line = msg_rcv.recv().unwrap();
+        let nutrients = parse_input!(input_line, u8); // the base score you gain from the next COMPLETE action
+        let input_line = msg_rcv.recv().unwrap();
+        let inputs = input_line.split(' ').collect::<Vec<_>>();
+        let sun = parse_input!(inputs[0], u32); // your sun points
+        let score = parse_input!(inputs[1], u32); // your current score
+        let input_line = msg_rcv.recv().unwrap();
+        let inputs = input_line.split(' ').collect::<Vec<_>>();
+        let opp_sun = parse_input!(inputs[0], u32); // opponent's sun points
+        let opp_score = parse_input!(inputs[1], u32); // opponent's score
+        let opp_is_waiting = parse_input!(inputs[2], i32); // whether your opponent is asleep until the next day
+        let input_line = msg_rcv.recv().unwrap();
+
+        players[0].sun = sun;
+        players[0].score = score;
+
+        players[1].sun = opp_sun;
+        players[1].score = opp_score;
+        players[1].is_asleep = match opp_is_waiting {
+            1 => true,
+            0 => false,
+            _ => panic!(),
+        };
+
+        let number_of_trees = parse_input!(input_line, i32); // the current amount of trees
+        for _ in 0..number_of_trees as usize {
+            let input_line = msg_rcv.recv().unwrap();
+            let inputs = input_line.split(' ').collect::<Vec<_>>();
+            let cell_index = parse_input!(inputs[0], usize); // location of this tree
+            let size = parse_input!(inputs[1], u8); // size of this tree: 0-3
+            let is_mine = parse_input!(inputs[2], i32); // 1 if this is your tree
+            let is_dormant = parse_input!(inputs[3], i32); // 1 if this tree is dormant
+
+            board[cell_index] = Some(game::Cell {
+                player: match is_mine {
+                    1 => 0,
+                    0 => 1,
+                    _ => panic!(),
+                },
+                tree: match size {
+                    0 => game::Tree::SEED,
+                    1 => game::Tree::SMALL_TREE,
+                    2 => game::Tree::MEDIUM_TREE,
+                    3 => game::Tree::LARGE_TREE,
+                    _ => panic!(),
+                },
+                is_dormant: match is_dormant {
+                    1 => true,
+                    0 => false,
+                    _ => panic!(),
+                },
+            });
+        }
+
+        for c in board.iter().flatten() {
+            match c.tree {
+                game::Tree::SEED => players[c.player as usize].seed_count += 1,
+                game::Tree::SMALL_TREE => players[c.player as usize].small_tree_count += 1,
+                game::Tree::MEDIUM_TREE => players[c.player as usize].medium_tree_count += 1,
+                game::Tree::LARGE_TREE => players[c.player as usize].large_tree_count += 1,
+            }
+        }
+
+        let input_line = msg_rcv.recv().unwrap();
+        let number_of_possible_actions = parse_input!(input_line, i32); // all legal actions
+        for _ in 0..number_of_possible_actions as usize {
+            // The valid moves are computed by the search
+            msg_rcv.recv().unwrap();
+        }
+
+        /* Create State from information above */
+
+        if prev_day < day as i8 {
+            turn_during_day = 0;
+        } else {
+            turn_during_day += 1;
+        }
+        prev_day = day as i8;
+
+        let state = game::State {
+            board,
+            players,
+            nutrient: nutrients,
+            day,
+            turn_during_day,
+
+            active: true,
+            winners: None,
+        };
+
+        /* Extract best move */
+        let best_move = duct.best_move(&state, &cache);
+
+        msg_snd.send((best_move.to_string(), None)).unwrap();
+    }
+}
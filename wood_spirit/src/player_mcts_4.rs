@@ -693,7 +693,8 @@ mod mcts {
     use std::time::Instant;
 
     const MAX_NODE_COUNT: usize = 300_000;
-    const TIME_LIMIT_MS: u128 = 98;
+    pub const TIME_LIMIT_MS: u128 = 98;
+    pub const EXPLORATION: f32 = 0.41;
 
     #[derive(Clone, Copy)]
     struct Node {
@@ -730,13 +731,19 @@ mod mcts {
         arr: Vec<Node>,
         len: usize,
         nb_simulations: u32,
+
+        exploration: f32,
+        time_limit_ms: u128,
     }
 
-    pub fn new() -> MCTS {
+    pub fn new(exploration: f32, time_limit_ms: u128) -> MCTS {
         MCTS {
             arr: vec![Default::default(); MAX_NODE_COUNT],
             len: 0,
             nb_simulations: 0,
+
+            exploration,
+            time_limit_ms,
         }
     }
 
@@ -757,7 +764,7 @@ mod mcts {
             let start = Instant::now();
             self.init(player);
 
-            while (start.elapsed().as_millis() < TIME_LIMIT_MS)
+            while (start.elapsed().as_millis() < self.time_limit_ms)
                 & (self.len < MAX_NODE_COUNT - game::MAX_VALID_MOVES)
             {
                 let mut state = root_state.clone();
@@ -842,7 +849,8 @@ mod mcts {
                     node.child_first.unwrap()..node.child_first.unwrap() + node.child_count as usize
                 {
                     let child = &self.arr[c];
-                    let child_ucb = MCTS::ucb(node.visits, child.score, child.visits);
+                    let child_ucb =
+                        MCTS::ucb(node.visits, child.score, child.visits, self.exploration);
                     if child_ucb == f32::INFINITY {
                         //TODO: I'm choosing the first child with ucb=INF. Try to choose a bit more randomly
                         max_ucb_node_idx = c;
@@ -866,12 +874,12 @@ mod mcts {
             node_idx
         }
 
-        fn ucb(parent_visit: u32, score: f32, visits: u32) -> f32 {
+        fn ucb(parent_visit: u32, score: f32, visits: u32, exploration: f32) -> f32 {
             match visits {
                 0 => f32::INFINITY,
                 _ => {
                     (score / visits as f32)
-                        + 0.41 * ((parent_visit as f32).ln() / (visits as f32)).sqrt()
+                        + exploration * ((parent_visit as f32).ln() / (visits as f32)).sqrt()
                 }
             }
         }
@@ -998,7 +1006,18 @@ pub fn play(
     let mut turn_during_day = 0;
 
     let cache = game::Cache::new(soil_richness);
-    let mut mcts: mcts::MCTS = mcts::new();
+    /* Named parameters "name=value" given by the simulator, e.g. to tune them */
+    let mut exploration = mcts::EXPLORATION;
+    let mut time_limit_ms = mcts::TIME_LIMIT_MS;
+    for param in params.iter().flatten() {
+        match param.split_once('=') {
+            Some(("exploration", value)) => exploration = parse_input!(value, f32),
+            Some(("time_limit_ms", value)) => time_limit_ms = parse_input!(value, u128),
+            _ => panic!("Unknown parameter {}", param),
+        }
+    }
+
+    let mut mcts: mcts::MCTS = mcts::new(exploration, time_limit_ms);
 
     // game loop
     while ctr_rcv.recv().unwrap() == true {